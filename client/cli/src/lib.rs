@@ -18,6 +18,7 @@
 
 #![warn(missing_docs)]
 
+use cumulus_client_pov_recovery::PoVRecoveryConfig;
 use sc_cli;
 use sc_service::{
 	config::{PrometheusConfig, TelemetryEndpoints},
@@ -27,8 +28,11 @@ use std::{
 	fs,
 	io::{self, Write},
 	net::SocketAddr,
+	str::FromStr,
+	time::Duration,
 };
 use structopt::StructOpt;
+use url::Url;
 
 /// The `purge-chain` command used to remove the whole chain: the parachain and the relay chain.
 #[derive(Debug, StructOpt)]
@@ -131,6 +135,106 @@ pub struct RunCmd {
 	/// Note that this is the same as running with `--validator`.
 	#[structopt(long, conflicts_with = "validator")]
 	pub collator: bool,
+
+	/// Run the node without a relay chain, authoring blocks on top of a simulated one.
+	///
+	/// With `instant` a block is sealed as soon as a transaction enters the pool, with `manual`
+	/// blocks are only sealed when requested over the `engine_createBlock` RPC.
+	/// `--dev` implies `--manual-seal instant`. Meant for local development only.
+	#[structopt(long, value_name = "SEALING", possible_values = &["instant", "manual"])]
+	pub manual_seal: Option<Sealing>,
+
+	/// Run a full node that follows the relay chain through the RPC interface of the relay chain
+	/// node at this WebSocket URL, instead of running an embedded relay chain node.
+	///
+	/// This is a full node only feature. Collating needs the overseer of an embedded relay chain
+	/// node, which isn't available over RPC.
+	#[structopt(long, conflicts_with_all = &["collator", "validator"])]
+	pub full_node_relay_chain_rpc_url: Option<Url>,
+
+	/// PoV recovery parameters of full nodes.
+	#[structopt(flatten)]
+	pub pov_recovery: PoVRecoveryParams,
+}
+
+/// Parameters of the PoV recovery on full nodes.
+#[derive(Debug, Clone, StructOpt)]
+pub struct PoVRecoveryParams {
+	/// Recover the blocks of included candidates that were never announced by their collator.
+	///
+	/// Collators always do this, full nodes only with this flag. Requires an embedded relay chain
+	/// node.
+	#[structopt(long = "pov-recovery", conflicts_with = "full-node-relay-chain-rpc-url")]
+	pub enabled: bool,
+
+	/// The minimum time in milliseconds to wait before recovering a candidate.
+	///
+	/// Full nodes should wait longer than collators, so they only recover blocks the collators
+	/// failed to recover.
+	#[structopt(long = "pov-recovery-min-delay", value_name = "MS", default_value = "6000")]
+	pub min_delay: u64,
+
+	/// The maximum time in milliseconds to wait before recovering a candidate.
+	#[structopt(long = "pov-recovery-max-delay", value_name = "MS", default_value = "24000")]
+	pub max_delay: u64,
+
+	/// The maximum number of candidates recovered at the same time.
+	#[structopt(long = "pov-recovery-max-parallel", value_name = "COUNT", default_value = "2")]
+	pub max_parallel_recoveries: usize,
+
+	/// The minimum time in milliseconds between starting two recoveries.
+	#[structopt(long = "pov-recovery-request-interval", value_name = "MS", default_value = "1000")]
+	pub min_request_interval: u64,
+
+	/// How often a failed recovery is retried before giving up on the candidate.
+	#[structopt(long = "pov-recovery-max-retries", value_name = "COUNT", default_value = "3")]
+	pub max_retries: u32,
+
+	/// The time in milliseconds to wait before retrying a failed recovery.
+	///
+	/// Doubled for every further retry.
+	#[structopt(long = "pov-recovery-retry-backoff", value_name = "MS", default_value = "6000")]
+	pub retry_backoff: u64,
+
+	/// Only recover candidates of finalized relay chain blocks.
+	#[structopt(long = "pov-recovery-finalized-only")]
+	pub finalized_only: bool,
+}
+
+impl PoVRecoveryParams {
+	/// The configuration of the PoV recovery, `None` if it is disabled.
+	pub fn config(&self) -> Option<PoVRecoveryConfig> {
+		self.enabled.then(|| PoVRecoveryConfig {
+			min_delay: Duration::from_millis(self.min_delay),
+			max_delay: Duration::from_millis(self.max_delay),
+			max_parallel_recoveries: self.max_parallel_recoveries.max(1),
+			min_request_interval: Duration::from_millis(self.min_request_interval),
+			max_retries: self.max_retries,
+			retry_backoff: Duration::from_millis(self.retry_backoff),
+			finalized_only: self.finalized_only,
+		})
+	}
+}
+
+/// How blocks are sealed by a node running without a relay chain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal a block as soon as a transaction enters the pool.
+	Instant,
+	/// Seal a block only when requested over RPC.
+	Manual,
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Self::Instant),
+			"manual" => Ok(Self::Manual),
+			other => Err(format!("Unknown sealing `{}`, expected `instant` or `manual`", other)),
+		}
+	}
 }
 
 /// A non-redundant version of the `RunCmd` that sets the `validator` field when the
@@ -150,6 +254,12 @@ impl RunCmd {
 
 		NormalizedRunCmd { base: new_base }
 	}
+
+	/// The sealing to use when the node should run without a relay chain, if any.
+	pub fn sealing(&self) -> Option<Sealing> {
+		self.manual_seal
+			.or_else(|| self.base.shared_params.dev.then(|| Sealing::Instant))
+	}
 }
 
 impl sc_cli::CliConfiguration for NormalizedRunCmd {
@@ -0,0 +1,134 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for running a parachain node in development mode.
+//!
+//! In development mode there is no relay chain. Blocks are authored with manual seal and the
+//! parachain inherent is mocked with [`MockValidationDataInherentDataProvider`]. Downward and
+//! horizontal messages can be injected into the next block through the [`MessageInjector`], which
+//! is exposed over RPC by [`rpc::DevRpc`].
+
+#![warn(missing_docs)]
+
+use cumulus_primitives_core::ParaId;
+use cumulus_primitives_parachain_inherent::{
+	MockValidationDataInherentDataProvider, MockXcmConfig,
+};
+use parking_lot::Mutex;
+use sc_client_api::{Backend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+use std::{mem, sync::Arc};
+
+pub mod rpc;
+
+/// The relay chain block number the simulated relay chain starts at.
+pub const RELAY_OFFSET: u32 = 1000;
+
+/// The number of simulated relay chain blocks between two parachain blocks.
+pub const RELAY_BLOCKS_PER_PARA_BLOCK: u32 = 2;
+
+/// Messages waiting to be included into the next block.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InjectedMessages {
+	/// Downward messages from the relay chain.
+	pub downward: Vec<Vec<u8>>,
+	/// Horizontal messages with the parachain that sent them.
+	pub horizontal: Vec<(ParaId, Vec<u8>)>,
+}
+
+/// Shared handle for injecting messages into the blocks of a development node.
+///
+/// Messages are queued until the next block is authored, which takes all of them.
+#[derive(Debug, Default, Clone)]
+pub struct MessageInjector {
+	pending: Arc<Mutex<InjectedMessages>>,
+}
+
+impl MessageInjector {
+	/// Create a new, empty `MessageInjector`.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Queue a downward message for the next block.
+	pub fn inject_downward(&self, message: Vec<u8>) {
+		self.pending.lock().downward.push(message);
+	}
+
+	/// Queue a horizontal message sent by `sender` for the next block.
+	pub fn inject_horizontal(&self, sender: ParaId, message: Vec<u8>) {
+		self.pending.lock().horizontal.push((sender, message));
+	}
+
+	/// Take all queued messages, leaving the queue empty.
+	pub fn take(&self) -> InjectedMessages {
+		mem::take(&mut *self.pending.lock())
+	}
+}
+
+/// Create the mocked parachain inherent for the child of `parent`.
+///
+/// The MQC heads are read from the storage of `parent`, so the given `messages` are accepted by
+/// the runtime as if they were sent on a real relay chain.
+pub fn mock_parachain_inherent<B, BE, C>(
+	client: &C,
+	parent: B::Hash,
+	para_id: ParaId,
+	messages: InjectedMessages,
+) -> sp_blockchain::Result<MockValidationDataInherentDataProvider>
+where
+	B: BlockT,
+	BE: Backend<B>,
+	C: HeaderBackend<B> + StorageProvider<B, BE>,
+{
+	let current_para_block = client
+		.number(parent)?
+		.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{:?}", parent)))?;
+
+	Ok(MockValidationDataInherentDataProvider {
+		current_para_block: current_para_block.unique_saturated_into(),
+		relay_offset: RELAY_OFFSET,
+		relay_blocks_per_para_block: RELAY_BLOCKS_PER_PARA_BLOCK,
+		xcm_config: MockXcmConfig::new(client, parent, para_id, Default::default()),
+		raw_downward_messages: messages.downward,
+		raw_horizontal_messages: messages.horizontal,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn take_drains_the_queue() {
+		let injector = MessageInjector::new();
+		let handle = injector.clone();
+
+		handle.inject_downward(vec![1]);
+		handle.inject_horizontal(ParaId::from(2000), vec![2]);
+		handle.inject_downward(vec![3]);
+
+		assert_eq!(
+			injector.take(),
+			InjectedMessages {
+				downward: vec![vec![1], vec![3]],
+				horizontal: vec![(ParaId::from(2000), vec![2])],
+			},
+		);
+		assert_eq!(injector.take(), InjectedMessages::default());
+	}
+}
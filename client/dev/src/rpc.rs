@@ -0,0 +1,72 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for injecting messages into the blocks of a development node.
+
+use crate::MessageInjector;
+use cumulus_primitives_core::ParaId;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+
+/// Error code returned when a message can not be injected.
+const INVALID_MESSAGE: i64 = 1;
+
+/// Development mode RPC methods.
+#[rpc]
+pub trait DevApi {
+	/// Queue a downward message for inclusion in the next block.
+	#[rpc(name = "dev_injectDownwardMessage")]
+	fn inject_downward_message(&self, message: Bytes) -> Result<()>;
+
+	/// Queue a horizontal message from the parachain `sender` for inclusion in the next block.
+	#[rpc(name = "dev_injectHorizontalMessage")]
+	fn inject_horizontal_message(&self, sender: u32, message: Bytes) -> Result<()>;
+}
+
+/// Implements the [`DevApi`] RPC trait on top of a [`MessageInjector`].
+pub struct DevRpc {
+	para_id: ParaId,
+	injector: MessageInjector,
+}
+
+impl DevRpc {
+	/// Create new `DevRpc` for the parachain `para_id`, feeding the given `injector`.
+	pub fn new(para_id: ParaId, injector: MessageInjector) -> Self {
+		Self { para_id, injector }
+	}
+}
+
+impl DevApi for DevRpc {
+	fn inject_downward_message(&self, message: Bytes) -> Result<()> {
+		self.injector.inject_downward(message.0);
+		Ok(())
+	}
+
+	fn inject_horizontal_message(&self, sender: u32, message: Bytes) -> Result<()> {
+		let sender = ParaId::from(sender);
+		if sender == self.para_id {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(INVALID_MESSAGE),
+				message: "A parachain can not send horizontal messages to itself.".into(),
+				data: None,
+			})
+		}
+
+		self.injector.inject_horizontal(sender, message.0);
+		Ok(())
+	}
+}
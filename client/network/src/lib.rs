@@ -43,16 +43,23 @@ use futures::{
 	future::{ready, FutureExt},
 	Future,
 };
+use substrate_prometheus_endpoint::{PrometheusError, Registry};
 
 use std::{convert::TryFrom, fmt, marker::PhantomData, pin::Pin, sync::Arc};
 
+mod metrics;
 #[cfg(test)]
 mod tests;
 
+use metrics::Metrics;
+
 const LOG_TARGET: &str = "sync::cumulus";
 
 type BoxedError = Box<dyn std::error::Error + Send>;
 
+/// The result of a block announcement validation, or the reason it failed with an error.
+type AnnouncementResult = Result<BlockAnnounceResult, (BlockAnnounceResult, BoxedError)>;
+
 #[derive(Debug)]
 struct BlockAnnounceError(String);
 impl std::error::Error for BlockAnnounceError {}
@@ -96,14 +103,14 @@ impl BlockAnnounceData {
 	/// Validate that the receipt, statement and announced header match.
 	///
 	/// This will not check the signature, for this you should use [`BlockAnnounceData::check_signature`].
-	fn validate(&self, encoded_header: Vec<u8>) -> Result<(), Validation> {
+	fn validate(&self, encoded_header: Vec<u8>) -> Result<(), BlockAnnounceResult> {
 		let candidate_hash = if let CompactStatement::Seconded(h) =
 			self.statement.unchecked_payload()
 		{
 			h
 		} else {
 			tracing::debug!(target: LOG_TARGET, "`CompactStatement` isn't the candidate variant!",);
-			return Err(Validation::Failure { disconnect: true })
+			return Err(BlockAnnounceResult::InvalidStatement)
 		};
 
 		if *candidate_hash != self.receipt.hash() {
@@ -111,7 +118,7 @@ impl BlockAnnounceData {
 				target: LOG_TARGET,
 				"Receipt candidate hash doesn't match candidate hash in statement",
 			);
-			return Err(Validation::Failure { disconnect: true })
+			return Err(BlockAnnounceResult::InvalidStatement)
 		}
 
 		if HeadData(encoded_header).hash() != self.receipt.descriptor.para_head {
@@ -119,7 +126,7 @@ impl BlockAnnounceData {
 				target: LOG_TARGET,
 				"Receipt para head hash doesn't match the hash of the header in the block announcement",
 			);
-			return Err(Validation::Failure { disconnect: true })
+			return Err(BlockAnnounceResult::InvalidStatement)
 		}
 
 		Ok(())
@@ -131,7 +138,7 @@ impl BlockAnnounceData {
 	fn check_signature<RCInterface>(
 		self,
 		relay_chain_client: &RCInterface,
-	) -> Result<Validation, BlockAnnounceError>
+	) -> Result<BlockAnnounceResult, BlockAnnounceError>
 	where
 		RCInterface: RelayChainInterface + 'static,
 	{
@@ -159,7 +166,7 @@ impl BlockAnnounceData {
 					"Block announcement justification signer is a validator index out of bound",
 				);
 
-				return Ok(Validation::Failure { disconnect: true })
+				return Ok(BlockAnnounceResult::UnknownSigner)
 			},
 		};
 
@@ -170,10 +177,10 @@ impl BlockAnnounceData {
 				"Block announcement justification signature is invalid.",
 			);
 
-			return Ok(Validation::Failure { disconnect: true })
+			return Ok(BlockAnnounceResult::BadSignature)
 		}
 
-		Ok(Validation::Success { is_new_best: true })
+		Ok(BlockAnnounceResult::ValidJustification)
 	}
 }
 
@@ -195,6 +202,123 @@ impl TryFrom<&'_ CollationSecondedSignal> for BlockAnnounceData {
 	}
 }
 
+/// The result of validating a block announcement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockAnnounceResult {
+	/// The node is major syncing and accepts announcements without checking them.
+	MajorSyncing,
+	/// The announced block is the best block of the parachain included in the relay chain.
+	MatchesBest,
+	/// The announced block is the latest block of the parachain backed in the relay chain.
+	MatchesBacked,
+	/// The announced block is below the best block and probably comes from a syncing node.
+	BelowBest,
+	/// The announcement has a valid justification.
+	ValidJustification,
+	/// The announcement is at the tip of the chain, but has no justification.
+	MissingJustification,
+	/// The attached [`BlockAnnounceData`] can not be decoded.
+	InvalidData,
+	/// The statement isn't a seconded statement or doesn't match the receipt or the header.
+	InvalidStatement,
+	/// The statement is not signed by a relay chain validator.
+	UnknownSigner,
+	/// The signature of the statement is invalid.
+	BadSignature,
+	/// The relay parent of the candidate could not be found.
+	UnknownRelayParent,
+	/// The relay chain could not be queried.
+	RelayChainError,
+}
+
+impl BlockAnnounceResult {
+	/// The name of this result, as used by the metrics.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::MajorSyncing => "major_syncing",
+			Self::MatchesBest => "matches_best",
+			Self::MatchesBacked => "matches_backed",
+			Self::BelowBest => "below_best",
+			Self::ValidJustification => "valid_justification",
+			Self::MissingJustification => "missing_justification",
+			Self::InvalidData => "invalid_data",
+			Self::InvalidStatement => "invalid_statement",
+			Self::UnknownSigner => "unknown_signer",
+			Self::BadSignature => "bad_signature",
+			Self::UnknownRelayParent => "unknown_relay_parent",
+			Self::RelayChainError => "relay_chain_error",
+		}
+	}
+}
+
+/// Decides which invalid block announcements disconnect the peer that sent them.
+///
+/// This is a per announcement policy, not a policy for repeat offenders: a peer is disconnected
+/// on its first offense of a category that is enabled here. Substrate doesn't tell the validator
+/// which peer sent an announcement, so offenses can't be counted per peer. Supporting that needs
+/// Substrate to pass the sending peer to [`BlockAnnounceValidatorT::validate`].
+///
+/// Substrate lowers the reputation of a peer for every block announcement that fails the
+/// validation and eventually bans peers that keep doing so. Disconnecting a peer on top of that is
+/// a much harsher punishment, meant for announcements that a well-behaving peer never sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisconnectPolicy {
+	/// Disconnect peers announcing the tip of the chain without a justification.
+	pub missing_justification: bool,
+	/// Disconnect peers attaching data that can not be decoded.
+	pub invalid_data: bool,
+	/// Disconnect peers attaching a statement that doesn't match the announcement.
+	pub invalid_statement: bool,
+	/// Disconnect peers attaching a statement with an unknown signer or an invalid signature.
+	pub bad_signature: bool,
+}
+
+impl Default for DisconnectPolicy {
+	fn default() -> Self {
+		Self {
+			missing_justification: false,
+			invalid_data: false,
+			invalid_statement: true,
+			bad_signature: true,
+		}
+	}
+}
+
+impl DisconnectPolicy {
+	/// Returns if a peer sending an announcement with the given `result` should be disconnected.
+	pub fn disconnect(&self, result: BlockAnnounceResult) -> bool {
+		match result {
+			BlockAnnounceResult::MissingJustification => self.missing_justification,
+			BlockAnnounceResult::InvalidData => self.invalid_data,
+			BlockAnnounceResult::InvalidStatement => self.invalid_statement,
+			BlockAnnounceResult::UnknownSigner | BlockAnnounceResult::BadSignature =>
+				self.bad_signature,
+			_ => false,
+		}
+	}
+
+	/// Turn the result of a validation into the [`Validation`] reported to Substrate.
+	///
+	/// Errors are passed on, unless the peer is disconnected.
+	fn validation(&self, result: AnnouncementResult) -> Result<Validation, BoxedError> {
+		let result = match result {
+			Ok(result) => result,
+			Err((result, _)) if self.disconnect(result) =>
+				return Ok(Validation::Failure { disconnect: true }),
+			Err((_, error)) => return Err(error),
+		};
+
+		match result {
+			BlockAnnounceResult::MajorSyncing | BlockAnnounceResult::BelowBest =>
+				Ok(Validation::Success { is_new_best: false }),
+			BlockAnnounceResult::MatchesBest |
+			BlockAnnounceResult::MatchesBacked |
+			BlockAnnounceResult::ValidJustification => Ok(Validation::Success { is_new_best: true }),
+			failure => Ok(Validation::Failure { disconnect: self.disconnect(failure) }),
+		}
+	}
+}
+
 /// Parachain specific block announce validator.
 ///
 /// This block announce validator is required if the parachain is running
@@ -222,10 +346,15 @@ impl TryFrom<&'_ CollationSecondedSignal> for BlockAnnounceData {
 /// chain. If it is at the tip, it is required to provide a justification or otherwise we reject
 /// it. However, if the announcement is for a block below the tip the announcement is accepted
 /// as it probably comes from a node that is currently syncing the chain.
+///
+/// Which invalid announcements disconnect the peer is decided by the [`DisconnectPolicy`], for
+/// every announcement on its own.
 pub struct BlockAnnounceValidator<Block, RCInterface> {
 	phantom: PhantomData<Block>,
 	relay_chain_interface: RCInterface,
 	para_id: ParaId,
+	disconnect_policy: DisconnectPolicy,
+	metrics: Option<Metrics>,
 }
 
 impl<Block, RCInterface> BlockAnnounceValidator<Block, RCInterface>
@@ -238,8 +367,22 @@ where
 			phantom: Default::default(),
 			relay_chain_interface: relay_chain_interface.clone(),
 			para_id,
+			disconnect_policy: Default::default(),
+			metrics: None,
 		}
 	}
+
+	/// Use the given `disconnect_policy` instead of the default one.
+	pub fn with_disconnect_policy(mut self, disconnect_policy: DisconnectPolicy) -> Self {
+		self.disconnect_policy = disconnect_policy;
+		self
+	}
+
+	/// Register the metrics of the validator in the given `registry`.
+	pub fn with_metrics(mut self, registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		self.metrics = registry.map(Metrics::register).transpose()?;
+		Ok(self)
+	}
 }
 
 impl<Block: BlockT, RCInterface> BlockAnnounceValidator<Block, RCInterface>
@@ -285,7 +428,7 @@ where
 	fn handle_empty_block_announce_data(
 		&self,
 		header: Block::Header,
-	) -> impl Future<Output = Result<Validation, BoxedError>> {
+	) -> impl Future<Output = AnnouncementResult> {
 		let relay_chain_interface = self.relay_chain_interface.clone();
 		let para_id = self.para_id;
 
@@ -296,45 +439,46 @@ where
 			let block_number = header.number();
 
 			let best_head =
-				Self::included_block(&relay_chain_interface, &runtime_api_block_id, para_id)?;
+				Self::included_block(&relay_chain_interface, &runtime_api_block_id, para_id)
+					.map_err(|e| (BlockAnnounceResult::RelayChainError, e))?;
 			let known_best_number = best_head.number();
-			let backed_block =
-				|| Self::backed_block_hash(&relay_chain_interface, &runtime_api_block_id, para_id);
+			let backed_block = || {
+				Self::backed_block_hash(&relay_chain_interface, &runtime_api_block_id, para_id)
+					.map_err(|e| (BlockAnnounceResult::RelayChainError, e))
+			};
 
 			if best_head == header {
 				tracing::debug!(target: LOG_TARGET, "Announced block matches best block.",);
 
-				Ok(Validation::Success { is_new_best: true })
+				Ok(BlockAnnounceResult::MatchesBest)
 			} else if Some(HeadData(header.encode()).hash()) == backed_block()? {
 				tracing::debug!(target: LOG_TARGET, "Announced block matches latest backed block.",);
 
-				Ok(Validation::Success { is_new_best: true })
+				Ok(BlockAnnounceResult::MatchesBacked)
 			} else if block_number >= known_best_number {
 				tracing::debug!(
 					target: LOG_TARGET,
 					"Validation failed because a justification is needed if the block at the top of the chain."
 				);
 
-				Ok(Validation::Failure { disconnect: false })
+				Ok(BlockAnnounceResult::MissingJustification)
 			} else {
-				Ok(Validation::Success { is_new_best: false })
+				Ok(BlockAnnounceResult::BelowBest)
 			}
 		}
 	}
-}
 
-impl<Block: BlockT, RCInterface> BlockAnnounceValidatorT<Block>
-	for BlockAnnounceValidator<Block, RCInterface>
-where
-	RCInterface: RelayChainInterface + Clone + 'static,
-{
-	fn validate(
-		&mut self,
+	/// Validate the block announcement of `header` with the attached `data`.
+	fn validate_announcement(
+		&self,
 		header: &Block::Header,
 		mut data: &[u8],
-	) -> Pin<Box<dyn Future<Output = Result<Validation, BoxedError>> + Send>> {
+	) -> Pin<Box<dyn Future<Output = AnnouncementResult> + Send>>
+	where
+		RCInterface: 'static,
+	{
 		if self.relay_chain_interface.is_major_syncing() {
-			return ready(Ok(Validation::Success { is_new_best: false })).boxed()
+			return ready(Ok(BlockAnnounceResult::MajorSyncing)).boxed()
 		}
 
 		if data.is_empty() {
@@ -344,12 +488,13 @@ where
 		let block_announce_data = match BlockAnnounceData::decode_all(&mut data) {
 			Ok(r) => r,
 			Err(err) =>
-				return async move {
-					Err(Box::new(BlockAnnounceError(format!(
+				return ready(Err((
+					BlockAnnounceResult::InvalidData,
+					Box::new(BlockAnnounceError(format!(
 						"Can not decode the `BlockAnnounceData`: {:?}",
 						err
-					))) as Box<_>)
-				}
+					))) as Box<_>,
+				)))
 				.boxed(),
 		};
 
@@ -357,25 +502,55 @@ where
 		let header_encoded = header.encode();
 
 		async move {
-			if let Err(e) = block_announce_data.validate(header_encoded) {
-				return Ok(e)
+			if let Err(result) = block_announce_data.validate(header_encoded) {
+				return Ok(result)
 			}
 
 			let relay_parent = block_announce_data.receipt.descriptor.relay_parent;
 
-			relay_chain_interface
-				.wait_for_block(relay_parent)
-				.await
-				.map_err(|e| Box::new(BlockAnnounceError(e.to_string())) as Box<_>)?;
+			relay_chain_interface.wait_for_block(relay_parent).await.map_err(|e| {
+				(
+					BlockAnnounceResult::UnknownRelayParent,
+					Box::new(BlockAnnounceError(e.to_string())) as Box<_>,
+				)
+			})?;
 
 			block_announce_data
 				.check_signature(&relay_chain_interface)
-				.map_err(|e| Box::new(e) as Box<_>)
+				.map_err(|e| (BlockAnnounceResult::RelayChainError, Box::new(e) as Box<_>))
 		}
 		.boxed()
 	}
 }
 
+impl<Block: BlockT, RCInterface> BlockAnnounceValidatorT<Block>
+	for BlockAnnounceValidator<Block, RCInterface>
+where
+	RCInterface: RelayChainInterface + Clone + 'static,
+{
+	fn validate(
+		&mut self,
+		header: &Block::Header,
+		data: &[u8],
+	) -> Pin<Box<dyn Future<Output = Result<Validation, BoxedError>> + Send>> {
+		let disconnect_policy = self.disconnect_policy;
+		let metrics = self.metrics.clone();
+
+		self.validate_announcement(header, data)
+			.map(move |result| {
+				if let Some(metrics) = metrics {
+					let result = match &result {
+						Ok(result) | Err((result, _)) => result,
+					};
+					metrics.validations.with_label_values(&[result.as_str()]).inc();
+				}
+
+				disconnect_policy.validation(result)
+			})
+			.boxed()
+	}
+}
+
 /// Wait before announcing a block that a candidate message has been received for this block, then
 /// add this message as justification for the block announcement.
 ///
@@ -0,0 +1,42 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use substrate_prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
+
+/// Prometheus metrics of the [`BlockAnnounceValidator`](crate::BlockAnnounceValidator).
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	/// Validated block announcements by [`BlockAnnounceResult`](crate::BlockAnnounceResult).
+	pub validations: CounterVec<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics in the given `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			validations: register(
+				CounterVec::new(
+					Opts::new(
+						"cumulus_block_announce_validations",
+						"Number of validated block announcements by result.",
+					),
+					&["result"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
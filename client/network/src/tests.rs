@@ -24,9 +24,9 @@ use parking_lot::Mutex;
 use polkadot_node_primitives::{SignedFullStatement, Statement};
 use polkadot_primitives::v1::{
 	Block as PBlock, CandidateCommitments, CandidateDescriptor, CollatorPair,
-	CommittedCandidateReceipt, Hash as PHash, HeadData, Id as ParaId, InboundDownwardMessage,
-	InboundHrmpMessage, OccupiedCoreAssumption, PersistedValidationData, SessionIndex,
-	SigningContext, ValidationCodeHash, ValidatorId,
+	CommittedCandidateReceipt, Hash as PHash, HeadData, Header as PHeader, Id as ParaId,
+	InboundDownwardMessage, InboundHrmpMessage, OccupiedCoreAssumption, PersistedValidationData,
+	SessionIndex, SigningContext, ValidationCodeHash, ValidatorId,
 };
 use polkadot_service::Handle;
 use polkadot_test_client::{
@@ -91,6 +91,13 @@ impl RelayChainInterface for DummyRelayChainInterface {
 		self.relay_backend.blockchain().status(block_id)
 	}
 
+	fn header(
+		&self,
+		block_id: cumulus_primitives_core::relay_chain::BlockId,
+	) -> Result<Option<PHeader>, sp_blockchain::Error> {
+		self.relay_backend.blockchain().header(block_id)
+	}
+
 	fn best_block_hash(&self) -> PHash {
 		self.relay_backend.blockchain().info().best_hash
 	}
@@ -507,6 +514,47 @@ fn check_header_match_candidate_receipt_header() {
 	assert_eq!(Validation::Failure { disconnect: true }, res.unwrap());
 }
 
+#[test]
+fn disconnect_policy_decides_about_disconnecting() {
+	let policy = DisconnectPolicy {
+		missing_justification: true,
+		invalid_data: true,
+		invalid_statement: false,
+		bad_signature: false,
+	};
+	let (validator, api) = make_validator_and_api();
+	let mut validator = validator.with_disconnect_policy(policy);
+
+	let header = Header { number: 1, state_root: Hash::random(), ..default_header() };
+	let res = block_on(validator.validate(&header, &[]));
+	assert_eq!(Validation::Failure { disconnect: true }, res.unwrap());
+
+	let res = block_on(validator.validate(&default_header(), &[0x42]));
+	assert_eq!(Validation::Failure { disconnect: true }, res.unwrap());
+
+	let (signal, header) = block_on(make_gossip_message_and_header_using_genesis(api, 1));
+	let data = BlockAnnounceData::try_from(&signal).unwrap().encode();
+	let res = block_on(validator.validate(&header, &data));
+	assert_eq!(Validation::Failure { disconnect: false }, res.unwrap());
+}
+
+#[test]
+fn validation_results_are_counted() {
+	let registry = Registry::new();
+	let mut validator = make_validator_and_api().0.with_metrics(Some(&registry)).unwrap();
+
+	block_on(validator.validate(&default_header(), &[])).unwrap();
+	block_on(validator.validate(&default_header(), &[])).unwrap();
+	block_on(validator.validate(&default_header(), &[0x42])).unwrap_err();
+
+	let validations = &validator.metrics.as_ref().unwrap().validations;
+	let count =
+		|result: BlockAnnounceResult| validations.with_label_values(&[result.as_str()]).get();
+	assert_eq!(count(BlockAnnounceResult::MatchesBest), 2);
+	assert_eq!(count(BlockAnnounceResult::InvalidData), 1);
+	assert_eq!(count(BlockAnnounceResult::BadSignature), 0);
+}
+
 /// Test that ensures that we postpone the block announce verification until
 /// a relay chain block is imported. This is important for when we receive a
 /// block announcement before we have imported the associated relay chain block
@@ -95,6 +95,11 @@ impl<Block: BlockT> ActiveCandidateRecovery<Block> {
 		self.candidates.contains(candidate)
 	}
 
+	/// Returns the number of recoveries that are currently being executed.
+	pub fn active_recoveries(&self) -> usize {
+		self.candidates.len()
+	}
+
 	/// Waits for the next recovery.
 	///
 	/// If the returned [`AvailableData`] is `None`, it means that the recovery failed.
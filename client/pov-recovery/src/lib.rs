@@ -24,20 +24,26 @@
 //! to share it with the rest of the network or maybe a collator went down before it could distribute
 //! the block in the network. When something like this happens we can use the PoV recovery algorithm
 //! implemented in this crate to recover a PoV and to propagate it with the rest of the network. This
-//! protocol is only executed by the collators, to not overwhelm the relay chain validators.
+//! protocol is always executed by the collators. Full nodes can opt into it as well, but to not
+//! overwhelm the relay chain validators they wait longer and recover fewer candidates at once (see
+//! [`PoVRecoveryConfig`]).
 //!
 //! It works in the following way:
 //!
 //! 1. For every included relay chain block we note the backed candidate of our parachain. If the
 //!    block belonging to the PoV is already known, we do nothing. Otherwise we start
-//!    a timer that waits a random time between 0..relay_chain_slot_length before starting to recover
-//!    the PoV.
+//!    a timer that waits a random time between [`PoVRecoveryConfig::min_delay`] and
+//!    [`PoVRecoveryConfig::max_delay`] before starting to recover the PoV. With
+//!    [`PoVRecoveryConfig::finalized_only`] only the candidates of finalized relay chain blocks are
+//!    noted.
 //!
 //! 2. If between starting and firing the timer the block is imported, we skip the recovery of the
 //!    PoV.
 //!
 //! 3. If the timer fired we recover the PoV using the relay chain PoV recovery protocol. After it
-//!    is recovered, we restore the block and import it.
+//!    is recovered, we restore the block and import it. The number of recoveries running at the
+//!    same time and the rate at which they are started are limited by the [`PoVRecoveryConfig`].
+//!    Failed recoveries are retried with an exponential backoff.
 //!
 //! If we need to recover multiple PoV blocks (which should hopefully not happen in real life), we
 //! make sure that the blocks are imported in the correct order.
@@ -53,34 +59,166 @@ use sp_runtime::{
 use polkadot_node_primitives::{AvailableData, POV_BOMB_LIMIT};
 use polkadot_overseer::Handle as OverseerHandle;
 use polkadot_primitives::v1::{
-	CandidateReceipt, CommittedCandidateReceipt, Id as ParaId, SessionIndex,
+	BlockNumber as PBlockNumber, CandidateReceipt, CommittedCandidateReceipt, Hash as PHash,
+	Header as PHeader, Id as ParaId, SessionIndex,
 };
 
 use cumulus_primitives_core::ParachainBlockData;
 use cumulus_relay_chain_interface::RelayChainInterface;
 
 use codec::Decode;
-use futures::{select, stream::FuturesUnordered, Future, FutureExt, Stream, StreamExt};
+use futures::{
+	select,
+	stream::{self, FuturesUnordered},
+	Future, FutureExt, Stream, StreamExt,
+};
 use futures_timer::Delay;
 use rand::{thread_rng, Rng};
+use substrate_prometheus_endpoint::Registry;
 
 use std::{
 	collections::{HashMap, VecDeque},
 	pin::Pin,
 	sync::Arc,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 mod active_candidate_recovery;
+mod metrics;
 use active_candidate_recovery::ActiveCandidateRecovery;
+use metrics::Metrics;
 
 const LOG_TARGET: &str = "cumulus-pov-recovery";
 
+/// Configuration of the PoV recovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoVRecoveryConfig {
+	/// The minimum time to wait before starting to recover a candidate.
+	pub min_delay: Duration,
+	/// The maximum time to wait before starting to recover a candidate.
+	///
+	/// The actual delay is chosen randomly for every candidate, between `min_delay` and this
+	/// value.
+	pub max_delay: Duration,
+	/// The maximum number of candidates that are recovered at the same time.
+	pub max_parallel_recoveries: usize,
+	/// The minimum time between starting two recoveries.
+	pub min_request_interval: Duration,
+	/// How often a failed recovery is retried before giving up on the candidate.
+	pub max_retries: u32,
+	/// The time to wait before the first retry, doubled for every further retry.
+	pub retry_backoff: Duration,
+	/// Only recover candidates that are pending availability in finalized relay chain blocks.
+	pub finalized_only: bool,
+}
+
+impl PoVRecoveryConfig {
+	/// The configuration used by collators.
+	///
+	/// Recovery starts within one relay chain slot and a failed recovery is retried a few times
+	/// after another slot. The recovery is not limited otherwise.
+	pub fn collator(relay_chain_slot_duration: Duration) -> Self {
+		Self {
+			min_delay: Duration::ZERO,
+			max_delay: relay_chain_slot_duration,
+			max_parallel_recoveries: usize::MAX,
+			min_request_interval: Duration::ZERO,
+			max_retries: 3,
+			retry_backoff: relay_chain_slot_duration,
+			finalized_only: false,
+		}
+	}
+
+	/// The delay before retrying a recovery that failed `failed_attempts` times, `None` if the
+	/// recovery should not be retried anymore.
+	fn retry_delay(&self, failed_attempts: u32) -> Option<Duration> {
+		if failed_attempts > self.max_retries {
+			return None
+		}
+
+		let doublings = failed_attempts.saturating_sub(1).min(16);
+		Some(self.retry_backoff.saturating_mul(1 << doublings))
+	}
+}
+
+/// Whether a candidate that is due to be recovered may start its recovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+	/// The recovery can be started now.
+	Start,
+	/// Too many recoveries are running, the candidate waits for one of them to finish.
+	Queued,
+	/// The last recovery was started too recently, the candidate needs to wait this long.
+	Delay(Duration),
+}
+
+/// Limits the number of recoveries running at the same time and the rate at which they are
+/// started, as configured by [`PoVRecoveryConfig::max_parallel_recoveries`] and
+/// [`PoVRecoveryConfig::min_request_interval`].
+struct RecoveryLimiter<Hash> {
+	max_parallel_recoveries: usize,
+	min_request_interval: Duration,
+	/// Candidates that are due to be recovered, but wait for a running recovery to finish.
+	waiting_for_recovery_slot: VecDeque<Hash>,
+	/// When the last recovery was started.
+	last_recovery_started: Option<Instant>,
+}
+
+impl<Hash: PartialEq> RecoveryLimiter<Hash> {
+	fn new(config: &PoVRecoveryConfig) -> Self {
+		Self {
+			max_parallel_recoveries: config.max_parallel_recoveries,
+			min_request_interval: config.min_request_interval,
+			waiting_for_recovery_slot: VecDeque::new(),
+			last_recovery_started: None,
+		}
+	}
+
+	/// Decide whether the recovery of the candidate for `block_hash` can start at `now`, while
+	/// `active_recoveries` recoveries are running.
+	///
+	/// Queued candidates are returned by [`Self::next_waiting`], a candidate is queued only once.
+	/// A recovery counts towards the request interval once it is reported by
+	/// [`Self::recovery_started`].
+	fn admit(&mut self, block_hash: Hash, active_recoveries: usize, now: Instant) -> Admission {
+		if active_recoveries >= self.max_parallel_recoveries {
+			if !self.waiting_for_recovery_slot.contains(&block_hash) {
+				self.waiting_for_recovery_slot.push_back(block_hash);
+			}
+			return Admission::Queued
+		}
+
+		if let Some(last) = self.last_recovery_started {
+			let elapsed = now.saturating_duration_since(last);
+			if elapsed < self.min_request_interval {
+				return Admission::Delay(self.min_request_interval - elapsed)
+			}
+		}
+
+		Admission::Start
+	}
+
+	/// Note that an admitted recovery was started at `now`.
+	fn recovery_started(&mut self, now: Instant) {
+		self.last_recovery_started = Some(now);
+	}
+
+	/// Returns the candidate that waits the longest for a running recovery to finish.
+	fn next_waiting(&mut self) -> Option<Hash> {
+		self.waiting_for_recovery_slot.pop_front()
+	}
+}
+
 /// Represents a pending candidate.
+#[derive(Clone)]
 struct PendingCandidate<Block: BlockT> {
 	receipt: CandidateReceipt,
 	session_index: SessionIndex,
 	block_number: NumberFor<Block>,
+	/// When the candidate was seen for the first time.
+	seen: Instant,
+	/// The number of failed recoveries of this candidate.
+	failed_attempts: u32,
 }
 
 /// Encapsulates the logic of the pov recovery.
@@ -98,7 +236,12 @@ pub struct PoVRecovery<Block: BlockT, PC, IQ, RC> {
 	///
 	/// Uses parent -> blocks mapping.
 	waiting_for_parent: HashMap<Block::Hash, Vec<Block>>,
-	relay_chain_slot_duration: Duration,
+	/// Decides when the candidates that are due to be recovered can start their recovery.
+	limiter: RecoveryLimiter<Block::Hash>,
+	/// The candidates that are currently recovered, kept around to retry a failed recovery.
+	recovering: HashMap<Block::Hash, PendingCandidate<Block>>,
+	config: PoVRecoveryConfig,
+	metrics: Option<Metrics>,
 	parachain_client: Arc<PC>,
 	parachain_import_queue: IQ,
 	relay_chain_interface: RC,
@@ -112,20 +255,38 @@ where
 	IQ: ImportQueue<Block>,
 {
 	/// Create a new instance.
+	///
+	/// The metrics of the PoV recovery are registered in the given `registry`.
 	pub fn new(
 		overseer_handle: OverseerHandle,
-		relay_chain_slot_duration: Duration,
+		config: PoVRecoveryConfig,
 		parachain_client: Arc<PC>,
 		parachain_import_queue: IQ,
 		relay_chain_interface: RCInterface,
 		para_id: ParaId,
+		registry: Option<&Registry>,
 	) -> Self {
+		let metrics = registry.and_then(|registry| match Metrics::register(registry) {
+			Ok(metrics) => Some(metrics),
+			Err(error) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					?error,
+					"Failed to register the PoV recovery metrics",
+				);
+				None
+			},
+		});
+
 		Self {
 			pending_candidates: HashMap::new(),
 			next_candidate_to_recover: Default::default(),
 			active_candidate_recovery: ActiveCandidateRecovery::new(overseer_handle),
-			relay_chain_slot_duration,
 			waiting_for_parent: HashMap::new(),
+			limiter: RecoveryLimiter::new(&config),
+			recovering: HashMap::new(),
+			config,
+			metrics,
 			parachain_client,
 			parachain_import_queue,
 			relay_chain_interface,
@@ -179,6 +340,8 @@ where
 					block_number: *header.number(),
 					receipt: receipt.to_plain(),
 					session_index,
+					seen: Instant::now(),
+					failed_attempts: 0,
 				},
 			)
 			.is_some()
@@ -186,13 +349,23 @@ where
 			return
 		}
 
-		// Wait some random time, with the maximum being the slot duration of the relay chain
-		// before we start to recover the candidate.
-		let delay = Delay::new(self.relay_chain_slot_duration.mul_f64(thread_rng().gen()));
+		if let Some(metrics) = &self.metrics {
+			metrics.candidates_seen.inc();
+		}
+
+		// Wait some random time between the configured minimum and maximum delay before we start
+		// to recover the candidate.
+		let spread = self.config.max_delay.saturating_sub(self.config.min_delay);
+		self.schedule_recovery(hash, self.config.min_delay + spread.mul_f64(thread_rng().gen()));
+	}
+
+	/// Schedule the recovery of the candidate for the given `block_hash` after `delay`.
+	fn schedule_recovery(&mut self, block_hash: Block::Hash, delay: Duration) {
+		let delay = Delay::new(delay);
 		self.next_candidate_to_recover.push(
 			async move {
 				delay.await;
-				hash
+				block_hash
 			}
 			.boxed(),
 		);
@@ -209,17 +382,67 @@ where
 	}
 
 	/// Recover the candidate for the given `block_hash`.
+	///
+	/// If too many recoveries are running, the candidate waits for one of them to finish. If the
+	/// last recovery was started too recently, the recovery is rescheduled.
 	async fn recover_candidate(&mut self, block_hash: Block::Hash) {
+		if !self.pending_candidates.contains_key(&block_hash) {
+			return
+		}
+
+		let active_recoveries = self.active_candidate_recovery.active_recoveries();
+		let now = Instant::now();
+		match self.limiter.admit(block_hash, active_recoveries, now) {
+			Admission::Start => {},
+			Admission::Queued => return,
+			Admission::Delay(delay) => {
+				self.schedule_recovery(block_hash, delay);
+				return
+			},
+		}
+
 		let pending_candidate = match self.pending_candidates.remove(&block_hash) {
 			Some(pending_candidate) => pending_candidate,
 			None => return,
 		};
+		self.limiter.recovery_started(now);
 
+		self.recovering.insert(block_hash, pending_candidate.clone());
 		self.active_candidate_recovery
 			.recover_candidate(block_hash, pending_candidate)
 			.await;
 	}
 
+	/// Returns if the candidate for the given `block_hash` is being recovered or waits for a retry.
+	fn is_being_recovered(&self, block_hash: &Block::Hash) -> bool {
+		let retrying = self
+			.pending_candidates
+			.get(block_hash)
+			.map_or(false, |candidate| candidate.failed_attempts > 0);
+
+		retrying || self.active_candidate_recovery.is_being_recovered(block_hash)
+	}
+
+	/// Give up on recovering the candidate for the given `block_hash`.
+	fn recovery_failed(&mut self, block_hash: Block::Hash) {
+		if let Some(metrics) = &self.metrics {
+			metrics.candidates_failed.inc();
+		}
+
+		self.clear_waiting_for_parent(block_hash);
+	}
+
+	/// Start the recovery of the next candidate that waits for a running recovery to finish.
+	async fn recover_waiting_candidate(&mut self) {
+		while let Some(block_hash) = self.limiter.next_waiting() {
+			// Candidates that were imported in the meantime don't need a recovery slot.
+			if self.pending_candidates.contains_key(&block_hash) {
+				self.recover_candidate(block_hash).await;
+				return
+			}
+		}
+	}
+
 	/// Clear `waiting_for_parent` from the given `hash` and do this recursively for all child
 	/// blocks.
 	fn clear_waiting_for_parent(&mut self, hash: Block::Hash) {
@@ -238,10 +461,40 @@ where
 		block_hash: Block::Hash,
 		available_data: Option<AvailableData>,
 	) {
-		let available_data = match available_data {
-			Some(data) => data,
-			None => {
-				self.clear_waiting_for_parent(block_hash);
+		let pending_candidate = self.recovering.remove(&block_hash);
+
+		let available_data = match (available_data, pending_candidate) {
+			(Some(data), pending_candidate) => {
+				if let Some((metrics, candidate)) = self.metrics.as_ref().zip(pending_candidate) {
+					metrics.time_to_recover.observe(candidate.seen.elapsed().as_secs_f64());
+				}
+				data
+			},
+			(None, Some(mut pending_candidate)) => {
+				pending_candidate.failed_attempts += 1;
+				let delay = match self.config.retry_delay(pending_candidate.failed_attempts) {
+					Some(delay) => delay,
+					None => {
+						self.recovery_failed(block_hash);
+						return
+					},
+				};
+
+				tracing::debug!(
+					target: LOG_TARGET,
+					?block_hash,
+					attempt = pending_candidate.failed_attempts,
+					?delay,
+					"Recovery failed, retrying.",
+				);
+
+				// Children waiting for this block stay around until the retry finished.
+				self.pending_candidates.insert(block_hash, pending_candidate);
+				self.schedule_recovery(block_hash, delay);
+				return
+			},
+			(None, None) => {
+				self.recovery_failed(block_hash);
 				return
 			},
 		};
@@ -254,7 +507,7 @@ where
 			Err(error) => {
 				tracing::debug!(target: LOG_TARGET, ?error, "Failed to decompress PoV");
 
-				self.clear_waiting_for_parent(block_hash);
+				self.recovery_failed(block_hash);
 
 				return
 			},
@@ -269,19 +522,23 @@ where
 					"Failed to decode parachain block data from recovered PoV",
 				);
 
-				self.clear_waiting_for_parent(block_hash);
+				self.recovery_failed(block_hash);
 
 				return
 			},
 		};
 
+		if let Some(metrics) = &self.metrics {
+			metrics.candidates_recovered.inc();
+		}
+
 		let block = block_data.into_block();
 
 		let parent = *block.header().parent_hash();
 
 		match self.parachain_client.block_status(&BlockId::hash(parent)) {
 			Ok(BlockStatus::Unknown) => {
-				if self.active_candidate_recovery.is_being_recovered(&parent) {
+				if self.is_being_recovered(&parent) {
 					tracing::debug!(
 						target: "cumulus-consensus",
 						?block_hash,
@@ -354,6 +611,10 @@ where
 			}
 		}
 
+		if let Some(metrics) = &self.metrics {
+			metrics.blocks_imported.inc_by(incoming_blocks.len() as u64);
+		}
+
 		self.parachain_import_queue
 			.import_blocks(BlockOrigin::ConsensusBroadcast, incoming_blocks);
 	}
@@ -362,8 +623,12 @@ where
 	pub async fn run(mut self) {
 		let mut imported_blocks = self.parachain_client.import_notification_stream().fuse();
 		let mut finalized_blocks = self.parachain_client.finality_notification_stream().fuse();
-		let pending_candidates =
-			pending_candidates(self.relay_chain_interface.clone(), self.para_id).fuse();
+		let pending_candidates = pending_candidates(
+			self.relay_chain_interface.clone(),
+			self.para_id,
+			self.config.finalized_only,
+		)
+		.fuse();
 		futures::pin_mut!(pending_candidates);
 
 		loop {
@@ -410,23 +675,98 @@ where
 					self.active_candidate_recovery.wait_for_recovery().fuse() =>
 				{
 					self.handle_candidate_recovered(block_hash, available_data).await;
+					self.recover_waiting_candidate().await;
 				},
 			}
 		}
 	}
 }
 
+/// Returns the hashes of the relay chain blocks that are finalized with `finalized`, ordered by
+/// their number.
+///
+/// All blocks up to `last_finalized` were seen already. Finality notifications are only sent
+/// for the last block finalized at once, the blocks in between are found through their parents,
+/// which are looked up with `header`.
+fn newly_finalized_blocks(
+	finalized: &PHeader,
+	last_finalized: Option<PBlockNumber>,
+	header: impl Fn(PHash) -> Option<PHeader>,
+) -> Vec<PHash> {
+	let mut blocks = vec![finalized.hash()];
+	let last_finalized = match last_finalized {
+		Some(last_finalized) => last_finalized,
+		None => return blocks,
+	};
+
+	let mut number = finalized.number;
+	let mut parent = finalized.parent_hash;
+	while number > last_finalized.saturating_add(1) {
+		match header(parent) {
+			Some(header) => {
+				blocks.push(parent);
+				number = header.number;
+				parent = header.parent_hash;
+			},
+			None => {
+				tracing::debug!(
+					target: LOG_TARGET,
+					block_hash = ?parent,
+					"Unknown finalized relay chain block, skipping the blocks below it.",
+				);
+				break
+			},
+		}
+	}
+
+	blocks.reverse();
+	blocks
+}
+
 /// Returns a stream over pending candidates for the parachain corresponding to `para_id`.
+///
+/// With `finalized_only` the candidates are taken from all finalized relay chain blocks,
+/// otherwise from all imported relay chain blocks.
 fn pending_candidates(
-	relay_chain_client: impl RelayChainInterface,
+	relay_chain_client: impl RelayChainInterface + Clone,
 	para_id: ParaId,
+	finalized_only: bool,
 ) -> impl Stream<Item = (CommittedCandidateReceipt, SessionIndex)> {
-	relay_chain_client.import_notification_stream().filter_map(move |n| {
+	let relay_blocks = if finalized_only {
+		let relay_chain_client = relay_chain_client.clone();
+		let mut last_finalized = None;
+
+		relay_chain_client
+			.finality_notification_stream()
+			.flat_map(move |n| {
+				let blocks = newly_finalized_blocks(&n.header, last_finalized, |hash| {
+					relay_chain_client
+						.header(BlockId::hash(hash))
+						.map_err(|e| {
+							tracing::error!(
+								target: LOG_TARGET,
+								error = ?e,
+								"Failed to fetch a finalized relay chain header.",
+							)
+						})
+						.ok()
+						.flatten()
+				});
+				last_finalized = Some(n.header.number);
+
+				stream::iter(blocks)
+			})
+			.boxed()
+	} else {
+		relay_chain_client.import_notification_stream().map(|n| n.hash).boxed()
+	};
+
+	relay_blocks.filter_map(move |hash| {
 		let res = relay_chain_client
-			.candidate_pending_availability(&BlockId::hash(n.hash), para_id)
+			.candidate_pending_availability(&BlockId::hash(hash), para_id)
 			.and_then(|pa| {
 				relay_chain_client
-					.session_index_for_child(&BlockId::hash(n.hash))
+					.session_index_for_child(&BlockId::hash(hash))
 					.map(|v| pa.map(|pa| (pa, v)))
 			})
 			.map_err(|e| {
@@ -442,3 +782,115 @@ fn pending_candidates(
 		async move { res }
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config() -> PoVRecoveryConfig {
+		PoVRecoveryConfig {
+			min_delay: Duration::ZERO,
+			max_delay: Duration::ZERO,
+			max_parallel_recoveries: 2,
+			min_request_interval: Duration::from_secs(10),
+			max_retries: 3,
+			retry_backoff: Duration::from_secs(1),
+			finalized_only: false,
+		}
+	}
+
+	/// A chain of relay chain headers with the numbers `1..=len`.
+	fn chain(len: PBlockNumber) -> Vec<PHeader> {
+		let mut headers = Vec::<PHeader>::new();
+		for number in 1..=len {
+			let parent_hash = headers.last().map(|header| header.hash()).unwrap_or_default();
+			headers.push(PHeader::new(
+				number,
+				Default::default(),
+				Default::default(),
+				parent_hash,
+				Default::default(),
+			));
+		}
+		headers
+	}
+
+	#[test]
+	fn limits_parallel_recoveries() {
+		let mut limiter = RecoveryLimiter::new(&PoVRecoveryConfig {
+			min_request_interval: Duration::ZERO,
+			..config()
+		});
+		let now = Instant::now();
+
+		assert_eq!(limiter.admit(1, 0, now), Admission::Start);
+		assert_eq!(limiter.admit(2, 1, now), Admission::Start);
+		assert_eq!(limiter.admit(3, 2, now), Admission::Queued);
+		assert_eq!(limiter.admit(4, 2, now), Admission::Queued);
+		// Candidates that are rescheduled while waiting are only queued once.
+		assert_eq!(limiter.admit(3, 2, now), Admission::Queued);
+
+		assert_eq!(limiter.next_waiting(), Some(3));
+		assert_eq!(limiter.next_waiting(), Some(4));
+		assert_eq!(limiter.next_waiting(), None);
+	}
+
+	#[test]
+	fn limits_request_interval() {
+		let mut limiter = RecoveryLimiter::new(&config());
+		let now = Instant::now();
+
+		// Admitted candidates that aren't recovered don't count towards the interval.
+		assert_eq!(limiter.admit(1, 0, now), Admission::Start);
+		assert_eq!(limiter.admit(1, 0, now), Admission::Start);
+		limiter.recovery_started(now);
+
+		assert_eq!(
+			limiter.admit(2, 0, now + Duration::from_secs(4)),
+			Admission::Delay(Duration::from_secs(6)),
+		);
+		assert_eq!(limiter.admit(2, 0, now + Duration::from_secs(10)), Admission::Start);
+		limiter.recovery_started(now + Duration::from_secs(10));
+		assert_eq!(
+			limiter.admit(3, 0, now + Duration::from_secs(11)),
+			Admission::Delay(Duration::from_secs(9)),
+		);
+
+		// Delayed candidates don't wait for a recovery slot.
+		assert_eq!(limiter.next_waiting(), None);
+	}
+
+	#[test]
+	fn retries_failed_recoveries_with_backoff() {
+		let config = config();
+
+		assert_eq!(config.retry_delay(1), Some(Duration::from_secs(1)));
+		assert_eq!(config.retry_delay(2), Some(Duration::from_secs(2)));
+		assert_eq!(config.retry_delay(3), Some(Duration::from_secs(4)));
+		assert_eq!(config.retry_delay(4), None);
+
+		let config = PoVRecoveryConfig { max_retries: 0, ..config };
+		assert_eq!(config.retry_delay(1), None);
+	}
+
+	#[test]
+	fn finds_all_newly_finalized_blocks() {
+		let headers = chain(5);
+		let hashes = headers.iter().map(|header| header.hash()).collect::<Vec<_>>();
+		let headers_by_hash = headers
+			.iter()
+			.map(|header| (header.hash(), header.clone()))
+			.collect::<HashMap<_, _>>();
+		let header_of = |hash: PHash| headers_by_hash.get(&hash).cloned();
+		let mut finalized = headers[4].clone();
+
+		// Without a previously finalized block only the notified block is returned.
+		assert_eq!(newly_finalized_blocks(&finalized, None, &header_of), vec![hashes[4]]);
+		assert_eq!(newly_finalized_blocks(&finalized, Some(4), &header_of), vec![hashes[4]]);
+		assert_eq!(newly_finalized_blocks(&finalized, Some(1), &header_of), hashes[1..].to_vec());
+
+		// Unknown parents end the walk.
+		finalized.parent_hash = PHash::repeat_byte(0xff);
+		assert_eq!(newly_finalized_blocks(&finalized, Some(1), &header_of), vec![finalized.hash()]);
+	}
+}
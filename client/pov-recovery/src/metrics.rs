@@ -0,0 +1,80 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use substrate_prometheus_endpoint::{
+	register, Counter, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
+
+/// Prometheus metrics of the PoV recovery.
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	/// Candidates whose block was unknown when they were seen on the relay chain.
+	pub candidates_seen: Counter<U64>,
+	/// Candidates whose block was recovered from the relay chain.
+	pub candidates_recovered: Counter<U64>,
+	/// Candidates that could not be recovered, even after retrying.
+	pub candidates_failed: Counter<U64>,
+	/// Recovered blocks handed to the import queue.
+	pub blocks_imported: Counter<U64>,
+	/// Time from seeing a candidate until its block was recovered.
+	pub time_to_recover: Histogram,
+}
+
+impl Metrics {
+	/// Register the metrics in the given `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			candidates_seen: register(
+				Counter::new(
+					"cumulus_pov_recovery_candidates_seen",
+					"Number of candidates with an unknown block seen on the relay chain.",
+				)?,
+				registry,
+			)?,
+			candidates_recovered: register(
+				Counter::new(
+					"cumulus_pov_recovery_candidates_recovered",
+					"Number of candidates whose block was recovered.",
+				)?,
+				registry,
+			)?,
+			candidates_failed: register(
+				Counter::new(
+					"cumulus_pov_recovery_candidates_failed",
+					"Number of candidates that could not be recovered.",
+				)?,
+				registry,
+			)?,
+			blocks_imported: register(
+				Counter::new(
+					"cumulus_pov_recovery_blocks_imported",
+					"Number of recovered blocks sent to the import queue.",
+				)?,
+				registry,
+			)?,
+			time_to_recover: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"cumulus_pov_recovery_time_to_recover",
+						"Time in seconds from seeing a candidate until its block was recovered.",
+					)
+					.buckets(vec![1.0, 2.0, 4.0, 6.0, 12.0, 24.0, 48.0, 96.0, 192.0]),
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
@@ -0,0 +1,715 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A [`RelayChainInterface`] adapter that caches results per relay chain block.
+//!
+//! Block announce validation, inherent creation, parachain consensus and PoV recovery all query
+//! the relay chain for the same relay parents. [`CachingRelayChainInterface`] remembers the
+//! results of the most common of these queries for a bounded number of relay chain blocks.
+//! Concurrent identical requests are deduplicated, only the first one reaches the wrapped
+//! interface while the others wait for its result.
+//!
+//! The numbers of the cached blocks are looked up when they are first cached, or learned from
+//! import notifications. A background task follows the finality notifications of the relay chain
+//! and drops all cached blocks below the last finalized block.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::Arc,
+};
+
+use cumulus_primitives_core::{
+	relay_chain::{
+		v1::{CommittedCandidateReceipt, OccupiedCoreAssumption, SessionIndex, ValidatorId},
+		Block as PBlock, BlockId, BlockNumber as PBlockNumber, Hash as PHash, Header as PHeader,
+		InboundHrmpMessage,
+	},
+	InboundDownwardMessage, ParaId, PersistedValidationData,
+};
+use futures::{select, FutureExt, StreamExt};
+use lru::LruCache;
+use parking_lot::Mutex;
+use polkadot_overseer::Handle as OverseerHandle;
+use sc_client_api::{
+	blockchain::BlockStatus, FinalityNotifications, ImportNotifications, StorageProof,
+};
+use sp_api::ApiError;
+use sp_core::traits::SpawnNamed;
+use sp_state_machine::StorageValue;
+use substrate_prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
+
+use async_trait::async_trait;
+
+use crate::{RelayChainInterface, WaitError};
+
+/// The default number of relay chain blocks [`CachingRelayChainInterface`] keeps results for.
+pub const DEFAULT_CACHE_SIZE: usize = 32;
+
+/// A cached value, `None` until the first request for it finished successfully.
+///
+/// The lock is held while the value is fetched, which makes concurrent requests for the same value
+/// wait for the first one instead of querying the relay chain themselves.
+type Cell<T> = Arc<Mutex<Option<T>>>;
+
+/// The cached results for a single relay chain block.
+#[derive(Default)]
+struct BlockCache {
+	/// The number of the block, if it is known.
+	number: Option<PBlockNumber>,
+	persisted_validation_data:
+		Vec<(ParaId, OccupiedCoreAssumption, Cell<Option<PersistedValidationData>>)>,
+	storage: HashMap<Vec<u8>, Cell<Option<StorageValue>>>,
+	session_index_for_child: Cell<SessionIndex>,
+}
+
+impl BlockCache {
+	fn persisted_validation_data(
+		&mut self,
+		para_id: ParaId,
+		assumption: OccupiedCoreAssumption,
+	) -> Cell<Option<PersistedValidationData>> {
+		if let Some((_, _, cell)) = self
+			.persisted_validation_data
+			.iter()
+			.find(|(p, a, _)| *p == para_id && *a == assumption)
+		{
+			return cell.clone()
+		}
+
+		let cell = Cell::default();
+		self.persisted_validation_data.push((para_id, assumption, cell.clone()));
+		cell
+	}
+}
+
+type Cache = Arc<Mutex<LruCache<PHash, BlockCache>>>;
+
+/// Cache metrics.
+#[derive(Clone)]
+struct Metrics {
+	hits: CounterVec<U64>,
+	misses: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			hits: register(
+				CounterVec::new(
+					Opts::new(
+						"cumulus_relay_chain_cache_hits",
+						"Number of relay chain requests answered from the cache.",
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+			misses: register(
+				CounterVec::new(
+					Opts::new(
+						"cumulus_relay_chain_cache_misses",
+						"Number of relay chain requests forwarded to the relay chain.",
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Wraps a [`RelayChainInterface`] and caches the results of
+/// [`persisted_validation_data`](RelayChainInterface::persisted_validation_data),
+/// [`get_storage_by_key`](RelayChainInterface::get_storage_by_key) and
+/// [`session_index_for_child`](RelayChainInterface::session_index_for_child).
+///
+/// Results are only cached for blocks requested by hash and are kept for the `cache_size` most
+/// recently used relay chain blocks. Blocks below the last finalized block are dropped from the
+/// cache when the relay chain finalizes. Errors are never cached. All other requests are passed
+/// through to the wrapped interface.
+#[derive(Clone)]
+pub struct CachingRelayChainInterface<R> {
+	inner: R,
+	cache: Cache,
+	metrics: Option<Metrics>,
+}
+
+impl<R: RelayChainInterface> CachingRelayChainInterface<R> {
+	/// Create a new instance wrapping `inner`, which keeps results for `cache_size` blocks.
+	///
+	/// The task that follows the notifications of the relay chain is spawned with `spawner`. Hit
+	/// and miss counters are registered in the given `registry`.
+	pub fn new(
+		inner: R,
+		cache_size: usize,
+		spawner: &dyn SpawnNamed,
+		registry: Option<&Registry>,
+	) -> Result<Self, PrometheusError> {
+		let metrics = registry.map(Metrics::register).transpose()?;
+		let cache = Arc::new(Mutex::new(LruCache::new(cache_size)));
+
+		spawner.spawn(
+			"cumulus-relay-chain-cache",
+			None,
+			process_notifications(
+				cache.clone(),
+				inner.import_notification_stream(),
+				inner.finality_notification_stream(),
+			)
+			.boxed(),
+		);
+
+		Ok(Self { inner, cache, metrics })
+	}
+
+	/// Return the cached value selected by `select` for `block_id`, fetching it with `fetch` if it
+	/// is not cached yet.
+	fn cached<T: Clone, E>(
+		&self,
+		method: &str,
+		block_id: &BlockId,
+		select: impl FnOnce(&mut BlockCache) -> Cell<T>,
+		fetch: impl FnOnce() -> Result<T, E>,
+	) -> Result<T, E> {
+		let hash = match block_id {
+			BlockId::Hash(hash) => *hash,
+			BlockId::Number(_) => return fetch(),
+		};
+
+		if !self.cache.lock().contains(&hash) {
+			// Looked up without holding the lock, the block is only inserted if no concurrent
+			// request did it in the meantime.
+			let number = self
+				.inner
+				.header(BlockId::Hash(hash))
+				.ok()
+				.flatten()
+				.map(|header| header.number);
+			let mut cache = self.cache.lock();
+			if !cache.contains(&hash) {
+				cache.put(hash, BlockCache { number, ..Default::default() });
+			}
+		}
+
+		let cell = {
+			let mut cache = self.cache.lock();
+			match cache.get_mut(&hash) {
+				Some(block) => select(block),
+				// Evicted by concurrent requests for other blocks, don't cache the result.
+				None => return fetch(),
+			}
+		};
+
+		let mut value = cell.lock();
+		if let Some(value) = &*value {
+			if let Some(metrics) = &self.metrics {
+				metrics.hits.with_label_values(&[method]).inc();
+			}
+			return Ok(value.clone())
+		}
+
+		if let Some(metrics) = &self.metrics {
+			metrics.misses.with_label_values(&[method]).inc();
+		}
+
+		let fetched = fetch()?;
+		*value = Some(fetched.clone());
+		Ok(fetched)
+	}
+}
+
+/// Follow the notifications of the relay chain until one of the streams ends.
+///
+/// Import notifications tell us the numbers of cached blocks whose number wasn't known yet, they
+/// are needed to drop everything below a newly finalized block. Blocks that aren't cached are
+/// ignored, they are only cached once they are requested.
+async fn process_notifications(
+	cache: Cache,
+	imports: ImportNotifications<PBlock>,
+	finality: FinalityNotifications<PBlock>,
+) {
+	let mut imports = imports.fuse();
+	let mut finality = finality.fuse();
+
+	loop {
+		select! {
+			notification = imports.next() => match notification {
+				Some(notification) =>
+					if let Some(block) = cache.lock().peek_mut(&notification.hash) {
+						block.number = Some(notification.header.number);
+					},
+				None => return,
+			},
+			notification = finality.next() => match notification {
+				Some(notification) => {
+					let finalized = notification.header.number;
+					let mut cache = cache.lock();
+					let outdated = cache
+						.iter()
+						.filter(|(_, block)| block.number.map_or(false, |number| number < finalized))
+						.map(|(hash, _)| *hash)
+						.collect::<Vec<_>>();
+
+					outdated.iter().for_each(|hash| {
+						cache.pop(hash);
+					});
+				},
+				None => return,
+			},
+		}
+	}
+}
+
+#[async_trait]
+impl<R: RelayChainInterface> RelayChainInterface for CachingRelayChainInterface<R> {
+	fn retrieve_dmq_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> Option<Vec<InboundDownwardMessage>> {
+		self.inner.retrieve_dmq_contents(para_id, relay_parent)
+	}
+
+	fn retrieve_all_inbound_hrmp_channel_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> Option<BTreeMap<ParaId, Vec<InboundHrmpMessage>>> {
+		self.inner.retrieve_all_inbound_hrmp_channel_contents(para_id, relay_parent)
+	}
+
+	fn persisted_validation_data(
+		&self,
+		block_id: &BlockId,
+		para_id: ParaId,
+		occupied_core_assumption: OccupiedCoreAssumption,
+	) -> Result<Option<PersistedValidationData>, ApiError> {
+		self.cached(
+			"persisted_validation_data",
+			block_id,
+			|block| block.persisted_validation_data(para_id, occupied_core_assumption),
+			|| {
+				self.inner
+					.persisted_validation_data(block_id, para_id, occupied_core_assumption)
+			},
+		)
+	}
+
+	fn candidate_pending_availability(
+		&self,
+		block_id: &BlockId,
+		para_id: ParaId,
+	) -> Result<Option<CommittedCandidateReceipt>, ApiError> {
+		self.inner.candidate_pending_availability(block_id, para_id)
+	}
+
+	fn session_index_for_child(&self, block_id: &BlockId) -> Result<SessionIndex, ApiError> {
+		self.cached(
+			"session_index_for_child",
+			block_id,
+			|block| block.session_index_for_child.clone(),
+			|| self.inner.session_index_for_child(block_id),
+		)
+	}
+
+	fn validators(&self, block_id: &BlockId) -> Result<Vec<ValidatorId>, ApiError> {
+		self.inner.validators(block_id)
+	}
+
+	fn import_notification_stream(&self) -> sc_client_api::ImportNotifications<PBlock> {
+		self.inner.import_notification_stream()
+	}
+
+	fn finality_notification_stream(&self) -> sc_client_api::FinalityNotifications<PBlock> {
+		self.inner.finality_notification_stream()
+	}
+
+	fn storage_changes_notification_stream(
+		&self,
+		filter_keys: Option<&[sc_client_api::StorageKey]>,
+		child_filter_keys: Option<
+			&[(sc_client_api::StorageKey, Option<Vec<sc_client_api::StorageKey>>)],
+		>,
+	) -> sc_client_api::blockchain::Result<sc_client_api::StorageEventStream<PHash>> {
+		self.inner.storage_changes_notification_stream(filter_keys, child_filter_keys)
+	}
+
+	fn best_block_hash(&self) -> PHash {
+		self.inner.best_block_hash()
+	}
+
+	fn block_status(&self, block_id: BlockId) -> Result<BlockStatus, sp_blockchain::Error> {
+		self.inner.block_status(block_id)
+	}
+
+	fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error> {
+		self.inner.header(block_id)
+	}
+
+	fn is_major_syncing(&self) -> bool {
+		self.inner.is_major_syncing()
+	}
+
+	fn overseer_handle(&self) -> Option<OverseerHandle> {
+		self.inner.overseer_handle()
+	}
+
+	fn get_storage_by_key(
+		&self,
+		block_id: &BlockId,
+		key: &[u8],
+	) -> Result<Option<StorageValue>, sp_blockchain::Error> {
+		self.cached(
+			"get_storage_by_key",
+			block_id,
+			|block| block.storage.entry(key.to_vec()).or_default().clone(),
+			|| self.inner.get_storage_by_key(block_id, key),
+		)
+	}
+
+	fn prove_read(
+		&self,
+		block_id: &BlockId,
+		relevant_keys: &Vec<Vec<u8>>,
+	) -> Result<Option<StorageProof>, Box<dyn sp_state_machine::Error>> {
+		self.inner.prove_read(block_id, relevant_keys)
+	}
+
+	async fn wait_for_block(&self, hash: PHash) -> Result<(), WaitError> {
+		self.inner.wait_for_block(hash).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::future::BoxFuture;
+	use sc_client_api::{BlockImportNotification, FinalityNotification};
+	use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
+	use sp_consensus::BlockOrigin;
+	use sp_runtime::traits::Header as HeaderT;
+
+	/// Spawner that keeps the spawned tasks, so that the tests can decide when they run.
+	#[derive(Default)]
+	struct TestSpawner(Mutex<Vec<BoxFuture<'static, ()>>>);
+
+	impl TestSpawner {
+		/// Run the spawned tasks until all of them wait for new notifications.
+		fn run_until_stalled(&self) {
+			self.0.lock().iter_mut().for_each(|task| {
+				assert!(task.as_mut().now_or_never().is_none(), "Tasks never finish");
+			});
+		}
+	}
+
+	impl SpawnNamed for TestSpawner {
+		fn spawn_blocking(
+			&self,
+			_: &'static str,
+			_: Option<&'static str>,
+			future: BoxFuture<'static, ()>,
+		) {
+			self.0.lock().push(future);
+		}
+
+		fn spawn(&self, _: &'static str, _: Option<&'static str>, future: BoxFuture<'static, ()>) {
+			self.0.lock().push(future);
+		}
+	}
+
+	/// Relay chain interface that counts the requests reaching it.
+	///
+	/// Only the headers of imported blocks are known, looking them up is not counted.
+	#[derive(Clone, Default)]
+	struct CountingRelayChainInterface {
+		requests: Arc<Mutex<usize>>,
+		headers: Arc<Mutex<HashMap<PHash, PHeader>>>,
+		import_sinks: Arc<Mutex<Vec<TracingUnboundedSender<BlockImportNotification<PBlock>>>>>,
+		finality_sinks: Arc<Mutex<Vec<TracingUnboundedSender<FinalityNotification<PBlock>>>>>,
+	}
+
+	impl CountingRelayChainInterface {
+		fn requests(&self) -> usize {
+			*self.requests.lock()
+		}
+
+		fn import(&self, header: &PHeader) {
+			self.headers.lock().insert(header.hash(), header.clone());
+			let notification = BlockImportNotification {
+				hash: header.hash(),
+				origin: BlockOrigin::NetworkBroadcast,
+				header: header.clone(),
+				is_new_best: true,
+				tree_route: None,
+			};
+			self.import_sinks
+				.lock()
+				.iter()
+				.for_each(|sink| sink.unbounded_send(notification.clone()).unwrap());
+		}
+
+		fn finalize(&self, header: &PHeader) {
+			let notification = FinalityNotification { hash: header.hash(), header: header.clone() };
+			self.finality_sinks
+				.lock()
+				.iter()
+				.for_each(|sink| sink.unbounded_send(notification.clone()).unwrap());
+		}
+	}
+
+	#[async_trait]
+	impl RelayChainInterface for CountingRelayChainInterface {
+		fn retrieve_dmq_contents(
+			&self,
+			_: ParaId,
+			_: PHash,
+		) -> Option<Vec<InboundDownwardMessage>> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn retrieve_all_inbound_hrmp_channel_contents(
+			&self,
+			_: ParaId,
+			_: PHash,
+		) -> Option<BTreeMap<ParaId, Vec<InboundHrmpMessage>>> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn persisted_validation_data(
+			&self,
+			_: &BlockId,
+			_: ParaId,
+			_: OccupiedCoreAssumption,
+		) -> Result<Option<PersistedValidationData>, ApiError> {
+			*self.requests.lock() += 1;
+			Ok(Some(Default::default()))
+		}
+
+		fn candidate_pending_availability(
+			&self,
+			_: &BlockId,
+			_: ParaId,
+		) -> Result<Option<CommittedCandidateReceipt>, ApiError> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn session_index_for_child(&self, _: &BlockId) -> Result<SessionIndex, ApiError> {
+			*self.requests.lock() += 1;
+			Ok(1)
+		}
+
+		fn validators(&self, _: &BlockId) -> Result<Vec<ValidatorId>, ApiError> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn import_notification_stream(&self) -> sc_client_api::ImportNotifications<PBlock> {
+			let (sink, stream) = tracing_unbounded("test_import_notifications");
+			self.import_sinks.lock().push(sink);
+			stream
+		}
+
+		fn finality_notification_stream(&self) -> sc_client_api::FinalityNotifications<PBlock> {
+			let (sink, stream) = tracing_unbounded("test_finality_notifications");
+			self.finality_sinks.lock().push(sink);
+			stream
+		}
+
+		fn storage_changes_notification_stream(
+			&self,
+			_: Option<&[sc_client_api::StorageKey]>,
+			_: Option<&[(sc_client_api::StorageKey, Option<Vec<sc_client_api::StorageKey>>)]>,
+		) -> sc_client_api::blockchain::Result<sc_client_api::StorageEventStream<PHash>> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn best_block_hash(&self) -> PHash {
+			unimplemented!("Not needed for test")
+		}
+
+		fn block_status(&self, _: BlockId) -> Result<BlockStatus, sp_blockchain::Error> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error> {
+			match block_id {
+				BlockId::Hash(hash) => Ok(self.headers.lock().get(&hash).cloned()),
+				BlockId::Number(_) => unimplemented!("Not needed for test"),
+			}
+		}
+
+		fn is_major_syncing(&self) -> bool {
+			unimplemented!("Not needed for test")
+		}
+
+		fn overseer_handle(&self) -> Option<OverseerHandle> {
+			unimplemented!("Not needed for test")
+		}
+
+		fn get_storage_by_key(
+			&self,
+			_: &BlockId,
+			key: &[u8],
+		) -> Result<Option<StorageValue>, sp_blockchain::Error> {
+			*self.requests.lock() += 1;
+			Ok(Some(key.to_vec()))
+		}
+
+		fn prove_read(
+			&self,
+			_: &BlockId,
+			_: &Vec<Vec<u8>>,
+		) -> Result<Option<StorageProof>, Box<dyn sp_state_machine::Error>> {
+			unimplemented!("Not needed for test")
+		}
+
+		async fn wait_for_block(&self, _: PHash) -> Result<(), WaitError> {
+			unimplemented!("Not needed for test")
+		}
+	}
+
+	fn header(number: PBlockNumber) -> PHeader {
+		PHeader::new(
+			number,
+			Default::default(),
+			Default::default(),
+			PHash::repeat_byte(number as u8),
+			Default::default(),
+		)
+	}
+
+	#[test]
+	fn caches_results_per_block() {
+		let inner = CountingRelayChainInterface::default();
+		let registry = Registry::new();
+		let spawner = TestSpawner::default();
+		let caching =
+			CachingRelayChainInterface::new(inner.clone(), 2, &spawner, Some(&registry)).unwrap();
+		let block = BlockId::Hash(header(1).hash());
+
+		assert_eq!(caching.session_index_for_child(&block).unwrap(), 1);
+		assert_eq!(caching.session_index_for_child(&block).unwrap(), 1);
+		assert_eq!(inner.requests(), 1);
+
+		assert_eq!(caching.get_storage_by_key(&block, b"a").unwrap(), Some(b"a".to_vec()));
+		assert_eq!(caching.get_storage_by_key(&block, b"b").unwrap(), Some(b"b".to_vec()));
+		assert_eq!(caching.get_storage_by_key(&block, b"a").unwrap(), Some(b"a".to_vec()));
+		assert_eq!(inner.requests(), 3);
+
+		let para_id = ParaId::from(100);
+		caching
+			.persisted_validation_data(&block, para_id, OccupiedCoreAssumption::Free)
+			.unwrap();
+		caching
+			.persisted_validation_data(&block, para_id, OccupiedCoreAssumption::Free)
+			.unwrap();
+		caching
+			.persisted_validation_data(&block, para_id, OccupiedCoreAssumption::TimedOut)
+			.unwrap();
+		assert_eq!(inner.requests(), 5);
+
+		// Requests by number are never cached.
+		caching.session_index_for_child(&BlockId::Number(1)).unwrap();
+		caching.session_index_for_child(&BlockId::Number(1)).unwrap();
+		assert_eq!(inner.requests(), 7);
+
+		let metrics = caching.metrics.as_ref().unwrap();
+		assert_eq!(metrics.hits.with_label_values(&["session_index_for_child"]).get(), 1);
+		assert_eq!(metrics.misses.with_label_values(&["get_storage_by_key"]).get(), 2);
+		assert_eq!(metrics.hits.with_label_values(&["persisted_validation_data"]).get(), 1);
+	}
+
+	#[test]
+	fn evicts_least_recently_used_blocks() {
+		let inner = CountingRelayChainInterface::default();
+		let spawner = TestSpawner::default();
+		let caching = CachingRelayChainInterface::new(inner.clone(), 2, &spawner, None).unwrap();
+		let blocks = (1..=3).map(|n| BlockId::Hash(header(n).hash())).collect::<Vec<_>>();
+
+		caching.session_index_for_child(&blocks[0]).unwrap();
+		caching.session_index_for_child(&blocks[1]).unwrap();
+		caching.session_index_for_child(&blocks[0]).unwrap();
+		assert_eq!(inner.requests(), 2);
+
+		// Evicts the second block, which was used least recently.
+		caching.session_index_for_child(&blocks[2]).unwrap();
+		caching.session_index_for_child(&blocks[0]).unwrap();
+		assert_eq!(inner.requests(), 3);
+
+		caching.session_index_for_child(&blocks[1]).unwrap();
+		assert_eq!(inner.requests(), 4);
+	}
+
+	#[test]
+	fn drops_blocks_below_finalized_block() {
+		let inner = CountingRelayChainInterface::default();
+		let spawner = TestSpawner::default();
+		let caching = CachingRelayChainInterface::new(inner.clone(), 8, &spawner, None).unwrap();
+		let headers = (1..=3).map(header).collect::<Vec<_>>();
+
+		headers.iter().for_each(|header| inner.import(header));
+		headers.iter().for_each(|header| {
+			caching.session_index_for_child(&BlockId::Hash(header.hash())).unwrap();
+		});
+		assert_eq!(inner.requests(), 3);
+
+		inner.finalize(&headers[1]);
+		spawner.run_until_stalled();
+
+		headers.iter().for_each(|header| {
+			caching.session_index_for_child(&BlockId::Hash(header.hash())).unwrap();
+		});
+		assert_eq!(inner.requests(), 4);
+	}
+
+	#[test]
+	fn learns_block_numbers_from_import_notifications() {
+		let inner = CountingRelayChainInterface::default();
+		let spawner = TestSpawner::default();
+		let caching = CachingRelayChainInterface::new(inner.clone(), 8, &spawner, None).unwrap();
+		let headers = (1..=3).map(header).collect::<Vec<_>>();
+
+		// Cached before the blocks are imported, so their numbers are unknown at first.
+		headers.iter().for_each(|header| {
+			caching.session_index_for_child(&BlockId::Hash(header.hash())).unwrap();
+		});
+		assert_eq!(inner.requests(), 3);
+
+		headers.iter().for_each(|header| inner.import(header));
+		spawner.run_until_stalled();
+		inner.finalize(&headers[1]);
+		spawner.run_until_stalled();
+
+		headers.iter().for_each(|header| {
+			caching.session_index_for_child(&BlockId::Hash(header.hash())).unwrap();
+		});
+		assert_eq!(inner.requests(), 4);
+	}
+
+	#[test]
+	fn import_notifications_do_not_evict_cached_blocks() {
+		let inner = CountingRelayChainInterface::default();
+		let spawner = TestSpawner::default();
+		let caching = CachingRelayChainInterface::new(inner.clone(), 1, &spawner, None).unwrap();
+		let block = BlockId::Hash(header(1).hash());
+
+		caching.session_index_for_child(&block).unwrap();
+		(2..=3).for_each(|number| inner.import(&header(number)));
+		spawner.run_until_stalled();
+
+		caching.session_index_for_child(&block).unwrap();
+		assert_eq!(inner.requests(), 1);
+	}
+}
@@ -19,7 +19,7 @@ use std::{collections::BTreeMap, sync::Arc};
 use cumulus_primitives_core::{
 	relay_chain::{
 		v1::{CommittedCandidateReceipt, OccupiedCoreAssumption, SessionIndex, ValidatorId},
-		Block as PBlock, BlockId, Hash as PHash, InboundHrmpMessage,
+		Block as PBlock, BlockId, Hash as PHash, Header as PHeader, InboundHrmpMessage,
 	},
 	InboundDownwardMessage, ParaId, PersistedValidationData,
 };
@@ -31,6 +31,10 @@ use sp_state_machine::StorageValue;
 
 use async_trait::async_trait;
 
+mod caching;
+
+pub use caching::{CachingRelayChainInterface, DEFAULT_CACHE_SIZE};
+
 #[derive(Debug, derive_more::Display)]
 pub enum WaitError {
 	#[display(fmt = "Timeout while waiting for relay-chain block `{}` to be imported.", _0)]
@@ -64,6 +68,9 @@ pub trait RelayChainInterface: Send + Sync {
 	/// Get the status of a given block.
 	fn block_status(&self, block_id: BlockId) -> Result<BlockStatus, sp_blockchain::Error>;
 
+	/// Get the header of a given block, `None` if the block is unknown.
+	fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error>;
+
 	/// Get the hash of the current best block.
 	fn best_block_hash(&self) -> PHash;
 
@@ -218,6 +225,10 @@ where
 		(**self).block_status(block_id)
 	}
 
+	fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error> {
+		(**self).header(block_id)
+	}
+
 	fn is_major_syncing(&self) -> bool {
 		(**self).is_major_syncing()
 	}
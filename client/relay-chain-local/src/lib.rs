@@ -21,7 +21,7 @@ use cumulus_primitives_core::{
 	relay_chain::{
 		v1::{CommittedCandidateReceipt, OccupiedCoreAssumption, SessionIndex, ValidatorId},
 		v2::ParachainHost,
-		Block as PBlock, BlockId, Hash as PHash, InboundHrmpMessage,
+		Block as PBlock, BlockId, Hash as PHash, Header as PHeader, InboundHrmpMessage,
 	},
 	InboundDownwardMessage, ParaId, PersistedValidationData,
 };
@@ -190,6 +190,10 @@ where
 		self.backend.blockchain().status(block_id)
 	}
 
+	fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error> {
+		self.backend.blockchain().header(block_id)
+	}
+
 	fn is_major_syncing(&self) -> bool {
 		let mut network = self.sync_oracle.lock();
 		network.is_major_syncing()
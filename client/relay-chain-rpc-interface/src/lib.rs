@@ -0,0 +1,682 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of the [`RelayChainInterface`] on top of the JSON-RPC interface of an external
+//! relay chain node.
+//!
+//! This avoids running an embedded relay chain node next to a parachain full node.
+//!
+//! Collators are not supported. Collating needs the overseer of an embedded relay chain node to
+//! distribute the collations, and the overseer subsystems can't be driven over RPC. So
+//! [`RelayChainInterface::overseer_handle`] always returns `None` and the nodes refuse to start a
+//! collator together with `--full-node-relay-chain-rpc-url`. Collators still have to embed a relay
+//! chain node.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use cumulus_primitives_core::{
+	relay_chain::{
+		v1::{CommittedCandidateReceipt, OccupiedCoreAssumption, SessionIndex, ValidatorId},
+		Block as PBlock, BlockId, Hash as PHash, Header as PHeader, InboundHrmpMessage,
+	},
+	InboundDownwardMessage, ParaId, PersistedValidationData,
+};
+use cumulus_relay_chain_interface::{RelayChainInterface, WaitError};
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::{
+	core::{
+		client::{ClientT, SubscriptionClientT},
+		Error as JsonRpseeError,
+	},
+	rpc_params,
+	types::ParamsSer,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use parking_lot::Mutex;
+use polkadot_overseer::Handle as OverseerHandle;
+use sc_client_api::{
+	blockchain::BlockStatus, BlockImportNotification, FinalityNotification, StorageProof,
+};
+use sc_rpc_api::{state::ReadProof, system::Health};
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
+use serde::de::DeserializeOwned;
+use sp_api::ApiError;
+use sp_consensus::BlockOrigin;
+use sp_core::{
+	sp_std::collections::btree_map::BTreeMap,
+	storage::{StorageData, StorageKey},
+	Bytes,
+};
+use sp_runtime::traits::Header as HeaderT;
+use sp_state_machine::StorageValue;
+
+const LOG_TARGET: &str = "relay-chain-rpc-interface";
+/// The timeout in seconds after that the waiting for a block should be aborted.
+const TIMEOUT_IN_SECONDS: u64 = 6;
+
+/// Errors of the [`RelayChainRPCInterface`].
+#[derive(Debug, derive_more::Display)]
+pub enum RelayChainRpcError {
+	#[display(fmt = "Request to the relay chain node failed: {}", _0)]
+	Rpc(JsonRpseeError),
+	#[display(fmt = "Unable to decode the response of `{}`: {}", _0, _1)]
+	Decode(String, codec::Error),
+	#[display(fmt = "Relay chain block `{:?}` is unknown to the relay chain node.", _0)]
+	UnknownBlock(BlockId),
+}
+
+impl std::error::Error for RelayChainRpcError {}
+
+impl From<JsonRpseeError> for RelayChainRpcError {
+	fn from(e: JsonRpseeError) -> Self {
+		Self::Rpc(e)
+	}
+}
+
+type Sinks<T> = Arc<Mutex<Vec<TracingUnboundedSender<T>>>>;
+
+/// Provides an implementation of the [`RelayChainInterface`] using the JSON-RPC interface of an
+/// external relay chain node.
+///
+/// The [`RelayChainInterface`] is synchronous, so most methods block the calling thread until the
+/// relay chain node answered. They must be called from within a multi threaded tokio runtime.
+#[derive(Clone)]
+pub struct RelayChainRPCInterface {
+	client: Arc<WsClient>,
+	best_hash: Arc<Mutex<PHash>>,
+	import_sinks: Sinks<BlockImportNotification<PBlock>>,
+	finality_sinks: Sinks<FinalityNotification<PBlock>>,
+}
+
+impl RelayChainRPCInterface {
+	/// Connect to the relay chain node listening on the WebSocket `url`.
+	///
+	/// The notifications of the relay chain node are forwarded by a background task which is
+	/// spawned on the current tokio runtime.
+	pub async fn new(url: &str) -> Result<Self, RelayChainRpcError> {
+		let client = Arc::new(WsClientBuilder::default().build(url).await?);
+
+		let all_heads = client
+			.subscribe::<PHeader>("chain_subscribeAllHeads", None, "chain_unsubscribeAllHeads")
+			.await?;
+		let finalized_heads = client
+			.subscribe::<PHeader>(
+				"chain_subscribeFinalizedHeads",
+				None,
+				"chain_unsubscribeFinalizedHeads",
+			)
+			.await?;
+		let best_hash = client
+			.request::<Option<PHash>>("chain_getBlockHash", None)
+			.await?
+			.ok_or_else(|| RelayChainRpcError::UnknownBlock(BlockId::Number(0)))?;
+
+		let interface = Self {
+			client,
+			best_hash: Arc::new(Mutex::new(best_hash)),
+			import_sinks: Default::default(),
+			finality_sinks: Default::default(),
+		};
+
+		let notifications = futures::stream::select(
+			all_heads.map(|header| header.map(HeadNotification::Imported)),
+			finalized_heads.map(|header| header.map(HeadNotification::Finalized)),
+		);
+		tokio::spawn(forward_notifications(
+			notifications,
+			interface.client.clone(),
+			interface.best_hash.clone(),
+			interface.import_sinks.clone(),
+			interface.finality_sinks.clone(),
+		));
+
+		Ok(interface)
+	}
+
+	async fn request<R: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Option<ParamsSer<'_>>,
+	) -> Result<R, RelayChainRpcError> {
+		self.client.request(method, params).await.map_err(|e| {
+			tracing::debug!(
+				target: LOG_TARGET,
+				method,
+				error = ?e,
+				"Request to the relay chain node failed.",
+			);
+			e.into()
+		})
+	}
+
+	/// Resolve `block_id` to a hash, returns `None` if the block number is unknown.
+	async fn block_hash(&self, block_id: &BlockId) -> Result<Option<PHash>, RelayChainRpcError> {
+		match block_id {
+			BlockId::Hash(hash) => Ok(Some(*hash)),
+			BlockId::Number(number) => {
+				self.request("chain_getBlockHash", rpc_params![number]).await
+			},
+		}
+	}
+
+	async fn block_hash_or_err(&self, block_id: &BlockId) -> Result<PHash, RelayChainRpcError> {
+		self.block_hash(block_id)
+			.await?
+			.ok_or_else(|| RelayChainRpcError::UnknownBlock(*block_id))
+	}
+
+	/// Call the runtime api `method` of the relay chain at `block_id`.
+	async fn call_runtime_api<R: Decode>(
+		&self,
+		method: &str,
+		block_id: &BlockId,
+		args: impl Encode,
+	) -> Result<R, RelayChainRpcError> {
+		let at = self.block_hash_or_err(block_id).await?;
+		let response: Bytes = self
+			.request("state_call", rpc_params![method, Bytes(args.encode()), at])
+			.await?;
+
+		R::decode(&mut &response[..]).map_err(|e| RelayChainRpcError::Decode(method.into(), e))
+	}
+
+	async fn header_by_hash(&self, hash: PHash) -> Result<Option<PHeader>, RelayChainRpcError> {
+		self.request("chain_getHeader", rpc_params![hash]).await
+	}
+}
+
+/// Run `future` to completion, blocking the current thread.
+///
+/// Other tasks of the current tokio worker are moved to another worker in the meantime.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+	tokio::task::block_in_place(|| futures::executor::block_on(future))
+}
+
+fn api_error(e: RelayChainRpcError) -> ApiError {
+	ApiError::Application(Box::new(e))
+}
+
+fn blockchain_error(e: RelayChainRpcError) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(e.to_string())
+}
+
+enum HeadNotification {
+	Imported(PHeader),
+	Finalized(PHeader),
+}
+
+/// Forward the head notifications of the relay chain node to the notification sinks.
+///
+/// Every block imported by the relay chain node is reported, including blocks on forks that
+/// never become the best block. A block is reported as new best if the relay chain node returns
+/// it as its best block when asked right after the import. A fork that becomes the best chain
+/// without importing a new block, like after a reorg to an already imported block, doesn't
+/// update the best block until the next import.
+///
+/// The relay chain node only announces the last block when several blocks are finalized at once,
+/// so finality notifications can skip blocks. Every finality notification still finalizes all of
+/// its ancestors.
+async fn forward_notifications(
+	notifications: impl futures::Stream<Item = Result<HeadNotification, JsonRpseeError>>,
+	client: Arc<WsClient>,
+	best_hash: Arc<Mutex<PHash>>,
+	import_sinks: Sinks<BlockImportNotification<PBlock>>,
+	finality_sinks: Sinks<FinalityNotification<PBlock>>,
+) {
+	futures::pin_mut!(notifications);
+
+	while let Some(notification) = notifications.next().await {
+		match notification {
+			Ok(HeadNotification::Imported(header)) => {
+				let hash = header.hash();
+				let is_new_best =
+					match client.request::<Option<PHash>>("chain_getBlockHash", None).await {
+						Ok(best) => best == Some(hash),
+						Err(e) => {
+							tracing::error!(
+								target: LOG_TARGET,
+								error = ?e,
+								"Failed to fetch the best block of the relay chain node.",
+							);
+							false
+						},
+					};
+				if is_new_best {
+					*best_hash.lock() = hash;
+				}
+
+				let notification = BlockImportNotification {
+					hash,
+					origin: BlockOrigin::NetworkBroadcast,
+					header,
+					is_new_best,
+					tree_route: None,
+				};
+				import_sinks
+					.lock()
+					.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+			},
+			Ok(HeadNotification::Finalized(header)) => {
+				let notification = FinalityNotification { hash: header.hash(), header };
+				finality_sinks
+					.lock()
+					.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+			},
+			Err(e) => tracing::error!(
+				target: LOG_TARGET,
+				error = ?e,
+				"Failed to decode a head notification of the relay chain node.",
+			),
+		}
+	}
+
+	tracing::error!(
+		target: LOG_TARGET,
+		"The head subscriptions of the relay chain node were closed.",
+	);
+}
+
+#[async_trait]
+impl RelayChainInterface for RelayChainRPCInterface {
+	fn retrieve_dmq_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> Option<Vec<InboundDownwardMessage>> {
+		block_on(self.call_runtime_api(
+			"ParachainHost_dmq_contents",
+			&BlockId::hash(relay_parent),
+			para_id,
+		))
+		.map_err(|e| {
+			tracing::error!(
+				target: LOG_TARGET,
+				relay_parent = ?relay_parent,
+				error = ?e,
+				"An error occured during requesting the downward messages.",
+			);
+		})
+		.ok()
+	}
+
+	fn retrieve_all_inbound_hrmp_channel_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> Option<BTreeMap<ParaId, Vec<InboundHrmpMessage>>> {
+		block_on(self.call_runtime_api(
+			"ParachainHost_inbound_hrmp_channels_contents",
+			&BlockId::hash(relay_parent),
+			para_id,
+		))
+		.map_err(|e| {
+			tracing::error!(
+				target: LOG_TARGET,
+				relay_parent = ?relay_parent,
+				error = ?e,
+				"An error occured during requesting the inbound HRMP messages.",
+			);
+		})
+		.ok()
+	}
+
+	fn persisted_validation_data(
+		&self,
+		block_id: &BlockId,
+		para_id: ParaId,
+		occupied_core_assumption: OccupiedCoreAssumption,
+	) -> Result<Option<PersistedValidationData>, ApiError> {
+		block_on(self.call_runtime_api(
+			"ParachainHost_persisted_validation_data",
+			block_id,
+			(para_id, occupied_core_assumption),
+		))
+		.map_err(api_error)
+	}
+
+	fn candidate_pending_availability(
+		&self,
+		block_id: &BlockId,
+		para_id: ParaId,
+	) -> Result<Option<CommittedCandidateReceipt>, ApiError> {
+		block_on(self.call_runtime_api(
+			"ParachainHost_candidate_pending_availability",
+			block_id,
+			para_id,
+		))
+		.map_err(api_error)
+	}
+
+	fn session_index_for_child(&self, block_id: &BlockId) -> Result<SessionIndex, ApiError> {
+		block_on(self.call_runtime_api("ParachainHost_session_index_for_child", block_id, ()))
+			.map_err(api_error)
+	}
+
+	fn validators(&self, block_id: &BlockId) -> Result<Vec<ValidatorId>, ApiError> {
+		block_on(self.call_runtime_api("ParachainHost_validators", block_id, ())).map_err(api_error)
+	}
+
+	fn import_notification_stream(&self) -> sc_client_api::ImportNotifications<PBlock> {
+		let (sink, stream) = tracing_unbounded("mpsc_relay_chain_rpc_import_notification_stream");
+		self.import_sinks.lock().push(sink);
+		stream
+	}
+
+	fn finality_notification_stream(&self) -> sc_client_api::FinalityNotifications<PBlock> {
+		let (sink, stream) = tracing_unbounded("mpsc_relay_chain_rpc_finality_notification_stream");
+		self.finality_sinks.lock().push(sink);
+		stream
+	}
+
+	fn storage_changes_notification_stream(
+		&self,
+		_: Option<&[sc_client_api::StorageKey]>,
+		_: Option<&[(sc_client_api::StorageKey, Option<Vec<sc_client_api::StorageKey>>)]>,
+	) -> sc_client_api::blockchain::Result<sc_client_api::StorageEventStream<PHash>> {
+		Err(sp_blockchain::Error::Backend(
+			"Storage change notifications are not supported by the RPC relay chain interface."
+				.into(),
+		))
+	}
+
+	fn best_block_hash(&self) -> PHash {
+		*self.best_hash.lock()
+	}
+
+	fn block_status(&self, block_id: BlockId) -> Result<BlockStatus, sp_blockchain::Error> {
+		Ok(match self.header(block_id)? {
+			Some(_) => BlockStatus::InChain,
+			None => BlockStatus::Unknown,
+		})
+	}
+
+	fn header(&self, block_id: BlockId) -> Result<Option<PHeader>, sp_blockchain::Error> {
+		block_on(async {
+			match self.block_hash(&block_id).await? {
+				Some(hash) => self.header_by_hash(hash).await,
+				None => Ok(None),
+			}
+		})
+		.map_err(blockchain_error)
+	}
+
+	fn is_major_syncing(&self) -> bool {
+		block_on(self.request::<Health>("system_health", None))
+			.map(|health| health.is_syncing)
+			.unwrap_or_else(|e| {
+				tracing::error!(
+					target: LOG_TARGET,
+					error = ?e,
+					"Failed to fetch the sync status of the relay chain node.",
+				);
+				false
+			})
+	}
+
+	/// There is no overseer available over RPC, so this always returns `None`.
+	fn overseer_handle(&self) -> Option<OverseerHandle> {
+		None
+	}
+
+	fn get_storage_by_key(
+		&self,
+		block_id: &BlockId,
+		key: &[u8],
+	) -> Result<Option<StorageValue>, sp_blockchain::Error> {
+		block_on(async {
+			let at = self.block_hash_or_err(block_id).await?;
+			let storage: Option<StorageData> = self
+				.request("state_getStorage", rpc_params![StorageKey(key.to_vec()), at])
+				.await?;
+
+			Ok(storage.map(|storage| storage.0))
+		})
+		.map_err(blockchain_error)
+	}
+
+	fn prove_read(
+		&self,
+		block_id: &BlockId,
+		relevant_keys: &Vec<Vec<u8>>,
+	) -> Result<Option<StorageProof>, Box<dyn sp_state_machine::Error>> {
+		let keys = relevant_keys.iter().cloned().map(StorageKey).collect::<Vec<_>>();
+
+		block_on(async {
+			let at = self.block_hash_or_err(block_id).await?;
+			self.request::<ReadProof<PHash>>("state_getReadProof", rpc_params![keys, at])
+				.await
+		})
+		.map(|read_proof| {
+			Some(StorageProof::new(read_proof.proof.into_iter().map(|node| node.0).collect()))
+		})
+		.map_err(|e| {
+			tracing::error!(
+				target: LOG_TARGET,
+				relay_parent = ?block_id,
+				error = ?e,
+				"Failed to collect required relay chain state storage proof.",
+			);
+			Box::new(e) as Box<dyn sp_state_machine::Error>
+		})
+	}
+
+	/// Wait for a given relay chain block.
+	///
+	/// An import notification listener is registered before asking the relay chain node for the
+	/// block, so the block can't be missed in between. The waiting is aborted after 6 seconds, like
+	/// for the in-process relay chain node.
+	async fn wait_for_block(&self, hash: PHash) -> Result<(), WaitError> {
+		let mut listener = self.import_notification_stream();
+
+		match self.header_by_hash(hash).await {
+			Ok(Some(_)) => return Ok(()),
+			Ok(None) => {},
+			Err(e) => return Err(WaitError::BlockchainError(hash, blockchain_error(e))),
+		}
+
+		let mut timeout = futures_timer::Delay::new(Duration::from_secs(TIMEOUT_IN_SECONDS)).fuse();
+
+		loop {
+			futures::select! {
+				_ = timeout => return Err(WaitError::Timeout(hash)),
+				evt = listener.next() => match evt {
+					Some(evt) if evt.hash == hash => return Ok(()),
+					// Not the event we waited on.
+					Some(_) => continue,
+					None => return Err(WaitError::ImportListenerClosed(hash)),
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use jsonrpsee::{
+		types::Params,
+		ws_server::{WsServerBuilder, WsServerHandle},
+		RpcModule,
+	};
+
+	fn header(number: u32) -> PHeader {
+		PHeader::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	/// A block on a fork next to the blocks returned by [`header`].
+	fn fork_header(number: u32) -> PHeader {
+		PHeader::new(
+			number,
+			Default::default(),
+			PHash::repeat_byte(1),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	/// Start a mock relay chain node that imports `imported` shortly after the all heads
+	/// subscription was made.
+	///
+	/// The blocks that are flagged become the best block of the mock node when they are imported.
+	async fn start_mock_node(imported: Vec<(PHeader, bool)>) -> (String, WsServerHandle) {
+		let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", server.local_addr().unwrap());
+		let genesis = header(0);
+		let genesis_hash = genesis.hash();
+
+		let mut module = RpcModule::new(Mutex::new(genesis_hash));
+		module
+			.register_method("chain_getBlockHash", |_, best_hash| Ok(Some(*best_hash.lock())))
+			.unwrap();
+		module
+			.register_method("chain_getHeader", move |params: Params, _| {
+				let hash: PHash = params.one()?;
+				Ok((hash == genesis_hash).then(|| genesis.clone()))
+			})
+			.unwrap();
+		module
+			.register_method("state_getStorage", |params: Params, _| {
+				let mut params = params.sequence();
+				let key: StorageKey = params.next()?;
+				Ok((key.0 == b"key".to_vec()).then(|| StorageData(b"value".to_vec())))
+			})
+			.unwrap();
+		module
+			.register_method("state_call", |params: Params, _| {
+				let mut params = params.sequence();
+				let method: String = params.next()?;
+				let args: Bytes = params.next()?;
+				assert_eq!(method, "ParachainHost_persisted_validation_data");
+				assert_eq!(args.0, (ParaId::from(100), OccupiedCoreAssumption::Included).encode());
+
+				let validation_data =
+					PersistedValidationData { relay_parent_number: 7, ..Default::default() };
+				Ok(Bytes(Some(validation_data).encode()))
+			})
+			.unwrap();
+		module
+			.register_method("system_health", |_, _| {
+				Ok(Health { peers: 1, is_syncing: true, should_have_peers: true })
+			})
+			.unwrap();
+		module
+			.register_subscription(
+				"chain_subscribeAllHeads",
+				"chain_allHead",
+				"chain_unsubscribeAllHeads",
+				move |_, mut sink, best_hash| {
+					let imported = imported.clone();
+					tokio::spawn(async move {
+						tokio::time::sleep(Duration::from_millis(500)).await;
+						for (header, is_new_best) in imported {
+							if is_new_best {
+								*best_hash.lock() = header.hash();
+							}
+							let _ = sink.send(&header);
+						}
+						// Keep the subscription open.
+						futures::future::pending::<()>().await;
+					});
+					Ok(())
+				},
+			)
+			.unwrap();
+		module
+			.register_subscription(
+				"chain_subscribeFinalizedHeads",
+				"chain_finalizedHead",
+				"chain_unsubscribeFinalizedHeads",
+				|_, sink, _| {
+					tokio::spawn(async move {
+						let _sink = sink;
+						futures::future::pending::<()>().await;
+					});
+					Ok(())
+				},
+			)
+			.unwrap();
+
+		(url, server.start(module).unwrap())
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn forwards_requests_to_the_relay_chain_node() {
+		let (url, _server) = start_mock_node(vec![(header(1), true)]).await;
+		let interface = RelayChainRPCInterface::new(&url).await.unwrap();
+		let at = BlockId::Hash(header(0).hash());
+
+		assert_eq!(interface.best_block_hash(), header(0).hash());
+		assert_eq!(interface.get_storage_by_key(&at, b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(interface.get_storage_by_key(&at, b"other").unwrap(), None);
+		assert_eq!(
+			interface
+				.persisted_validation_data(&at, ParaId::from(100), OccupiedCoreAssumption::Included)
+				.unwrap()
+				.map(|data| data.relay_parent_number),
+			Some(7),
+		);
+		assert!(matches!(interface.block_status(at), Ok(BlockStatus::InChain)));
+		assert!(matches!(
+			interface.block_status(BlockId::Hash(PHash::repeat_byte(1))),
+			Ok(BlockStatus::Unknown)
+		));
+		assert!(interface.is_major_syncing());
+		assert!(interface.overseer_handle().is_none());
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn wait_for_block_resolves_on_new_head() {
+		let new_head = header(1);
+		let (url, _server) = start_mock_node(vec![(new_head.clone(), true)]).await;
+		let interface = RelayChainRPCInterface::new(&url).await.unwrap();
+
+		interface.wait_for_block(new_head.hash()).await.unwrap();
+		assert_eq!(interface.best_block_hash(), new_head.hash());
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn wait_for_block_resolves_on_fork_block() {
+		let (url, _server) =
+			start_mock_node(vec![(header(1), true), (fork_header(1), false)]).await;
+		let interface = RelayChainRPCInterface::new(&url).await.unwrap();
+		let mut imports = interface.import_notification_stream();
+
+		interface.wait_for_block(fork_header(1).hash()).await.unwrap();
+		assert_eq!(interface.best_block_hash(), header(1).hash());
+
+		let imported = imports.next().await.unwrap();
+		assert_eq!((imported.hash, imported.is_new_best), (header(1).hash(), true));
+		let imported = imports.next().await.unwrap();
+		assert_eq!((imported.hash, imported.is_new_best), (fork_header(1).hash(), false));
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn wait_for_block_returns_directly_for_known_block() {
+		let (url, _server) = start_mock_node(vec![(header(1), true)]).await;
+		let interface = RelayChainRPCInterface::new(&url).await.unwrap();
+
+		interface.wait_for_block(header(0).hash()).await.unwrap();
+	}
+}
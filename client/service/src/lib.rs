@@ -19,6 +19,7 @@
 //! Provides functions for starting a collator node or a normal full node.
 
 use cumulus_client_consensus_common::ParachainConsensus;
+use cumulus_client_pov_recovery::{PoVRecovery, PoVRecoveryConfig};
 use cumulus_primitives_core::{CollectCollationInfo, ParaId};
 use cumulus_relay_chain_interface::RelayChainInterface;
 use polkadot_primitives::v1::CollatorPair;
@@ -39,6 +40,7 @@ use sp_runtime::{
 	Justifications,
 };
 use std::{sync::Arc, time::Duration};
+use substrate_prometheus_endpoint::Registry;
 
 pub mod genesis;
 
@@ -55,6 +57,7 @@ pub struct StartCollatorParams<'a, Block: BlockT, BS, Client, RCInterface, Spawn
 	pub import_queue: IQ,
 	pub collator_key: CollatorPair,
 	pub slot_duration: Duration,
+	pub prometheus_registry: Option<&'a Registry>,
 }
 
 /// Start a collator node for a parachain.
@@ -75,6 +78,7 @@ pub async fn start_collator<'a, Block, BS, Client, Backend, RCInterface, Spawner
 		import_queue,
 		collator_key,
 		slot_duration,
+		prometheus_registry,
 	}: StartCollatorParams<'a, Block, BS, Client, RCInterface, Spawner, IQ>,
 ) -> sc_service::error::Result<()>
 where
@@ -107,15 +111,16 @@ where
 		.spawn_essential_handle()
 		.spawn("cumulus-consensus", None, consensus);
 
-	let pov_recovery = cumulus_client_pov_recovery::PoVRecovery::new(
+	let pov_recovery = PoVRecovery::new(
 		relay_chain_interface
 			.overseer_handle()
 			.ok_or_else(|| "Polkadot full node did not provide an `OverseerHandle`!")?,
-		slot_duration,
+		PoVRecoveryConfig::collator(slot_duration),
 		client.clone(),
 		import_queue,
 		relay_chain_interface.clone(),
 		para_id,
+		prometheus_registry,
 	);
 
 	task_manager
@@ -140,26 +145,34 @@ where
 }
 
 /// Parameters given to [`start_full_node`].
-pub struct StartFullNodeParams<'a, Block: BlockT, Client, RCInterface> {
+pub struct StartFullNodeParams<'a, Block: BlockT, Client, RCInterface, IQ> {
 	pub para_id: ParaId,
 	pub client: Arc<Client>,
 	pub relay_chain_interface: RCInterface,
 	pub task_manager: &'a mut TaskManager,
 	pub announce_block: Arc<dyn Fn(Block::Hash, Option<Vec<u8>>) + Send + Sync>,
+	pub import_queue: IQ,
+	/// Run the PoV recovery with the given configuration, `None` disables it.
+	pub pov_recovery: Option<PoVRecoveryConfig>,
+	pub prometheus_registry: Option<&'a Registry>,
 }
 
 /// Start a full node for a parachain.
 ///
 /// A full node will only sync the given parachain and will follow the
-/// tip of the chain.
-pub fn start_full_node<Block, Client, Backend, RCInterface>(
+/// tip of the chain. If enabled, it also recovers blocks that were included
+/// in the relay chain, but never announced by their collator.
+pub fn start_full_node<Block, Client, Backend, RCInterface, IQ>(
 	StartFullNodeParams {
 		client,
 		announce_block,
 		task_manager,
 		relay_chain_interface,
 		para_id,
-	}: StartFullNodeParams<Block, Client, RCInterface>,
+		import_queue,
+		pov_recovery,
+		prometheus_registry,
+	}: StartFullNodeParams<Block, Client, RCInterface, IQ>,
 ) -> sc_service::error::Result<()>
 where
 	Block: BlockT,
@@ -173,6 +186,7 @@ where
 	for<'a> &'a Client: BlockImport<Block>,
 	Backend: BackendT<Block> + 'static,
 	RCInterface: RelayChainInterface + Clone + 'static,
+	IQ: ImportQueue<Block> + 'static,
 {
 	let consensus = cumulus_client_consensus_common::run_parachain_consensus(
 		para_id,
@@ -185,6 +199,26 @@ where
 		.spawn_essential_handle()
 		.spawn("cumulus-consensus", None, consensus);
 
+	if let Some(config) = pov_recovery {
+		let pov_recovery = PoVRecovery::new(
+			relay_chain_interface.overseer_handle().ok_or_else(|| {
+				"PoV recovery needs an embedded relay chain node providing an `OverseerHandle`!"
+			})?,
+			config,
+			client,
+			import_queue,
+			relay_chain_interface,
+			para_id,
+			prometheus_registry,
+		);
+
+		task_manager.spawn_essential_handle().spawn(
+			"cumulus-pov-recovery",
+			None,
+			pov_recovery.run(),
+		);
+	}
+
 	Ok(())
 }
 
@@ -0,0 +1,82 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for inspecting the queue of the DMP queue pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use cumulus_pallet_dmp_queue_rpc_runtime_api::{
+	DmpQueueApi as DmpQueueRuntimeApi, OverweightIndex, QueueInfo,
+};
+
+/// Error code returned when the runtime api call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// DMP queue RPC methods.
+#[rpc]
+pub trait DmpQueueApi<BlockHash> {
+	/// Summary of the downward messages waiting to be executed.
+	#[rpc(name = "dmpQueue_queueInfo")]
+	fn queue_info(&self, at: Option<BlockHash>) -> Result<QueueInfo>;
+
+	/// The indices of the messages in the overweight queue.
+	#[rpc(name = "dmpQueue_overweightIndices")]
+	fn overweight_indices(&self, at: Option<BlockHash>) -> Result<Vec<OverweightIndex>>;
+}
+
+/// Implements the [`DmpQueueApi`] RPC trait for inspecting the DMP queue.
+pub struct DmpQueue<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> DmpQueue<C, B> {
+	/// Create new `DmpQueue` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: PhantomData }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the DMP queue.".into(),
+		data: Some(e.to_string().into()),
+	}
+}
+
+impl<C, Block> DmpQueueApi<<Block as BlockT>::Hash> for DmpQueue<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DmpQueueRuntimeApi<Block>,
+{
+	fn queue_info(&self, at: Option<Block::Hash>) -> Result<QueueInfo> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().queue_info(&at).map_err(runtime_error)
+	}
+
+	fn overweight_indices(&self, at: Option<Block::Hash>) -> Result<Vec<OverweightIndex>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().overweight_indices(&at).map_err(runtime_error)
+	}
+}
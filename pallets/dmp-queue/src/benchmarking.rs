@@ -0,0 +1,112 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-dmp-queue
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::EnsureOrigin;
+
+/// The maximum number of messages in a page.
+const MAX_PAGE_MESSAGES: u32 = 1_000;
+
+/// An XCM that doesn't do anything. It weighs nothing, so it passes the usual barriers.
+fn empty_xcm<T: Config>() -> Vec<u8> {
+	VersionedXcm::<T::Call>::from(Xcm::<T::Call>(vec![])).encode()
+}
+
+/// An XCM that needs more than no weight, so it can't be executed without any weight.
+fn heavy_xcm<T: Config>() -> Vec<u8> {
+	VersionedXcm::<T::Call>::from(Xcm::<T::Call>(vec![Transact {
+		origin_type: OriginKind::Native,
+		require_weight_at_most: 1,
+		call: Vec::new().into(),
+	}]))
+	.encode()
+}
+
+/// Put `n` empty XCMs into the overweight queue.
+fn stash_overweights<T: Config>(n: u32) {
+	for index in 0..n as OverweightIndex {
+		Overweight::<T>::insert(index, (1, empty_xcm::<T>()));
+	}
+	PageIndex::<T>::mutate(|page_index| page_index.overweight_count = n as OverweightIndex);
+}
+
+benchmarks! {
+	service_overweight {
+		stash_overweights::<T>(1);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, 0, 0)
+	verify {
+		assert!(!Overweight::<T>::contains_key(0));
+	}
+
+	discard_overweight {
+		stash_overweights::<T>(1);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, 0, false)
+	verify {
+		assert!(!Overweight::<T>::contains_key(0));
+	}
+
+	discard_overweights {
+		let n in 1 .. T::MaxDiscard::get();
+		stash_overweights::<T>(n);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, (0..n as OverweightIndex).collect(), false)
+	verify {
+		assert_eq!(Overweight::<T>::iter().count(), 0);
+	}
+
+	update_max_individual {
+		let origin = T::ControllerOrigin::successful_origin();
+	}: _<T::Origin>(origin, 1_000_000_000)
+	verify {
+		assert_eq!(Configuration::<T>::get().max_individual, 1_000_000_000);
+	}
+
+	service_queue {
+	}: {
+		Pallet::<T>::service_queue(0);
+	}
+
+	service_page {
+		let m in 1 .. MAX_PAGE_MESSAGES;
+		let page = (0..m).map(|_| (1, empty_xcm::<T>())).collect::<Vec<_>>();
+		Pages::<T>::insert(0, page);
+		PageIndex::<T>::mutate(|page_index| page_index.end_used = 1);
+	}: {
+		Pallet::<T>::service_queue(Weight::max_value());
+	}
+	verify {
+		assert_eq!(PageIndex::<T>::get().end_used, 0);
+	}
+
+	// No weight is available, so all of the messages are put into a page.
+	enqueue_page {
+		let m in 1 .. MAX_PAGE_MESSAGES;
+		let messages = (0..m).map(|_| (1, heavy_xcm::<T>())).collect::<Vec<_>>();
+	}: {
+		Pallet::<T>::handle_dmp_messages(messages.into_iter(), 0);
+	}
+	verify {
+		assert_eq!(Pages::<T>::get(0).len(), m as usize);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::tests::new_test_ext(), crate::tests::Test);
@@ -21,6 +21,10 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
 use codec::{Decode, DecodeLimit, Encode};
 use cumulus_primitives_core::{relay_chain::BlockNumber as RelayBlockNumber, DmpMessageHandler};
 use frame_support::{
@@ -31,6 +35,9 @@ use scale_info::TypeInfo;
 use sp_runtime::RuntimeDebug;
 use sp_std::{convert::TryFrom, prelude::*};
 use xcm::{latest::prelude::*, VersionedXcm, MAX_XCM_DECODE_DEPTH};
+use xcm_executor::traits::{DropAssets, FilterAssetLocation, TransactAsset};
+
+pub use weights::WeightInfo;
 
 #[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
 pub struct ConfigData {
@@ -40,6 +47,13 @@ pub struct ConfigData {
 	max_individual: Weight,
 }
 
+impl ConfigData {
+	/// Whether the configuration can be used to service the queue.
+	pub fn is_valid(&self) -> bool {
+		self.max_individual > 0
+	}
+}
+
 impl Default for ConfigData {
 	fn default() -> Self {
 		Self {
@@ -59,6 +73,21 @@ pub struct PageIndexData {
 	overweight_count: OverweightIndex,
 }
 
+/// Summary of the queued downward messages, as reported by the `DmpQueueApi` runtime API.
+#[derive(Clone, Eq, PartialEq, Default, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct QueueInfo {
+	/// The number of pages in the queue.
+	pub pages: PageCounter,
+	/// The number of messages in all pages of the queue.
+	pub messages: u32,
+	/// The total size in bytes of the messages in the queue.
+	pub bytes: u32,
+	/// The relay block number at which the oldest message in the queue was sent, if any.
+	pub oldest_sent_at: Option<RelayBlockNumber>,
+}
+
 /// Simple type used to identify messages for the purpose of reporting events. Secure if and only
 /// if the message content is unique.
 pub type MessageId = [u8; 32];
@@ -89,6 +118,59 @@ pub mod pallet {
 
 		/// Origin which is allowed to execute overweight messages.
 		type ExecuteOverweightOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Origin which is allowed to change the queue `Configuration`.
+		type ControllerOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Where assets carried by discarded overweight messages are trapped, if requested.
+		type AssetTrap: DropAssets;
+
+		/// Which assets the relay chain may deposit as their reserve. Should be the XCM executor's
+		/// `IsReserve`, assets of discarded messages which don't pass it are not trapped.
+		type IsReserve: FilterAssetLocation;
+
+		/// Which assets the relay chain may teleport to us. Should be the XCM executor's
+		/// `IsTeleporter`, assets of discarded messages which don't pass it are not trapped.
+		type IsTeleporter: FilterAssetLocation;
+
+		/// Used to check in the teleported assets of discarded messages before they are trapped.
+		/// Should be the XCM executor's `AssetTransactor`.
+		type AssetTransactor: TransactAsset;
+
+		/// The maximum weight of trapping the assets of a single discarded message, including the
+		/// check in of teleported assets.
+		#[pallet::constant]
+		type TrapAssetsWeight: Get<Weight>;
+
+		/// The maximum number of overweight messages which can be discarded by a single call to
+		/// `discard_overweights`.
+		#[pallet::constant]
+		type MaxDiscard: Get<u32>;
+
+		/// Weight information for the extrinsics and the queue servicing of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		/// The maximum amount of weight any individual message may consume.
+		pub max_individual: Weight,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self { max_individual: ConfigData::default().max_individual }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			let config = ConfigData { max_individual: self.max_individual };
+			assert!(config.is_valid(), "invalid dmp queue configuration in genesis");
+			Configuration::<T>::put(config);
+		}
 	}
 
 	/// The configuration.
@@ -115,6 +197,10 @@ pub mod pallet {
 		Unknown,
 		/// The amount of weight given is possibly not enough for executing the message.
 		OverLimit,
+		/// The new configuration is invalid, e.g. the maximum individual weight is zero.
+		BadConfig,
+		/// More overweight messages than `MaxDiscard` are to be discarded at once.
+		TooManyIndices,
 	}
 
 	#[pallet::hooks]
@@ -139,7 +225,7 @@ pub mod pallet {
 		///
 		/// Events:
 		/// - `OverweightServiced`: On success.
-		#[pallet::weight(weight_limit.saturating_add(1_000_000))]
+		#[pallet::weight(weight_limit.saturating_add(T::WeightInfo::service_overweight()))]
 		pub fn service_overweight(
 			origin: OriginFor<T>,
 			index: OverweightIndex,
@@ -152,7 +238,97 @@ pub mod pallet {
 				.map_err(|_| Error::<T>::OverLimit)?;
 			Overweight::<T>::remove(index);
 			Self::deposit_event(Event::OverweightServiced(index, used));
-			Ok(Some(used.saturating_add(1_000_000)).into())
+			Ok(Some(used.saturating_add(T::WeightInfo::service_overweight())).into())
+		}
+
+		/// Discard a single overweight message without executing it.
+		///
+		/// - `origin`: Must pass `ExecuteOverweightOrigin`.
+		/// - `index`: The index of the overweight message to discard.
+		/// - `trap_assets`: Whether the assets the message would have placed into holding should
+		///   be handed to `AssetTrap`, so that they can be claimed later. The assets are only
+		///   trapped if they pass the same reserve and teleport checks as when the message is
+		///   executed.
+		///
+		/// Errors:
+		/// - `Unknown`: Message of `index` is unknown.
+		///
+		/// Events:
+		/// - `OverweightDiscarded`: On success.
+		#[pallet::weight(
+			T::WeightInfo::discard_overweight()
+				.saturating_add(Pallet::<T>::trap_weight(1, *trap_assets))
+		)]
+		pub fn discard_overweight(
+			origin: OriginFor<T>,
+			index: OverweightIndex,
+			trap_assets: bool,
+		) -> DispatchResultWithPostInfo {
+			T::ExecuteOverweightOrigin::ensure_origin(origin)?;
+
+			let (_, data) = Overweight::<T>::take(index).ok_or(Error::<T>::Unknown)?;
+			let used = if trap_assets { Self::trap_overweight_assets(&data[..]) } else { 0 };
+			Self::deposit_event(Event::OverweightDiscarded(index));
+			Ok(Some(T::WeightInfo::discard_overweight().saturating_add(used)).into())
+		}
+
+		/// Discard a number of overweight messages without executing them. Unknown indices are
+		/// skipped.
+		///
+		/// - `origin`: Must pass `ExecuteOverweightOrigin`.
+		/// - `indices`: The indices of the overweight messages to discard, at most `MaxDiscard`.
+		/// - `trap_assets`: As for `discard_overweight`.
+		///
+		/// Errors:
+		/// - `TooManyIndices`: More than `MaxDiscard` indices are given.
+		///
+		/// Events:
+		/// - `OverweightDiscarded`: For each discarded message.
+		#[pallet::weight(
+			T::WeightInfo::discard_overweights(indices.len() as u32)
+				.saturating_add(Pallet::<T>::trap_weight(indices.len() as u32, *trap_assets))
+		)]
+		pub fn discard_overweights(
+			origin: OriginFor<T>,
+			indices: Vec<OverweightIndex>,
+			trap_assets: bool,
+		) -> DispatchResultWithPostInfo {
+			T::ExecuteOverweightOrigin::ensure_origin(origin)?;
+			ensure!(indices.len() as u32 <= T::MaxDiscard::get(), Error::<T>::TooManyIndices);
+
+			let mut used = T::WeightInfo::discard_overweights(indices.len() as u32);
+			for index in indices {
+				if let Some((_, data)) = Overweight::<T>::take(index) {
+					if trap_assets {
+						used = used.saturating_add(Self::trap_overweight_assets(&data[..]));
+					}
+					Self::deposit_event(Event::OverweightDiscarded(index));
+				}
+			}
+			Ok(Some(used).into())
+		}
+
+		/// Overwrite the maximum amount of weight any individual message may consume. Messages
+		/// above this weight go into the overweight queue.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `ConfigData.max_individual`.
+		///
+		/// Errors:
+		/// - `BadConfig`: `new` is zero.
+		///
+		/// Events:
+		/// - `MaxIndividualUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::update_max_individual(), DispatchClass::Operational))]
+		pub fn update_max_individual(origin: OriginFor<T>, new: Weight) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Configuration::<T>::try_mutate(|config| -> DispatchResult {
+				config.max_individual = new;
+				ensure!(config.is_valid(), Error::<T>::BadConfig);
+				Ok(())
+			})?;
+			Self::deposit_event(Event::MaxIndividualUpdated(new));
+			Ok(())
 		}
 	}
 
@@ -177,14 +353,49 @@ pub mod pallet {
 		/// Downward message from the overweight queue was executed.
 		/// \[ index, used \]
 		OverweightServiced(OverweightIndex, Weight),
+		/// Downward message from the overweight queue was discarded without being executed.
+		/// \[ index \]
+		OverweightDiscarded(OverweightIndex),
+		/// The maximum weight of an individual message was changed.
+		/// \[ new \]
+		MaxIndividualUpdated(Weight),
 	}
 
 	impl<T: Config> Pallet<T> {
+		/// Summary of the messages which are waiting in the queue.
+		pub fn queue_info() -> QueueInfo {
+			let page_index = PageIndex::<T>::get();
+			let mut info = QueueInfo {
+				pages: page_index.end_used - page_index.begin_used,
+				..Default::default()
+			};
+			for page in (page_index.begin_used..page_index.end_used).map(Pages::<T>::get) {
+				if info.oldest_sent_at.is_none() {
+					info.oldest_sent_at = page.first().map(|(sent_at, _)| *sent_at);
+				}
+				info.messages += page.len() as u32;
+				info.bytes += page.iter().map(|(_, data)| data.len() as u32).sum::<u32>();
+			}
+			info
+		}
+
+		/// The indices of all messages in the overweight queue, in ascending order.
+		pub fn overweight_indices() -> Vec<OverweightIndex> {
+			let mut indices = Overweight::<T>::iter_keys().collect::<Vec<_>>();
+			indices.sort();
+			indices
+		}
+
 		/// Service the message queue up to some given weight `limit`.
 		///
-		/// Returns the weight consumed by executing messages in the queue.
-		fn service_queue(limit: Weight) -> Weight {
-			PageIndex::<T>::mutate(|page_index| Self::do_service_queue(limit, page_index))
+		/// Returns the weight consumed by executing messages in the queue, including the
+		/// bookkeeping of the queue.
+		pub(crate) fn service_queue(limit: Weight) -> Weight {
+			let overhead = T::WeightInfo::service_queue();
+			let limit = limit.saturating_sub(overhead);
+			overhead.saturating_add(PageIndex::<T>::mutate(|page_index| {
+				Self::do_service_queue(limit, page_index)
+			}))
 		}
 
 		/// Exactly equivalent to `service_queue` but expects a mutable `page_index` to be passed
@@ -193,6 +404,7 @@ pub mod pallet {
 			let mut used = 0;
 			while page_index.begin_used < page_index.end_used {
 				let page = Pages::<T>::take(page_index.begin_used);
+				used = used.saturating_add(T::WeightInfo::service_page(page.len() as u32));
 				for (i, &(sent_at, ref data)) in page.iter().enumerate() {
 					match Self::try_service_message(limit.saturating_sub(used), sent_at, &data[..])
 					{
@@ -214,6 +426,63 @@ pub mod pallet {
 			used
 		}
 
+		/// The maximum weight of trapping the assets of `n` discarded overweight messages, which
+		/// is zero unless `trap_assets` is set.
+		fn trap_weight(n: u32, trap_assets: bool) -> Weight {
+			if trap_assets {
+				T::TrapAssetsWeight::get().saturating_mul(n as Weight)
+			} else {
+				0
+			}
+		}
+
+		/// Hand the assets which the overweight message `data` would have placed into the holding
+		/// register over to `T::AssetTrap`, returning the weight used.
+		///
+		/// Only assets arriving with the message (`ReserveAssetDeposited` or
+		/// `ReceiveTeleportedAsset`) are trapped, and only if they pass the same checks as when the
+		/// message is executed: deposited assets must pass `T::IsReserve`, teleported assets must
+		/// pass `T::IsTeleporter` and are checked in with `T::AssetTransactor`. If any of the
+		/// assets fails these checks, nothing is trapped.
+		fn trap_overweight_assets(data: &[u8]) -> Weight {
+			let xcm = VersionedXcm::<()>::decode_all_with_depth_limit(
+				MAX_XCM_DECODE_DEPTH,
+				&mut &data[..],
+			)
+			.ok()
+			.and_then(|xcm| Xcm::<()>::try_from(xcm).ok());
+			let origin = MultiLocation::parent();
+			let assets = match xcm.and_then(|xcm| xcm.0.into_iter().next()) {
+				Some(ReserveAssetDeposited(assets)) => {
+					if !assets
+						.inner()
+						.iter()
+						.all(|asset| T::IsReserve::filter_asset_location(asset, &origin))
+					{
+						return 0
+					}
+					assets
+				},
+				Some(ReceiveTeleportedAsset(assets)) => {
+					if !assets.inner().iter().all(|asset| {
+						T::IsTeleporter::filter_asset_location(asset, &origin) &&
+							T::AssetTransactor::can_check_in(&origin, asset).is_ok()
+					}) {
+						return 0
+					}
+					for asset in assets.inner() {
+						T::AssetTransactor::check_in(&origin, asset);
+					}
+					assets
+				},
+				_ => return 0,
+			};
+			T::AssetTrap::drop_assets(&origin, assets.into());
+			// The weight reported by the trap doesn't cover the check in, so we always account
+			// for the maximum.
+			T::TrapAssetsWeight::get()
+		}
+
 		/// Attempt to service an individual message. Will return `Ok` with the execution weight
 		/// consumed unless the message was found to need more weight than `limit`.
 		///
@@ -267,7 +536,8 @@ pub mod pallet {
 			let config = Configuration::<T>::get();
 
 			// First try to use `max_weight` to service the current queue.
-			let mut used = Self::do_service_queue(limit, &mut page_index);
+			let mut used = T::WeightInfo::service_queue();
+			used += Self::do_service_queue(limit.saturating_sub(used), &mut page_index);
 
 			// Then if the queue is empty, use the weight remaining to service the incoming messages
 			// and once we run out of weight, place them in the queue.
@@ -318,6 +588,7 @@ pub mod pallet {
 
 			// Deposit the enqueued page if any and save the index.
 			if let Some(enqueue_page) = maybe_enqueue_page {
+				used = used.saturating_add(T::WeightInfo::enqueue_page(enqueue_page.len() as u32));
 				Pages::<T>::insert(page_index.end_used, enqueue_page);
 				page_index.end_used += 1;
 			}
@@ -335,7 +606,10 @@ mod tests {
 
 	use codec::Encode;
 	use cumulus_primitives_core::ParaId;
-	use frame_support::{assert_noop, parameter_types, traits::OnIdle};
+	use frame_support::{
+		assert_noop, assert_ok, parameter_types,
+		traits::{GenesisBuild, OnIdle},
+	};
 	use sp_core::H256;
 	use sp_runtime::{
 		testing::Header,
@@ -343,8 +617,8 @@ mod tests {
 		DispatchError::BadOrigin,
 	};
 	use sp_version::RuntimeVersion;
-	use std::cell::RefCell;
-	use xcm::latest::{MultiLocation, OriginKind};
+	use std::cell::{Cell, RefCell};
+	use xcm::latest::{MultiAsset, MultiAssets, MultiLocation, OriginKind};
 
 	type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 	type Block = frame_system::mocking::MockBlock<Test>;
@@ -357,7 +631,7 @@ mod tests {
 			UncheckedExtrinsic = UncheckedExtrinsic,
 		{
 			System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-			DmpQueue: dmp_queue::{Pallet, Call, Storage, Event<T>},
+			DmpQueue: dmp_queue::{Pallet, Call, Config, Storage, Event<T>},
 		}
 	);
 
@@ -443,10 +717,91 @@ mod tests {
 		}
 	}
 
+	thread_local! {
+		pub static TRAPPED: RefCell<Vec<(MultiLocation, Vec<MultiAsset>)>> =
+			RefCell::new(Vec::new());
+	}
+	pub fn take_trapped() -> Vec<(MultiLocation, Vec<MultiAsset>)> {
+		TRAPPED.with(|t| t.take())
+	}
+
+	pub struct MockAssetTrap;
+	impl DropAssets for MockAssetTrap {
+		fn drop_assets(origin: &MultiLocation, assets: xcm_executor::Assets) -> Weight {
+			TRAPPED.with(|t| t.borrow_mut().push((origin.clone(), assets.into())));
+			1_000
+		}
+	}
+
+	/// Only trusts the relay chain with its own asset.
+	pub struct RelayAsset;
+	impl FilterAssetLocation for RelayAsset {
+		fn filter_asset_location(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+			*origin == MultiLocation::parent() && asset.id == Concrete(MultiLocation::parent())
+		}
+	}
+
+	thread_local! {
+		pub static CHECKED_IN: RefCell<Vec<MultiAsset>> = RefCell::new(Vec::new());
+	}
+	pub fn take_checked_in() -> Vec<MultiAsset> {
+		CHECKED_IN.with(|c| c.take())
+	}
+
+	/// Asset transactor which only records the assets checked in.
+	pub struct MockAssetTransactor;
+	impl TransactAsset for MockAssetTransactor {
+		fn can_check_in(_origin: &MultiLocation, _what: &MultiAsset) -> Result<(), XcmError> {
+			Ok(())
+		}
+		fn check_in(_origin: &MultiLocation, what: &MultiAsset) {
+			CHECKED_IN.with(|c| c.borrow_mut().push(what.clone()));
+		}
+	}
+
+	thread_local! {
+		pub static PAGE_WEIGHT: Cell<Weight> = Cell::new(0);
+	}
+
+	/// Weights which only charge for servicing pages, as set in `PAGE_WEIGHT`, so that the tests
+	/// can reason about the weight used by the executed messages.
+	pub struct MockWeightInfo;
+	impl WeightInfo for MockWeightInfo {
+		fn service_overweight() -> Weight {
+			0
+		}
+		fn discard_overweight() -> Weight {
+			0
+		}
+		fn discard_overweights(_n: u32) -> Weight {
+			0
+		}
+		fn update_max_individual() -> Weight {
+			0
+		}
+		fn service_queue() -> Weight {
+			0
+		}
+		fn service_page(_m: u32) -> Weight {
+			PAGE_WEIGHT.with(|w| w.get())
+		}
+		fn enqueue_page(_m: u32) -> Weight {
+			0
+		}
+	}
+
 	impl Config for Test {
 		type Event = Event;
 		type XcmExecutor = MockExec;
 		type ExecuteOverweightOrigin = frame_system::EnsureRoot<AccountId>;
+		type ControllerOrigin = frame_system::EnsureRoot<AccountId>;
+		type AssetTrap = MockAssetTrap;
+		type IsReserve = RelayAsset;
+		type IsTeleporter = RelayAsset;
+		type AssetTransactor = MockAssetTransactor;
+		type TrapAssetsWeight = frame_support::traits::ConstU64<1_000>;
+		type MaxDiscard = frame_support::traits::ConstU32<3>;
+		type WeightInfo = MockWeightInfo;
 	}
 
 	pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
@@ -786,4 +1141,176 @@ mod tests {
 			assert_eq!(pages_queued(), 1);
 		});
 	}
+
+	#[test]
+	fn update_max_individual_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_eq!(Configuration::<Test>::get().max_individual, 10 * WEIGHT_PER_MILLIS);
+
+			assert_noop!(DmpQueue::update_max_individual(Origin::signed(1), 9999), BadOrigin);
+			assert_noop!(
+				DmpQueue::update_max_individual(Origin::root(), 0),
+				Error::<Test>::BadConfig
+			);
+
+			assert_ok!(DmpQueue::update_max_individual(Origin::root(), 9999));
+			assert_eq!(Configuration::<Test>::get(), ConfigData { max_individual: 9999 });
+			System::assert_last_event(Event::DmpQueue(dmp_queue::Event::MaxIndividualUpdated(
+				9999,
+			)));
+
+			// The new limit is used for incoming messages right away.
+			let weight_used = handle_messages(&[msg(10000)], 2500);
+			assert_eq!(weight_used, 0);
+			assert_eq!(take_trace(), vec![msg_limit_reached(10000)]);
+			assert_eq!(overweights(), vec![0]);
+		});
+	}
+
+	#[test]
+	fn genesis_config_sets_max_individual() {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisBuild::<Test>::assimilate_storage(
+			&dmp_queue::GenesisConfig { max_individual: 1234 },
+			&mut t,
+		)
+		.unwrap();
+		sp_io::TestExternalities::new(t).execute_with(|| {
+			assert_eq!(Configuration::<Test>::get(), ConfigData { max_individual: 1234 });
+		});
+	}
+
+	#[test]
+	fn overweights_should_be_discardable() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Configuration::<Test>::put(ConfigData { max_individual: 9999 });
+
+			let incoming = vec![msg(10000), msg(10001)];
+			let weight_used = handle_messages(&incoming, 2500);
+			assert_eq!(weight_used, 0);
+			assert_eq!(overweights(), vec![0, 1]);
+			take_trace();
+
+			assert_noop!(DmpQueue::discard_overweight(Origin::signed(1), 0, false), BadOrigin);
+			assert_noop!(
+				DmpQueue::discard_overweight(Origin::root(), 2, false),
+				Error::<Test>::Unknown
+			);
+
+			assert_ok!(DmpQueue::discard_overweight(Origin::root(), 0, false));
+			System::assert_last_event(Event::DmpQueue(dmp_queue::Event::OverweightDiscarded(0)));
+			assert_eq!(overweights(), vec![1]);
+			assert_noop!(
+				DmpQueue::service_overweight(Origin::root(), 0, 20000),
+				Error::<Test>::Unknown
+			);
+
+			// Unknown indices are skipped by the bulk variant.
+			assert_ok!(DmpQueue::discard_overweights(Origin::root(), vec![0, 1, 2], false));
+			assert!(overweights().is_empty());
+			// Nothing got executed.
+			assert!(take_trace().is_empty());
+		});
+	}
+
+	#[test]
+	fn discarding_overweight_can_trap_assets() {
+		new_test_ext().execute_with(|| {
+			let assets: MultiAssets = vec![(Parent, 1_000u128).into()].into();
+			let data = VersionedXcm::<Call>::from(Xcm(vec![
+				ReceiveTeleportedAsset(assets.clone()),
+				ClearOrigin,
+			]))
+			.encode();
+			Overweight::<Test>::insert(0, (0, data.clone()));
+			Overweight::<Test>::insert(1, (0, data));
+
+			assert_ok!(DmpQueue::discard_overweight(Origin::root(), 0, false));
+			assert!(take_trapped().is_empty());
+
+			assert_ok!(DmpQueue::discard_overweight(Origin::root(), 1, true));
+			assert_eq!(take_trapped(), vec![(Parent.into(), assets.clone().drain())]);
+			assert_eq!(take_checked_in(), assets.drain());
+		});
+	}
+
+	#[test]
+	fn discarding_overweight_does_not_trap_untrusted_assets() {
+		new_test_ext().execute_with(|| {
+			// The relay chain is no reserve or teleporter of a sibling's asset.
+			let assets: MultiAssets = vec![((Parent, Parachain(1000)), 1_000u128).into()].into();
+			let reserve =
+				VersionedXcm::<Call>::from(Xcm(vec![ReserveAssetDeposited(assets.clone())]));
+			let teleport = VersionedXcm::<Call>::from(Xcm(vec![ReceiveTeleportedAsset(assets)]));
+			Overweight::<Test>::insert(0, (0, reserve.encode()));
+			Overweight::<Test>::insert(1, (0, teleport.encode()));
+
+			assert_ok!(DmpQueue::discard_overweights(Origin::root(), vec![0, 1], true));
+			assert_eq!(Overweight::<Test>::iter().count(), 0);
+			assert!(take_trapped().is_empty());
+			assert!(take_checked_in().is_empty());
+		});
+	}
+
+	#[test]
+	fn discarding_overweights_is_bounded() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				DmpQueue::discard_overweights(Origin::root(), vec![0, 1, 2, 3], false),
+				Error::<Test>::TooManyIndices,
+			);
+		});
+	}
+
+	#[test]
+	fn queue_info_works() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(DmpQueue::queue_info(), QueueInfo::default());
+
+			let incoming = vec![msg(1000), msg(1001), msg(1002)];
+			let weight_used = handle_messages(&incoming, 999);
+			assert_eq!(weight_used, 0);
+			enqueue(&vec![msg(1003)]);
+			take_trace();
+
+			let bytes = incoming
+				.iter()
+				.chain(&[msg(1003)])
+				.map(|m| VersionedXcm::<Call>::from(m.clone()).encode().len() as u32)
+				.sum();
+			assert_eq!(
+				DmpQueue::queue_info(),
+				QueueInfo { pages: 2, messages: 4, bytes, oldest_sent_at: Some(0) }
+			);
+		});
+	}
+
+	#[test]
+	fn overweight_indices_works() {
+		new_test_ext().execute_with(|| {
+			Configuration::<Test>::put(ConfigData { max_individual: 9999 });
+
+			let incoming = vec![msg(10000), msg(10001), msg(10002)];
+			handle_messages(&incoming, 2500);
+			assert_ok!(DmpQueue::discard_overweight(Origin::root(), 1, false));
+
+			assert_eq!(DmpQueue::overweight_indices(), vec![0, 2]);
+		});
+	}
+
+	#[test]
+	fn serviced_pages_are_weighed() {
+		new_test_ext().execute_with(|| {
+			PAGE_WEIGHT.with(|w| w.set(100));
+			enqueue(&vec![msg(1000)]);
+			enqueue(&vec![msg(1001)]);
+
+			let weight_used = DmpQueue::on_idle(1, 6000);
+			assert_eq!(weight_used, 100 + 1000 + 100 + 1001);
+			assert_eq!(take_trace(), vec![msg_complete(1000), msg_complete(1001)]);
+			assert!(queue_is_empty());
+		});
+	}
 }
@@ -0,0 +1,129 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `cumulus_pallet_dmp_queue`.
+//!
+//! The weights of the servicing paths only cover the bookkeeping of the queue, the weight used
+//! by executing the messages is accounted for separately.
+//!
+//! The values are hand-written estimates, they were NOT generated by the benchmarking CLI. They
+//! need to be replaced with the output of this pallet's benchmarks before they are relied upon.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// The weight info trait for `cumulus_pallet_dmp_queue`.
+pub trait WeightInfo {
+	fn service_overweight() -> Weight;
+	fn discard_overweight() -> Weight;
+	fn discard_overweights(n: u32) -> Weight;
+	fn update_max_individual() -> Weight;
+	/// Reading and writing back the page index when servicing the queue.
+	fn service_queue() -> Weight;
+	/// Taking a single page of `m` messages from the queue.
+	fn service_page(m: u32) -> Weight;
+	/// Putting a page of `m` incoming messages into the queue.
+	fn enqueue_page(m: u32) -> Weight;
+}
+
+/// Weights for `cumulus_pallet_dmp_queue` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn service_overweight() -> Weight {
+		(23_904_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweight() -> Weight {
+		(19_512_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweights(n: u32) -> Weight {
+		(2_985_000 as Weight)
+			.saturating_add((9_207_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	fn update_max_individual() -> Weight {
+		(2_603_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn service_queue() -> Weight {
+		(2_194_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn service_page(m: u32) -> Weight {
+		(8_731_000 as Weight)
+			.saturating_add((1_148_000 as Weight).saturating_mul(m as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn enqueue_page(m: u32) -> Weight {
+		(5_377_000 as Weight)
+			.saturating_add((412_000 as Weight).saturating_mul(m as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn service_overweight() -> Weight {
+		(23_904_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweight() -> Weight {
+		(19_512_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweights(n: u32) -> Weight {
+		(2_985_000 as Weight)
+			.saturating_add((9_207_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	fn update_max_individual() -> Weight {
+		(2_603_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn service_queue() -> Weight {
+		(2_194_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn service_page(m: u32) -> Weight {
+		(8_731_000 as Weight)
+			.saturating_add((1_148_000 as Weight).saturating_mul(m as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn enqueue_page(m: u32) -> Weight {
+		(5_377_000 as Weight)
+			.saturating_add((412_000 as Weight).saturating_mul(m as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
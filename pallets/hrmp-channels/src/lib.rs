@@ -0,0 +1,583 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pallet to manage the HRMP channels of a parachain.
+//!
+//! Channels are opened, accepted and closed by sending `Transact` messages to the `hrmp` pallet
+//! of the relay chain through UMP. The notifications of the relay chain about channels of this
+//! parachain are handled by wrapping the DMP message handler into [`HandleHrmpNotifications`].
+//!
+//! The state of every channel is tracked in [`Channels`]. Channels which are opened or closed on
+//! the relay chain are updated through the [`OnSystemEvent`] hooks of the parachain system pallet,
+//! so the pallet has to be part of its `OnSystemEvent`. The channels that are already open when
+//! the pallet is added are picked up once at the beginning of the first block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, DecodeLimit, Encode};
+use cumulus_pallet_parachain_system::{ChannelDirection, OnSystemEvent};
+use cumulus_primitives_core::{
+	relay_chain::{v1::HrmpChannelId, BlockNumber as RelayBlockNumber},
+	DmpMessageHandler, ParaId, PersistedValidationData,
+};
+use frame_support::{
+	traits::{Contains, Get},
+	weights::{DispatchClass, Weight},
+};
+pub use pallet::*;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::{collections::btree_set::BTreeSet, convert::TryFrom, marker::PhantomData, prelude::*};
+use xcm::{
+	latest::{
+		Instruction, Junction::Parachain, Junctions::Here, MultiAsset, MultiAssetFilter::Wild,
+		OriginKind, Parent, SendXcm, WeightLimit::Unlimited, WildMultiAsset::All, Xcm,
+	},
+	VersionedXcm, MAX_XCM_DECODE_DEPTH,
+};
+
+/// The state of an HRMP channel this parachain takes part in.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum ChannelState {
+	/// We requested to open the channel and wait for the recipient to accept it.
+	Requested,
+	/// The sender requested to open the channel and waits for us to accept it.
+	AwaitingAcceptance,
+	/// The channel was accepted and opens with the next session of the relay chain.
+	Accepted,
+	/// The channel is open.
+	Open,
+	/// The channel was requested to be closed and closes with the next session of the relay
+	/// chain.
+	Closing,
+}
+
+/// The calls of the relay chain's `hrmp` pallet used to manage channels.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum HrmpCall {
+	/// Request to open a channel to the given recipient with the proposed maximum capacity and
+	/// maximum message size.
+	#[codec(index = 0)]
+	InitOpenChannel(ParaId, u32, u32),
+	/// Accept the request of the given sender to open a channel.
+	#[codec(index = 1)]
+	AcceptOpenChannel(ParaId),
+	/// Close the given channel.
+	#[codec(index = 2)]
+	CloseChannel(HrmpChannelId),
+	/// Cancel the request to open the given channel, with the number of open requests on the
+	/// relay chain as witness.
+	#[codec(index = 6)]
+	CancelOpenRequest(HrmpChannelId, u32),
+}
+
+/// Provides the HRMP channels of this parachain that are open as per the relay chain.
+pub trait OpenChannels {
+	/// The senders of the open inbound channels, or `None` if they are not known yet.
+	fn ingress() -> Option<Vec<ParaId>>;
+	/// The recipients of the open outbound channels, or `None` if they are not known yet.
+	fn egress() -> Option<Vec<ParaId>>;
+}
+
+impl<T: cumulus_pallet_parachain_system::Config> OpenChannels
+	for cumulus_pallet_parachain_system::Pallet<T>
+{
+	fn ingress() -> Option<Vec<ParaId>> {
+		Self::relevant_messaging_state()
+			.map(|state| state.ingress_channels.into_iter().map(|(sender, _)| sender).collect())
+	}
+
+	fn egress() -> Option<Vec<ParaId>> {
+		Self::relevant_messaging_state().map(|state| {
+			state.egress_channels.into_iter().map(|(recipient, _)| recipient).collect()
+		})
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Returns the parachain ID we are running with.
+		type SelfParaId: Get<ParaId>;
+
+		/// The channels that are open as per the relay chain, used to pick up the channels that
+		/// are already open when the pallet is added.
+		type OpenChannels: OpenChannels;
+
+		/// How to send messages to the relay chain, usually `ParentAsUmp`.
+		type UmpSender: SendXcm;
+
+		/// The origin that is allowed to request, accept and close channels.
+		type ControllerOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The senders whose requests to open a channel to us are accepted automatically.
+		type AutoAcceptFrom: Contains<ParaId>;
+
+		/// The index of the `hrmp` pallet in the relay chain runtime.
+		type HrmpPalletIndex: Get<u8>;
+
+		/// The amount of the relay chain's native asset that is withdrawn from the sovereign
+		/// account of this parachain to pay for a call to the `hrmp` pallet.
+		type RelayFee: Get<u128>;
+
+		/// The maximum weight of a call to the `hrmp` pallet on the relay chain.
+		type RelayCallWeight: Get<Weight>;
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A channel can't be opened to ourselves.
+		InvalidRecipient,
+		/// The channel is already known.
+		ChannelExists,
+		/// There is no request to open the channel waiting for acceptance.
+		NoOpenRequest,
+		/// The channel is not open.
+		ChannelNotOpen,
+		/// We are neither the sender nor the recipient of the channel.
+		NotParticipant,
+		/// The message couldn't be sent to the relay chain.
+		SendFailure,
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// We requested to open a channel.
+		/// \[ recipient, max_capacity, max_message_size \]
+		OpenChannelRequested(ParaId, u32, u32),
+		/// A sibling requested to open a channel to us.
+		/// \[ sender, max_capacity, max_message_size \]
+		OpenChannelRequestReceived(ParaId, u32, u32),
+		/// A channel was accepted by its recipient.
+		/// \[ sender, recipient \]
+		OpenChannelAccepted(ParaId, ParaId),
+		/// A channel was opened.
+		/// \[ sender, recipient \]
+		ChannelOpened(ParaId, ParaId),
+		/// A channel was requested to be closed.
+		/// \[ sender, recipient \]
+		ChannelClosing(ParaId, ParaId),
+		/// A channel was closed.
+		/// \[ sender, recipient \]
+		ChannelClosed(ParaId, ParaId),
+		/// A request to open a channel was cancelled.
+		/// \[ sender, recipient \]
+		OpenRequestCancelled(ParaId, ParaId),
+	}
+
+	/// The state of the channels this parachain takes part in.
+	#[pallet::storage]
+	pub(super) type Channels<T: Config> =
+		StorageMap<_, Blake2_128Concat, HrmpChannelId, ChannelState, OptionQuery>;
+
+	/// Whether the channels that were open as per the relay chain when the pallet was added have
+	/// been picked up.
+	#[pallet::storage]
+	pub(super) type ChannelsSynced<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			if ChannelsSynced::<T>::get() {
+				return T::DbWeight::get().reads(1)
+			}
+			T::DbWeight::get().reads(1).saturating_add(Self::sync_channels())
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Request to open a channel to the given `recipient`.
+		///
+		/// The channel opens with the next session of the relay chain after the recipient
+		/// accepted it.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `recipient`: The parachain to open the channel to.
+		/// - `max_capacity`: The proposed maximum number of messages in the channel.
+		/// - `max_message_size`: The proposed maximum size of a message in the channel.
+		#[pallet::weight((T::DbWeight::get().reads_writes(3, 3), DispatchClass::Operational))]
+		pub fn request_open(
+			origin: OriginFor<T>,
+			recipient: ParaId,
+			max_capacity: u32,
+			max_message_size: u32,
+		) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			let sender = T::SelfParaId::get();
+			ensure!(recipient != sender, Error::<T>::InvalidRecipient);
+			let channel = HrmpChannelId { sender, recipient };
+			ensure!(!Channels::<T>::contains_key(&channel), Error::<T>::ChannelExists);
+
+			Self::send_hrmp_call(HrmpCall::InitOpenChannel(
+				recipient,
+				max_capacity,
+				max_message_size,
+			))?;
+			Channels::<T>::insert(&channel, ChannelState::Requested);
+
+			Self::deposit_event(Event::OpenChannelRequested(
+				recipient,
+				max_capacity,
+				max_message_size,
+			));
+			Ok(())
+		}
+
+		/// Accept the request of the given `sender` to open a channel to us.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The parachain that requested to open the channel.
+		#[pallet::weight((T::DbWeight::get().reads_writes(3, 3), DispatchClass::Operational))]
+		pub fn accept_open(origin: OriginFor<T>, sender: ParaId) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			let recipient = T::SelfParaId::get();
+			let channel = HrmpChannelId { sender, recipient };
+			ensure!(
+				Channels::<T>::get(&channel) == Some(ChannelState::AwaitingAcceptance),
+				Error::<T>::NoOpenRequest
+			);
+
+			Self::send_hrmp_call(HrmpCall::AcceptOpenChannel(sender))?;
+			Channels::<T>::insert(&channel, ChannelState::Accepted);
+
+			Self::deposit_event(Event::OpenChannelAccepted(sender, recipient));
+			Ok(())
+		}
+
+		/// Close the open channel from `sender` to `recipient`.
+		///
+		/// The channel closes with the next session of the relay chain.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The sender of the channel.
+		/// - `recipient`: The recipient of the channel.
+		#[pallet::weight((T::DbWeight::get().reads_writes(3, 3), DispatchClass::Operational))]
+		pub fn close(origin: OriginFor<T>, sender: ParaId, recipient: ParaId) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			let self_id = T::SelfParaId::get();
+			ensure!(sender == self_id || recipient == self_id, Error::<T>::NotParticipant);
+			let channel = HrmpChannelId { sender, recipient };
+			ensure!(
+				Channels::<T>::get(&channel) == Some(ChannelState::Open),
+				Error::<T>::ChannelNotOpen
+			);
+
+			Self::send_hrmp_call(HrmpCall::CloseChannel(channel.clone()))?;
+			Channels::<T>::insert(&channel, ChannelState::Closing);
+
+			Self::deposit_event(Event::ChannelClosing(sender, recipient));
+			Ok(())
+		}
+
+		/// Cancel the request to open the channel from `sender` to `recipient` which wasn't
+		/// accepted yet.
+		///
+		/// The channel is forgotten even if the relay chain fails to cancel the request, for
+		/// example because it never registered the request in the first place. This allows to
+		/// request the channel again.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The sender of the channel.
+		/// - `recipient`: The recipient of the channel.
+		/// - `open_requests`: The number of requests to open a channel on the relay chain, which
+		///   the relay chain requires as witness.
+		#[pallet::weight((T::DbWeight::get().reads_writes(3, 3), DispatchClass::Operational))]
+		pub fn cancel_open_request(
+			origin: OriginFor<T>,
+			sender: ParaId,
+			recipient: ParaId,
+			open_requests: u32,
+		) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+
+			let self_id = T::SelfParaId::get();
+			ensure!(sender == self_id || recipient == self_id, Error::<T>::NotParticipant);
+			let channel = HrmpChannelId { sender, recipient };
+			ensure!(
+				matches!(
+					Channels::<T>::get(&channel),
+					Some(ChannelState::Requested) | Some(ChannelState::AwaitingAcceptance)
+				),
+				Error::<T>::NoOpenRequest
+			);
+
+			Self::send_hrmp_call(HrmpCall::CancelOpenRequest(channel.clone(), open_requests))?;
+			Channels::<T>::remove(&channel);
+
+			Self::deposit_event(Event::OpenRequestCancelled(sender, recipient));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the state of the channel from `sender` to `recipient`, if it is known.
+	pub fn channel_state(sender: ParaId, recipient: ParaId) -> Option<ChannelState> {
+		Channels::<T>::get(HrmpChannelId { sender, recipient })
+	}
+
+	/// Send the given `call` to the `hrmp` pallet of the relay chain.
+	///
+	/// The call is dispatched with the parachain origin and paid for from the sovereign account
+	/// of this parachain, which gets back what is left of the fee.
+	fn send_hrmp_call(call: HrmpCall) -> Result<(), Error<T>> {
+		let fee: MultiAsset = (Here, T::RelayFee::get()).into();
+		let message = Xcm(vec![
+			Instruction::WithdrawAsset(fee.clone().into()),
+			Instruction::BuyExecution { fees: fee, weight_limit: Unlimited },
+			Instruction::Transact {
+				origin_type: OriginKind::Native,
+				require_weight_at_most: T::RelayCallWeight::get(),
+				call: (T::HrmpPalletIndex::get(), call).encode().into(),
+			},
+			Instruction::RefundSurplus,
+			Instruction::DepositAsset {
+				assets: Wild(All),
+				max_assets: 1,
+				beneficiary: Parachain(T::SelfParaId::get().into()).into(),
+			},
+		]);
+
+		T::UmpSender::send_xcm(Parent, message).map_err(|_| Error::<T>::SendFailure)
+	}
+
+	/// Bring the tracked channels in line with the channels that are open as per the relay
+	/// chain, once they are known.
+	///
+	/// Channels which became open are marked as such and open or closing channels which are gone
+	/// are removed. Open channels which aren't tracked yet are added.
+	fn sync_channels() -> Weight {
+		let (ingress, egress) = match (T::OpenChannels::ingress(), T::OpenChannels::egress()) {
+			(Some(ingress), Some(egress)) => (ingress, egress),
+			_ => return T::DbWeight::get().reads(1),
+		};
+
+		let self_id = T::SelfParaId::get();
+		let mut open = ingress
+			.into_iter()
+			.map(|sender| HrmpChannelId { sender, recipient: self_id })
+			.chain(egress.into_iter().map(|recipient| HrmpChannelId { sender: self_id, recipient }))
+			.collect::<BTreeSet<_>>();
+
+		let channels = Channels::<T>::iter().collect::<Vec<_>>();
+		let mut reads = 1 + channels.len() as Weight;
+		let mut writes = 0;
+		for (channel, state) in channels {
+			let is_open = open.remove(&channel);
+			match state {
+				ChannelState::Open | ChannelState::Closing if !is_open => {
+					Channels::<T>::remove(&channel);
+					Self::deposit_event(Event::ChannelClosed(channel.sender, channel.recipient));
+					writes += 1;
+				},
+				ChannelState::Open | ChannelState::Closing => {},
+				_ if is_open => {
+					Channels::<T>::insert(&channel, ChannelState::Open);
+					Self::deposit_event(Event::ChannelOpened(channel.sender, channel.recipient));
+					writes += 1;
+				},
+				_ => {},
+			}
+		}
+
+		// What is left are open channels that we didn't know of.
+		for channel in open {
+			Channels::<T>::insert(&channel, ChannelState::Open);
+			Self::deposit_event(Event::ChannelOpened(channel.sender, channel.recipient));
+			reads += 1;
+			writes += 1;
+		}
+
+		ChannelsSynced::<T>::put(true);
+		T::DbWeight::get().reads_writes(reads, writes + 1)
+	}
+
+	/// Returns the channel with `para_id` in the given `direction`.
+	fn channel_with(para_id: ParaId, direction: ChannelDirection) -> HrmpChannelId {
+		let self_id = T::SelfParaId::get();
+		match direction {
+			ChannelDirection::Inbound => HrmpChannelId { sender: para_id, recipient: self_id },
+			ChannelDirection::Outbound => HrmpChannelId { sender: self_id, recipient: para_id },
+		}
+	}
+
+	/// The channel was opened on the relay chain.
+	fn on_channel_opened(channel: HrmpChannelId) -> Weight {
+		if Channels::<T>::get(&channel) == Some(ChannelState::Open) {
+			return T::DbWeight::get().reads(1)
+		}
+
+		Channels::<T>::insert(&channel, ChannelState::Open);
+		Self::deposit_event(Event::ChannelOpened(channel.sender, channel.recipient));
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// The channel was closed on the relay chain.
+	fn on_channel_closed(channel: HrmpChannelId) -> Weight {
+		if !matches!(
+			Channels::<T>::get(&channel),
+			Some(ChannelState::Open) | Some(ChannelState::Closing)
+		) {
+			return T::DbWeight::get().reads(1)
+		}
+
+		Channels::<T>::remove(&channel);
+		Self::deposit_event(Event::ChannelClosed(channel.sender, channel.recipient));
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// Handle the given downward message if it is an HRMP channel notification of the relay
+	/// chain.
+	///
+	/// Returns the weight used, or `None` if the message is not a notification.
+	fn handle_notification(data: &[u8]) -> Option<Weight> {
+		let versioned =
+			VersionedXcm::<()>::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut &data[..])
+				.ok()?;
+		let message = Xcm::<()>::try_from(versioned).ok()?;
+
+		match message.0.as_slice() {
+			[Instruction::HrmpNewChannelOpenRequest { sender, max_message_size, max_capacity }] =>
+				Some(Self::on_open_request((*sender).into(), *max_capacity, *max_message_size)),
+			[Instruction::HrmpChannelAccepted { recipient }] =>
+				Some(Self::on_open_accepted((*recipient).into())),
+			[Instruction::HrmpChannelClosing { sender, recipient, .. }] =>
+				Some(Self::on_closing((*sender).into(), (*recipient).into())),
+			_ => None,
+		}
+	}
+
+	/// A sibling requested to open a channel to us, accept it right away if it is allowed to.
+	fn on_open_request(sender: ParaId, max_capacity: u32, max_message_size: u32) -> Weight {
+		let recipient = T::SelfParaId::get();
+		let channel = HrmpChannelId { sender, recipient };
+		Self::deposit_event(Event::OpenChannelRequestReceived(
+			sender,
+			max_capacity,
+			max_message_size,
+		));
+
+		// If sending fails, the request can still be accepted through `accept_open`.
+		if T::AutoAcceptFrom::contains(&sender) &&
+			Self::send_hrmp_call(HrmpCall::AcceptOpenChannel(sender)).is_ok()
+		{
+			Channels::<T>::insert(&channel, ChannelState::Accepted);
+			Self::deposit_event(Event::OpenChannelAccepted(sender, recipient));
+			T::DbWeight::get().reads_writes(3, 3)
+		} else {
+			Channels::<T>::insert(&channel, ChannelState::AwaitingAcceptance);
+			T::DbWeight::get().writes(1)
+		}
+	}
+
+	/// The recipient accepted our request to open a channel.
+	fn on_open_accepted(recipient: ParaId) -> Weight {
+		let sender = T::SelfParaId::get();
+		let channel = HrmpChannelId { sender, recipient };
+		if Channels::<T>::get(&channel) != Some(ChannelState::Requested) {
+			return T::DbWeight::get().reads(1)
+		}
+
+		Channels::<T>::insert(&channel, ChannelState::Accepted);
+		Self::deposit_event(Event::OpenChannelAccepted(sender, recipient));
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// The channel from `sender` to `recipient` was requested to be closed.
+	fn on_closing(sender: ParaId, recipient: ParaId) -> Weight {
+		let channel = HrmpChannelId { sender, recipient };
+		if !Channels::<T>::contains_key(&channel) {
+			return T::DbWeight::get().reads(1)
+		}
+
+		Channels::<T>::insert(&channel, ChannelState::Closing);
+		Self::deposit_event(Event::ChannelClosing(sender, recipient));
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+}
+
+/// Updates the channels which were opened or closed on the relay chain.
+///
+/// The hooks are called while the validation data inherent is applied, the weight they use is
+/// registered on top of the weight of the inherent.
+impl<T: Config> OnSystemEvent for Pallet<T> {
+	fn on_validation_data(_data: &PersistedValidationData) {}
+
+	fn on_validation_code_applied() {}
+
+	fn on_hrmp_channel_opened(para_id: ParaId, direction: ChannelDirection) {
+		let weight = Self::on_channel_opened(Self::channel_with(para_id, direction));
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			weight,
+			DispatchClass::Mandatory,
+		);
+	}
+
+	fn on_hrmp_channel_closed(para_id: ParaId, direction: ChannelDirection) {
+		let weight = Self::on_channel_closed(Self::channel_with(para_id, direction));
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			weight,
+			DispatchClass::Mandatory,
+		);
+	}
+}
+
+/// A [`DmpMessageHandler`] which handles the HRMP channel notifications of the relay chain and
+/// passes all other downward messages on to `Inner`.
+///
+/// The weight used for the notifications is not taken into account for the limit given to
+/// `Inner`, as handling them is cheap.
+pub struct HandleHrmpNotifications<T, Inner>(PhantomData<(T, Inner)>);
+impl<T: Config, Inner: DmpMessageHandler> DmpMessageHandler for HandleHrmpNotifications<T, Inner> {
+	fn handle_dmp_messages(
+		iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>,
+		limit: Weight,
+	) -> Weight {
+		let mut used = 0;
+		let others = iter.filter(|(_, data)| match Pallet::<T>::handle_notification(data) {
+			Some(weight) => {
+				used += weight;
+				false
+			},
+			None => true,
+		});
+		let inner_used = Inner::handle_dmp_messages(others, limit);
+		used.saturating_add(inner_used)
+	}
+}
@@ -0,0 +1,173 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use crate as hrmp_channels;
+use frame_support::parameter_types;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+use xcm::latest::{MultiLocation, SendError};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		HrmpChannels: hrmp_channels::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+type AccountId = u64;
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+/// The para id of the mock runtime.
+pub const SELF_ID: u32 = 1000;
+/// A sibling whose requests to open a channel are accepted automatically.
+pub const TRUSTED_SIBLING: u32 = 2000;
+/// A sibling whose requests to open a channel need to be accepted by the controller.
+pub const OTHER_SIBLING: u32 = 3000;
+
+parameter_types! {
+	pub SelfParaId: ParaId = SELF_ID.into();
+	pub const HrmpPalletIndex: u8 = 60;
+	pub const RelayFee: u128 = 1_000;
+	pub const RelayCallWeight: Weight = 1_000_000;
+}
+
+thread_local! {
+	pub static SENT_MESSAGES: RefCell<Vec<(MultiLocation, Xcm<()>)>> = RefCell::new(Vec::new());
+	pub static OPEN: RefCell<Option<(Vec<ParaId>, Vec<ParaId>)>> = RefCell::new(None);
+	pub static OTHER_DOWNWARD_MESSAGES: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+pub fn take_sent_messages() -> Vec<(MultiLocation, Xcm<()>)> {
+	SENT_MESSAGES.with(|m| m.take())
+}
+
+/// Set the senders of the open inbound and the recipients of the open outbound channels.
+pub fn set_open_channels(ingress: Vec<u32>, egress: Vec<u32>) {
+	OPEN.with(|o| {
+		*o.borrow_mut() = Some((
+			ingress.into_iter().map(Into::into).collect(),
+			egress.into_iter().map(Into::into).collect(),
+		))
+	});
+}
+
+pub fn take_other_downward_messages() -> Vec<Vec<u8>> {
+	OTHER_DOWNWARD_MESSAGES.with(|m| m.take())
+}
+
+/// Sender which only records the messages it was given.
+pub struct MockUmpSender;
+impl SendXcm for MockUmpSender {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> Result<(), SendError> {
+		SENT_MESSAGES.with(|m| m.borrow_mut().push((dest.into(), msg)));
+		Ok(())
+	}
+}
+
+/// Open channels as set with `set_open_channels`.
+pub struct MockOpenChannels;
+impl OpenChannels for MockOpenChannels {
+	fn ingress() -> Option<Vec<ParaId>> {
+		OPEN.with(|o| o.borrow().as_ref().map(|(ingress, _)| ingress.clone()))
+	}
+
+	fn egress() -> Option<Vec<ParaId>> {
+		OPEN.with(|o| o.borrow().as_ref().map(|(_, egress)| egress.clone()))
+	}
+}
+
+pub struct AutoAcceptFrom;
+impl Contains<ParaId> for AutoAcceptFrom {
+	fn contains(sender: &ParaId) -> bool {
+		*sender == TRUSTED_SIBLING.into()
+	}
+}
+
+/// Downward message handler which records the messages it was given, using no weight.
+pub struct MockDmpHandler;
+impl DmpMessageHandler for MockDmpHandler {
+	fn handle_dmp_messages(
+		iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>,
+		_limit: Weight,
+	) -> Weight {
+		OTHER_DOWNWARD_MESSAGES.with(|m| m.borrow_mut().extend(iter.map(|(_, data)| data)));
+		0
+	}
+}
+
+impl Config for Test {
+	type Event = Event;
+	type SelfParaId = SelfParaId;
+	type OpenChannels = MockOpenChannels;
+	type UmpSender = MockUmpSender;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AutoAcceptFrom = AutoAcceptFrom;
+	type HrmpPalletIndex = HrmpPalletIndex;
+	type RelayFee = RelayFee;
+	type RelayCallWeight = RelayCallWeight;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
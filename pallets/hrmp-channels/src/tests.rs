@@ -0,0 +1,299 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use mock::{
+	new_test_ext, set_open_channels, take_other_downward_messages, take_sent_messages,
+	HrmpChannels, MockDmpHandler, Origin, System, Test, OTHER_SIBLING, SELF_ID, TRUSTED_SIBLING,
+};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::MultiLocation;
+
+/// The calls to the relay chain's `hrmp` pallet that were sent since the last call.
+fn sent_hrmp_calls() -> Vec<HrmpCall> {
+	take_sent_messages()
+		.into_iter()
+		.map(|(dest, message)| {
+			assert_eq!(dest, MultiLocation::parent());
+			let call = message
+				.0
+				.into_iter()
+				.find_map(|instruction| match instruction {
+					Instruction::Transact { call, .. } => Some(call.into_encoded()),
+					_ => None,
+				})
+				.expect("a message to the hrmp pallet transacts");
+			let (pallet_index, call) = <(u8, HrmpCall)>::decode(&mut &call[..]).unwrap();
+			assert_eq!(pallet_index, 60);
+			call
+		})
+		.collect()
+}
+
+/// Handle the given instructions as downward messages, each in its own message.
+fn receive_downward(instructions: Vec<Instruction<()>>) {
+	let messages = instructions
+		.into_iter()
+		.map(|instruction| (1, VersionedXcm::from(Xcm(vec![instruction])).encode()));
+	HandleHrmpNotifications::<Test, MockDmpHandler>::handle_dmp_messages(
+		messages,
+		Weight::max_value(),
+	);
+}
+
+fn state(sender: u32, recipient: u32) -> Option<ChannelState> {
+	HrmpChannels::channel_state(sender.into(), recipient.into())
+}
+
+fn last_event() -> mock::Event {
+	System::events().pop().expect("an event was deposited").event
+}
+
+#[test]
+fn request_open_sends_call_to_relay_chain() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::request_open(Origin::signed(1), OTHER_SIBLING.into(), 8, 1024),
+			BadOrigin
+		);
+		assert_noop!(
+			HrmpChannels::request_open(Origin::root(), SELF_ID.into(), 8, 1024),
+			Error::<Test>::InvalidRecipient
+		);
+
+		assert_ok!(HrmpChannels::request_open(Origin::root(), OTHER_SIBLING.into(), 8, 1024));
+		assert_eq!(
+			sent_hrmp_calls(),
+			vec![HrmpCall::InitOpenChannel(OTHER_SIBLING.into(), 8, 1024)]
+		);
+		assert_eq!(state(SELF_ID, OTHER_SIBLING), Some(ChannelState::Requested));
+		assert_eq!(
+			last_event(),
+			Event::<Test>::OpenChannelRequested(OTHER_SIBLING.into(), 8, 1024).into()
+		);
+
+		assert_noop!(
+			HrmpChannels::request_open(Origin::root(), OTHER_SIBLING.into(), 8, 1024),
+			Error::<Test>::ChannelExists
+		);
+	});
+}
+
+#[test]
+fn requested_channel_opens_once_accepted() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::request_open(Origin::root(), OTHER_SIBLING.into(), 8, 1024));
+		take_sent_messages();
+
+		receive_downward(vec![Instruction::HrmpChannelAccepted { recipient: OTHER_SIBLING }]);
+		assert_eq!(state(SELF_ID, OTHER_SIBLING), Some(ChannelState::Accepted));
+
+		HrmpChannels::on_hrmp_channel_opened(OTHER_SIBLING.into(), ChannelDirection::Outbound);
+		assert_eq!(state(SELF_ID, OTHER_SIBLING), Some(ChannelState::Open));
+		assert_eq!(
+			last_event(),
+			Event::<Test>::ChannelOpened(SELF_ID.into(), OTHER_SIBLING.into()).into()
+		);
+		assert!(take_other_downward_messages().is_empty());
+	});
+}
+
+#[test]
+fn open_request_awaits_acceptance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::accept_open(Origin::root(), OTHER_SIBLING.into()),
+			Error::<Test>::NoOpenRequest
+		);
+
+		receive_downward(vec![Instruction::HrmpNewChannelOpenRequest {
+			sender: OTHER_SIBLING,
+			max_message_size: 1024,
+			max_capacity: 8,
+		}]);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::AwaitingAcceptance));
+		assert_eq!(
+			last_event(),
+			Event::<Test>::OpenChannelRequestReceived(OTHER_SIBLING.into(), 8, 1024).into()
+		);
+		assert!(sent_hrmp_calls().is_empty());
+
+		assert_ok!(HrmpChannels::accept_open(Origin::root(), OTHER_SIBLING.into()));
+		assert_eq!(sent_hrmp_calls(), vec![HrmpCall::AcceptOpenChannel(OTHER_SIBLING.into())]);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::Accepted));
+
+		assert_noop!(
+			HrmpChannels::accept_open(Origin::root(), OTHER_SIBLING.into()),
+			Error::<Test>::NoOpenRequest
+		);
+	});
+}
+
+#[test]
+fn open_request_is_accepted_automatically() {
+	new_test_ext().execute_with(|| {
+		receive_downward(vec![Instruction::HrmpNewChannelOpenRequest {
+			sender: TRUSTED_SIBLING,
+			max_message_size: 1024,
+			max_capacity: 8,
+		}]);
+
+		assert_eq!(sent_hrmp_calls(), vec![HrmpCall::AcceptOpenChannel(TRUSTED_SIBLING.into())]);
+		assert_eq!(state(TRUSTED_SIBLING, SELF_ID), Some(ChannelState::Accepted));
+		assert_eq!(
+			last_event(),
+			Event::<Test>::OpenChannelAccepted(TRUSTED_SIBLING.into(), SELF_ID.into()).into()
+		);
+	});
+}
+
+#[test]
+fn cancel_open_request() {
+	new_test_ext().execute_with(|| {
+		let channel = HrmpChannelId { sender: SELF_ID.into(), recipient: OTHER_SIBLING.into() };
+		assert_noop!(
+			HrmpChannels::cancel_open_request(
+				Origin::root(),
+				SELF_ID.into(),
+				OTHER_SIBLING.into(),
+				1
+			),
+			Error::<Test>::NoOpenRequest
+		);
+		assert_ok!(HrmpChannels::request_open(Origin::root(), OTHER_SIBLING.into(), 8, 1024));
+		take_sent_messages();
+
+		assert_noop!(
+			HrmpChannels::cancel_open_request(
+				Origin::signed(1),
+				SELF_ID.into(),
+				OTHER_SIBLING.into(),
+				1
+			),
+			BadOrigin
+		);
+		assert_ok!(HrmpChannels::cancel_open_request(
+			Origin::root(),
+			SELF_ID.into(),
+			OTHER_SIBLING.into(),
+			1
+		));
+		assert_eq!(sent_hrmp_calls(), vec![HrmpCall::CancelOpenRequest(channel, 1)]);
+		assert_eq!(state(SELF_ID, OTHER_SIBLING), None);
+		assert_eq!(
+			last_event(),
+			Event::<Test>::OpenRequestCancelled(SELF_ID.into(), OTHER_SIBLING.into()).into()
+		);
+
+		// The channel can be requested again.
+		assert_ok!(HrmpChannels::request_open(Origin::root(), OTHER_SIBLING.into(), 8, 1024));
+	});
+}
+
+#[test]
+fn close_open_channel() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::close(Origin::root(), OTHER_SIBLING.into(), TRUSTED_SIBLING.into()),
+			Error::<Test>::NotParticipant
+		);
+		assert_noop!(
+			HrmpChannels::close(Origin::root(), OTHER_SIBLING.into(), SELF_ID.into()),
+			Error::<Test>::ChannelNotOpen
+		);
+
+		// Channels that are open on the relay chain are picked up.
+		set_open_channels(vec![OTHER_SIBLING], vec![]);
+		HrmpChannels::on_initialize(2);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::Open));
+
+		assert_ok!(HrmpChannels::close(Origin::root(), OTHER_SIBLING.into(), SELF_ID.into()));
+		assert_eq!(
+			sent_hrmp_calls(),
+			vec![HrmpCall::CloseChannel(HrmpChannelId {
+				sender: OTHER_SIBLING.into(),
+				recipient: SELF_ID.into(),
+			})]
+		);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::Closing));
+
+		HrmpChannels::on_hrmp_channel_closed(OTHER_SIBLING.into(), ChannelDirection::Inbound);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), None);
+		assert_eq!(
+			last_event(),
+			Event::<Test>::ChannelClosed(OTHER_SIBLING.into(), SELF_ID.into()).into()
+		);
+	});
+}
+
+#[test]
+fn open_channels_are_picked_up_once() {
+	new_test_ext().execute_with(|| {
+		// The open channels are not known before the first validation data.
+		HrmpChannels::on_initialize(1);
+		set_open_channels(vec![OTHER_SIBLING], vec![TRUSTED_SIBLING]);
+		HrmpChannels::on_initialize(2);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::Open));
+		assert_eq!(state(SELF_ID, TRUSTED_SIBLING), Some(ChannelState::Open));
+
+		// From then on, only the channel changes reported by the hooks are taken into account.
+		set_open_channels(vec![], vec![]);
+		HrmpChannels::on_initialize(3);
+		assert_eq!(state(OTHER_SIBLING, SELF_ID), Some(ChannelState::Open));
+
+		HrmpChannels::on_hrmp_channel_closed(TRUSTED_SIBLING.into(), ChannelDirection::Outbound);
+		assert_eq!(state(SELF_ID, TRUSTED_SIBLING), None);
+	});
+}
+
+#[test]
+fn closing_notification_is_tracked() {
+	new_test_ext().execute_with(|| {
+		set_open_channels(vec![], vec![OTHER_SIBLING]);
+		HrmpChannels::on_initialize(2);
+
+		receive_downward(vec![Instruction::HrmpChannelClosing {
+			initiator: OTHER_SIBLING,
+			sender: SELF_ID,
+			recipient: OTHER_SIBLING,
+		}]);
+		assert_eq!(state(SELF_ID, OTHER_SIBLING), Some(ChannelState::Closing));
+		assert_eq!(
+			last_event(),
+			Event::<Test>::ChannelClosing(SELF_ID.into(), OTHER_SIBLING.into()).into()
+		);
+	});
+}
+
+#[test]
+fn other_downward_messages_are_passed_on() {
+	new_test_ext().execute_with(|| {
+		let other = VersionedXcm::<()>::from(Xcm(vec![Instruction::ClearOrigin])).encode();
+		let notification = VersionedXcm::<()>::from(Xcm(vec![Instruction::HrmpChannelAccepted {
+			recipient: OTHER_SIBLING,
+		}]))
+		.encode();
+		let messages = vec![(1, other.clone()), (1, notification), (2, vec![1, 2, 3])];
+
+		HandleHrmpNotifications::<Test, MockDmpHandler>::handle_dmp_messages(
+			messages.into_iter(),
+			Weight::max_value(),
+		);
+
+		assert_eq!(take_other_downward_messages(), vec![other, vec![1, 2, 3]]);
+	});
+}
@@ -0,0 +1,30 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for inspecting the validation code upgrade process of the parachain
+//! system pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use cumulus_pallet_parachain_system::{PendingUpgrade, UpgradeStatus};
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to inspect where a validation code upgrade of the parachain stands.
+	pub trait ParachainUpgradeApi {
+		/// The state of the authorized, pending or restricted validation code upgrade.
+		fn upgrade_status() -> UpgradeStatus<Block::Hash>;
+	}
+}
@@ -0,0 +1,70 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for inspecting the validation code upgrade process of the parachain system
+//! pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use cumulus_pallet_parachain_system_rpc_runtime_api::{
+	ParachainUpgradeApi as ParachainUpgradeRuntimeApi, PendingUpgrade, UpgradeStatus,
+};
+
+/// Error code returned when the runtime api call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Parachain upgrade RPC methods.
+#[rpc]
+pub trait ParachainUpgradeApi<BlockHash> {
+	/// The state of the authorized, pending or restricted validation code upgrade.
+	#[rpc(name = "parachainSystem_upgradeStatus")]
+	fn upgrade_status(&self, at: Option<BlockHash>) -> Result<UpgradeStatus<BlockHash>>;
+}
+
+/// Implements the [`ParachainUpgradeApi`] RPC trait for inspecting the upgrade process.
+pub struct ParachainUpgrade<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> ParachainUpgrade<C, B> {
+	/// Create new `ParachainUpgrade` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: PhantomData }
+	}
+}
+
+impl<C, Block> ParachainUpgradeApi<<Block as BlockT>::Hash> for ParachainUpgrade<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ParachainUpgradeRuntimeApi<Block>,
+{
+	fn upgrade_status(&self, at: Option<Block::Hash>) -> Result<UpgradeStatus<Block::Hash>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().upgrade_status(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query the upgrade status.".into(),
+			data: Some(e.to_string().into()),
+		})
+	}
+}
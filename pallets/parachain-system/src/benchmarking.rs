@@ -0,0 +1,237 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-parachain-system
+
+use super::*;
+
+use cumulus_primitives_core::relay_chain::{v1::HrmpChannelId, well_known_keys};
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use sp_runtime::traits::HashFor;
+use sp_state_machine::{Backend, TrieBackend};
+use sp_trie::{MemoryDB, StorageProof};
+
+/// The maximum number of downward messages in the benchmarked inherent.
+const MAX_DOWNWARD_MESSAGES: u32 = 1_000;
+/// The maximum number of inbound HRMP channels in the benchmarked inherent.
+const MAX_HRMP_CHANNELS: u32 = 100;
+/// The maximum number of horizontal messages in the benchmarked inherent.
+const MAX_HRMP_MESSAGES: u32 = 1_000;
+/// The size of every message in the benchmarked inherent.
+const MESSAGE_SIZE: u32 = 256;
+/// The relay parent number of the benchmarked inherent, all messages are sent at it.
+const RELAY_PARENT_NUMBER: RelayChainBlockNumber = 1;
+/// The maximum number of additional relay chain state entries that are stored.
+const MAX_RELAY_STATE_ENTRIES: u32 = 100;
+/// The maximum number of tracked sibling parachains.
+const MAX_TRACKED_SIBLING_PARAS: u32 = 100;
+/// The size of every relay chain state entry and sibling head.
+const ENTRY_SIZE: u32 = 1024;
+/// The maximum size of the validation code that is applied on go-ahead.
+const MAX_CODE_SIZE: u32 = 3 * 1024 * 1024;
+
+/// Message handler that drops all messages, so that the benchmark only measures the weight of
+/// the pallet itself. The weight used by the runtime's handlers is added on top of it.
+struct NoopMessageHandler;
+
+impl DmpMessageHandler for NoopMessageHandler {
+	fn handle_dmp_messages(
+		iter: impl Iterator<Item = (RelayChainBlockNumber, Vec<u8>)>,
+		_max_weight: Weight,
+	) -> Weight {
+		iter.for_each(drop);
+		0
+	}
+}
+
+impl XcmpMessageHandler for NoopMessageHandler {
+	fn handle_xcmp_messages<'a, I: Iterator<Item = (ParaId, RelayChainBlockNumber, &'a [u8])>>(
+		iter: I,
+		_max_weight: Weight,
+	) -> Weight {
+		iter.for_each(drop);
+		0
+	}
+}
+
+/// Build a relay chain state proof that contains all of the given entries.
+fn relay_state_proof(entries: Vec<(Vec<u8>, Vec<u8>)>) -> (relay_chain::Hash, StorageProof) {
+	let (db, root) = MemoryDB::<HashFor<relay_chain::Block>>::default_with_root();
+	let backend = TrieBackend::new(db, root);
+	let (root, mut transaction) = backend.storage_root(
+		entries.iter().map(|(key, value)| (&key[..], Some(&value[..]))),
+		Default::default(),
+	);
+	let nodes = transaction.drain().into_iter().map(|(_, (node, _))| node);
+
+	(root, StorageProof::new(nodes))
+}
+
+/// Build a [`RelayChainStateProof`] that contains all of the given entries.
+fn relay_chain_state_proof<T: Config>(
+	mut entries: Vec<(Vec<u8>, Vec<u8>)>,
+) -> RelayChainStateProof {
+	// Make sure that the proof isn't empty, it wouldn't contain the storage root otherwise.
+	entries.push((b"filler".to_vec(), vec![0]));
+	let (root, proof) = relay_state_proof(entries);
+	RelayChainStateProof::new(T::SelfParaId::get(), root, proof)
+		.expect("The proof was built with the same root; qed")
+}
+
+/// Build the inherent data with `d` downward messages and `h` horizontal messages that are
+/// spread over `c` inbound HRMP channels.
+fn inherent_data<T: Config>(d: u32, c: u32, h: u32) -> ParachainInherentData {
+	let para_id = T::SelfParaId::get();
+
+	let downward_messages = (0..d)
+		.map(|_| InboundDownwardMessage {
+			sent_at: RELAY_PARENT_NUMBER,
+			msg: vec![0; MESSAGE_SIZE as usize],
+		})
+		.collect::<Vec<_>>();
+	let mut dmq_mqc_head = MessageQueueChain::default();
+	downward_messages.iter().for_each(|message| {
+		dmq_mqc_head.extend_downward(message);
+	});
+
+	let senders = (0..c).map(|i| ParaId::from(1_000 + i)).collect::<Vec<_>>();
+	let mut horizontal_messages =
+		senders.iter().map(|sender| (*sender, Vec::new())).collect::<BTreeMap<_, _>>();
+	for i in 0..h {
+		if let Some(messages) = horizontal_messages.get_mut(&senders[(i % c) as usize]) {
+			messages.push(InboundHrmpMessage {
+				sent_at: RELAY_PARENT_NUMBER,
+				data: vec![0; MESSAGE_SIZE as usize],
+			});
+		}
+	}
+
+	let host_config = AbridgedHostConfiguration {
+		max_code_size: 2 * 1024 * 1024,
+		max_head_data_size: 1024 * 1024,
+		max_upward_queue_count: 8,
+		max_upward_queue_size: 1024,
+		max_upward_message_size: 256,
+		max_upward_message_num_per_candidate: 5,
+		hrmp_max_message_num_per_candidate: 5,
+		validation_upgrade_cooldown: 6,
+		validation_upgrade_delay: 6,
+	};
+	let mut entries = vec![
+		(well_known_keys::ACTIVE_CONFIG.to_vec(), host_config.encode()),
+		(well_known_keys::dmq_mqc_head(para_id), dmq_mqc_head.head().encode()),
+		(well_known_keys::hrmp_ingress_channel_index(para_id), senders.encode()),
+	];
+	for (sender, messages) in &horizontal_messages {
+		let mut mqc_head = MessageQueueChain::default();
+		messages.iter().for_each(|message| {
+			mqc_head.extend_hrmp(message);
+		});
+		let channel = AbridgedHrmpChannel {
+			max_capacity: MAX_HRMP_MESSAGES,
+			max_total_size: MAX_HRMP_MESSAGES * MESSAGE_SIZE,
+			max_message_size: MESSAGE_SIZE,
+			msg_count: messages.len() as u32,
+			total_size: messages.len() as u32 * MESSAGE_SIZE,
+			mqc_head: Some(mqc_head.head()),
+		};
+		let channel_id = HrmpChannelId { sender: *sender, recipient: para_id };
+		entries.push((well_known_keys::hrmp_channels(channel_id), channel.encode()));
+	}
+	let (relay_parent_storage_root, relay_chain_state) = relay_state_proof(entries);
+
+	ParachainInherentData {
+		validation_data: PersistedValidationData {
+			relay_parent_number: RELAY_PARENT_NUMBER,
+			relay_parent_storage_root,
+			..Default::default()
+		},
+		relay_chain_state,
+		downward_messages,
+		horizontal_messages,
+	}
+}
+
+benchmarks! {
+	set_validation_data {
+		let d in 0 .. MAX_DOWNWARD_MESSAGES;
+		let c in 1 .. MAX_HRMP_CHANNELS;
+		let h in 0 .. MAX_HRMP_MESSAGES;
+		let data = inherent_data::<T>(d, c, h);
+	}: {
+		Pallet::<T>::do_set_validation_data::<NoopMessageHandler, NoopMessageHandler>(data);
+	}
+	verify {
+		assert_eq!(ProcessedDownwardMessages::<T>::get(), d);
+		assert_eq!(HrmpWatermark::<T>::get(), RELAY_PARENT_NUMBER);
+	}
+
+	store_relay_state_entries {
+		let k in 0 .. MAX_RELAY_STATE_ENTRIES;
+		let keys = (0..k).map(|i| (b"entry", i).encode()).collect::<Vec<_>>();
+		let entries = keys.iter().map(|key| (key.clone(), vec![0; ENTRY_SIZE as usize])).collect();
+		let relay_state_proof = relay_chain_state_proof::<T>(entries);
+	}: {
+		Pallet::<T>::store_relay_state_entries(&relay_state_proof, keys);
+	}
+	verify {
+		assert_eq!(RelayStateEntries::<T>::iter().count() as u32, k);
+	}
+
+	update_sibling_para_heads {
+		let s in 0 .. MAX_TRACKED_SIBLING_PARAS;
+		let tracked = (0..s).map(|i| ParaId::from(2_000 + i)).collect::<Vec<_>>();
+		let head = HeadData(vec![0; ENTRY_SIZE as usize]);
+		// All of the heads are known already and get replaced.
+		for para_id in &tracked {
+			SiblingParaHeads::<T>::insert(para_id, (0, head.clone()));
+		}
+		let entries =
+			tracked.iter().map(|para_id| (para_head_key(*para_id), head.encode())).collect();
+		let relay_state_proof = relay_chain_state_proof::<T>(entries);
+	}: {
+		Pallet::<T>::update_sibling_para_heads(&relay_state_proof, RELAY_PARENT_NUMBER, tracked);
+	}
+	verify {
+		let heads = SiblingParaHeads::<T>::iter_values().collect::<Vec<_>>();
+		assert_eq!(heads.len() as u32, s);
+		assert!(heads.iter().all(|(number, _)| *number == RELAY_PARENT_NUMBER));
+	}
+
+	note_relay_parent {
+		let r in 0 .. T::RelayParentHistoryLength::get();
+		for number in 0..r {
+			Pallet::<T>::note_relay_parent(number, Default::default());
+		}
+	}: {
+		Pallet::<T>::note_relay_parent(r, Default::default());
+	}
+	verify {
+		assert_eq!(Pallet::<T>::relay_parent_storage_root(r), Some(Default::default()));
+	}
+
+	apply_pending_validation_code {
+		let b in 1 .. MAX_CODE_SIZE;
+		<PendingValidationCode<T>>::put(vec![0; b as usize]);
+	}: {
+		Pallet::<T>::apply_pending_validation_code(RELAY_PARENT_NUMBER);
+	}
+	verify {
+		assert!(!<PendingValidationCode<T>>::exists());
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::tests::new_test_ext(), crate::tests::Test);
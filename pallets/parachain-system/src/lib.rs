@@ -27,12 +27,12 @@
 //!
 //! Users must ensure that they register this pallet as an inherent provider.
 
-use codec::Encode;
+use codec::{Decode, Encode};
 use cumulus_primitives_core::{
-	relay_chain, AbridgedHostConfiguration, ChannelStatus, CollationInfo, DmpMessageHandler,
-	GetChannelInfo, InboundDownwardMessage, InboundHrmpMessage, MessageSendError,
-	OutboundHrmpMessage, ParaId, PersistedValidationData, UpwardMessage, UpwardMessageSender,
-	XcmpMessageHandler, XcmpMessageSource,
+	relay_chain, AbridgedHostConfiguration, AbridgedHrmpChannel, ChannelStatus, CollationInfo,
+	DmpMessageHandler, GetChannelInfo, InboundDownwardMessage, InboundHrmpMessage,
+	MessageSendError, OutboundHrmpMessage, ParaId, PersistedValidationData, UpwardMessage,
+	UpwardMessageSender, XcmpMessageHandler, XcmpMessageSource,
 };
 use cumulus_primitives_parachain_inherent::{MessageQueueChain, ParachainInherentData};
 use frame_support::{
@@ -42,25 +42,31 @@ use frame_support::{
 	storage,
 	traits::Get,
 	weights::{Pays, PostDispatchInfo, Weight},
+	BoundedVec,
 };
 use frame_system::{ensure_none, ensure_root};
-use polkadot_parachain::primitives::RelayChainBlockNumber;
+use polkadot_parachain::primitives::{HeadData, RelayChainBlockNumber};
 use relay_state_snapshot::MessagingStateSnapshot;
+use scale_info::TypeInfo;
 use sp_runtime::{
-	traits::{Block as BlockT, BlockNumberProvider, Hash},
+	traits::{Block as BlockT, BlockNumberProvider, Hash, Header as HeaderT, Saturating},
 	transaction_validity::{
 		InvalidTransaction, TransactionLongevity, TransactionSource, TransactionValidity,
 		ValidTransaction,
 	},
+	RuntimeDebug,
 };
 use sp_std::{cmp, collections::btree_map::BTreeMap, prelude::*};
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 mod migration;
 mod relay_state_snapshot;
-#[macro_use]
-pub mod validate_block;
 #[cfg(test)]
 mod tests;
+#[macro_use]
+pub mod validate_block;
+pub mod weights;
 
 /// Register the `validate_block` function that is used by parachains to validate blocks on a
 /// validator.
@@ -85,10 +91,83 @@ mod tests;
 /// # fn main() {}
 /// ```
 pub use cumulus_pallet_parachain_system_proc_macro::register_validate_block;
-pub use relay_state_snapshot::RelayChainStateProof;
+pub use relay_state_snapshot::{para_head_key, ReadEntryErr, RelayChainStateProof};
+pub use weights::WeightInfo;
 
 pub use pallet::*;
 
+/// Identifier given to every upward message when it is queued.
+pub type UpwardMessageId = u64;
+
+/// Index of a page of the upward message queue.
+pub type UpwardPageIndex = u32;
+
+/// The maximum number of upward messages in a page of the upward message queue.
+pub const UPWARD_MESSAGES_PER_PAGE: u32 = 32;
+
+/// The state of the queue of upward messages waiting to be sent to the relay chain.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct UpwardQueueState {
+	/// The index of the first page with messages.
+	pub first_page: UpwardPageIndex,
+	/// The index after the last page with messages. The queue is empty if it equals
+	/// `first_page`.
+	pub end_page: UpwardPageIndex,
+	/// The number of messages in the queue.
+	pub count: u32,
+	/// The id of the next message to be queued.
+	pub next_id: UpwardMessageId,
+}
+
+/// The direction of an HRMP channel, as seen from this parachain.
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum ChannelDirection {
+	/// The channel from the other parachain to us.
+	Inbound,
+	/// The channel from us to the other parachain.
+	Outbound,
+}
+
+/// An authorization to upgrade the runtime to the code with the given hash.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CodeUpgradeAuthorization<Hash, BlockNumber> {
+	/// The hash of the authorized code.
+	pub code_hash: Hash,
+	/// Whether the `RuntimeVersion` of the code is checked before the upgrade is scheduled.
+	pub check_version: bool,
+	/// The last block in which the upgrade can be enacted.
+	pub expires_at: BlockNumber,
+}
+
+/// A validation code upgrade that waits for the go-ahead signal of the relay chain.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PendingUpgrade<Hash> {
+	/// The hash of the pending validation code.
+	pub code_hash: Hash,
+	/// The size of the pending validation code in bytes.
+	pub code_size: u32,
+	/// The relay parent number of the block that signalled the upgrade to the relay chain.
+	pub signalled_at: RelayChainBlockNumber,
+	/// The relay chain block from which on the upgrade is expected to be enacted.
+	pub expected_at: RelayChainBlockNumber,
+}
+
+/// The state of the validation code upgrade process, as reported by the `ParachainUpgradeApi`
+/// runtime API.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct UpgradeStatus<Hash> {
+	/// The hash of the code that is authorized and can still be enacted, if any.
+	pub authorized_code_hash: Option<Hash>,
+	/// The upgrade that waits for the go-ahead signal of the relay chain, if any.
+	pub pending: Option<PendingUpgrade<Hash>>,
+	/// Whether the relay chain currently prohibits signalling an upgrade.
+	pub restricted: bool,
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
@@ -128,6 +207,31 @@ pub mod pallet {
 
 		/// The weight we reserve at the beginning of the block for processing XCMP messages.
 		type ReservedXcmpWeight: Get<Weight>;
+
+		/// The maximum number of upward messages which may be waiting to be sent to the relay
+		/// chain. Sending further messages fails with `MessageSendError::QueueFull`.
+		type MaxPendingUpwardMessages: Get<u32>;
+
+		/// The relay chain storage keys that should be proven in the relay chain state proof in
+		/// addition to the keys that are always proven.
+		///
+		/// The collator queries these through the `RelayStorageKeysApi`. The proven values can
+		/// be read with [`Pallet::read_relay_entry`].
+		type AdditionalRelayStorageKeys: Get<Vec<Vec<u8>>>;
+
+		/// The sibling parachains whose latest heads are read from the relay chain state proof
+		/// and stored in [`SiblingParaHeads`].
+		type TrackedSiblingParas: Get<Vec<ParaId>>;
+
+		/// The number of blocks for which an upgrade authorized with
+		/// [`Pallet::authorize_upgrade`] can be enacted.
+		type UpgradeAuthorizationPeriod: Get<Self::BlockNumber>;
+
+		/// The number of recent relay parents that are kept in [`RelayParentHistory`].
+		type RelayParentHistoryLength: Get<u32>;
+
+		/// Weight information for the extrinsics of this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	#[pallet::hooks]
@@ -138,7 +242,6 @@ pub mod pallet {
 
 		fn on_finalize(_: T::BlockNumber) {
 			<DidSetValidationCode<T>>::kill();
-			<UpgradeRestrictionSignal<T>>::kill();
 
 			assert!(
 				<ValidationData<T>>::exists(),
@@ -167,36 +270,19 @@ pub mod pallet {
 				},
 			};
 
-			<PendingUpwardMessages<T>>::mutate(|up| {
-				let (count, size) = relevant_messaging_state.relay_dispatch_queue_size;
-
-				let available_capacity = cmp::min(
-					host_config.max_upward_queue_count.saturating_sub(count),
-					host_config.max_upward_message_num_per_candidate,
-				);
-				let available_size = host_config.max_upward_queue_size.saturating_sub(size);
-
-				// Count the number of messages we can possibly fit in the given constraints, i.e.
-				// available_capacity and available_size.
-				let num = up
-					.iter()
-					.scan((available_capacity as usize, available_size as usize), |state, msg| {
-						let (cap_left, size_left) = *state;
-						match (cap_left.checked_sub(1), size_left.checked_sub(msg.len())) {
-							(Some(new_cap), Some(new_size)) => {
-								*state = (new_cap, new_size);
-								Some(())
-							},
-							_ => None,
-						}
-					})
-					.count();
-
-				// TODO: #274 Return back messages that do not longer fit into the queue.
-
-				UpwardMessages::<T>::put(&up[..num]);
-				*up = up.split_off(num);
-			});
+			let (count, size) = relevant_messaging_state.relay_dispatch_queue_size;
+			let available_capacity = cmp::min(
+				host_config.max_upward_queue_count.saturating_sub(count),
+				host_config.max_upward_message_num_per_candidate,
+			);
+			let available_size = host_config.max_upward_queue_size.saturating_sub(size);
+			let max_message_size =
+				cmp::min(host_config.max_upward_message_size, host_config.max_upward_queue_size);
+			UpwardMessages::<T>::put(Self::take_upward_messages(
+				available_capacity,
+				available_size,
+				max_message_size,
+			));
 
 			// Sending HRMP messages is a little bit more involved. There are the following
 			// constraints:
@@ -238,8 +324,12 @@ pub mod pallet {
 			UpwardMessages::<T>::kill();
 			HrmpOutboundMessages::<T>::kill();
 			CustomValidationHeadData::<T>::kill();
+			let removed_entries = match RelayStateEntries::<T>::remove_all(None) {
+				sp_io::KillStorageResult::AllRemoved(n) |
+				sp_io::KillStorageResult::SomeRemaining(n) => n,
+			};
 
-			weight += T::DbWeight::get().writes(6);
+			weight += T::DbWeight::get().writes(6 + removed_entries as Weight);
 
 			// Here, in `on_initialize` we must report the weight for both `on_initialize` and
 			// `on_finalize`.
@@ -265,10 +355,15 @@ pub mod pallet {
 				.unwrap_or(0);
 			<AnnouncedHrmpMessagesPerCandidate<T>>::put(hrmp_max_message_num_per_candidate);
 
+			// The same goes for the pages of the upward message queue we will go through.
+			let upward_pages = Self::host_configuration()
+				.map(|cfg| cfg.max_upward_message_num_per_candidate / UPWARD_MESSAGES_PER_PAGE + 1)
+				.unwrap_or(1);
+
 			// NOTE that the actual weight consumed by `on_finalize` may turn out lower.
 			weight += T::DbWeight::get().reads_writes(
-				3 + hrmp_max_message_num_per_candidate as u64,
-				4 + hrmp_max_message_num_per_candidate as u64,
+				3 + hrmp_max_message_num_per_candidate as u64 + upward_pages as u64,
+				4 + hrmp_max_message_num_per_candidate as u64 + upward_pages as u64,
 			);
 
 			weight
@@ -286,88 +381,23 @@ pub mod pallet {
 		///
 		/// As a side effect, this function upgrades the current validation function
 		/// if the appropriate time has come.
-		#[pallet::weight((0, DispatchClass::Mandatory))]
-		// TODO: This weight should be corrected.
+		///
+		/// The weight is bounded by the weight reserved for the DMP and XCMP message handlers,
+		/// the weight that isn't used by the handlers is refunded.
+		#[pallet::weight((
+			Pallet::<T>::validation_data_overhead(data)
+				.saturating_add(Pallet::<T>::reserved_dmp_weight())
+				.saturating_add(Pallet::<T>::reserved_xcmp_weight()),
+			DispatchClass::Mandatory,
+		))]
 		pub fn set_validation_data(
 			origin: OriginFor<T>,
 			data: ParachainInherentData,
 		) -> DispatchResultWithPostInfo {
 			ensure_none(origin)?;
-			assert!(
-				!<ValidationData<T>>::exists(),
-				"ValidationData must be updated only once in a block",
-			);
-
-			let ParachainInherentData {
-				validation_data: vfp,
-				relay_chain_state,
-				downward_messages,
-				horizontal_messages,
-			} = data;
-
-			Self::validate_validation_data(&vfp);
-
-			let relay_state_proof = RelayChainStateProof::new(
-				T::SelfParaId::get(),
-				vfp.relay_parent_storage_root,
-				relay_chain_state,
-			)
-			.expect("Invalid relay chain state proof");
-
-			// initialization logic: we know that this runs exactly once every block,
-			// which means we can put the initialization logic here to remove the
-			// sequencing problem.
-			let upgrade_go_ahead_signal = relay_state_proof
-				.read_upgrade_go_ahead_signal()
-				.expect("Invalid upgrade go ahead signal");
-			match upgrade_go_ahead_signal {
-				Some(relay_chain::v1::UpgradeGoAhead::GoAhead) => {
-					assert!(
-						<PendingValidationCode<T>>::exists(),
-						"No new validation function found in storage, GoAhead signal is not expected",
-					);
-					let validation_code = <PendingValidationCode<T>>::take();
-
-					Self::put_parachain_code(&validation_code);
-					<T::OnSystemEvent as OnSystemEvent>::on_validation_code_applied();
-					Self::deposit_event(Event::ValidationFunctionApplied(vfp.relay_parent_number));
-				},
-				Some(relay_chain::v1::UpgradeGoAhead::Abort) => {
-					<PendingValidationCode<T>>::kill();
-					Self::deposit_event(Event::ValidationFunctionDiscarded);
-				},
-				None => {},
-			}
-			<UpgradeRestrictionSignal<T>>::put(
-				relay_state_proof
-					.read_upgrade_restriction_signal()
-					.expect("Invalid upgrade restriction signal"),
-			);
-
-			let host_config = relay_state_proof
-				.read_abridged_host_configuration()
-				.expect("Invalid host configuration in relay chain state proof");
-			let relevant_messaging_state = relay_state_proof
-				.read_messaging_state_snapshot()
-				.expect("Invalid messaging state in relay chain state proof");
 
-			<ValidationData<T>>::put(&vfp);
-			<RelevantMessagingState<T>>::put(relevant_messaging_state.clone());
-			<HostConfiguration<T>>::put(host_config);
-
-			<T::OnSystemEvent as OnSystemEvent>::on_validation_data(&vfp);
-
-			// TODO: This is more than zero, but will need benchmarking to figure out what.
-			let mut total_weight = 0;
-			total_weight += Self::process_inbound_downward_messages(
-				relevant_messaging_state.dmq_mqc_head,
-				downward_messages,
-			);
-			total_weight += Self::process_inbound_horizontal_messages(
-				&relevant_messaging_state.ingress_channels,
-				horizontal_messages,
-				vfp.relay_parent_number,
-			);
+			let total_weight =
+				Self::do_set_validation_data::<T::DmpMessageHandler, T::XcmpMessageHandler>(data);
 
 			Ok(PostDispatchInfo { actual_weight: Some(total_weight), pays_fee: Pays::No })
 		}
@@ -382,17 +412,38 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Authorize an upgrade to the code with the given `code_hash`, replacing any previous
+		/// authorization.
+		///
+		/// The upgrade can be enacted by anyone with [`Pallet::enact_authorized_upgrade`] within
+		/// [`Config::UpgradeAuthorizationPeriod`] blocks. If `check_version` is set, the
+		/// `RuntimeVersion` of the code needs to have the same `spec_name` and a higher
+		/// `spec_version` than the current runtime.
 		#[pallet::weight((1_000_000, DispatchClass::Operational))]
-		pub fn authorize_upgrade(origin: OriginFor<T>, code_hash: T::Hash) -> DispatchResult {
+		pub fn authorize_upgrade(
+			origin: OriginFor<T>,
+			code_hash: T::Hash,
+			check_version: bool,
+		) -> DispatchResult {
 			ensure_root(origin)?;
 
-			AuthorizedUpgrade::<T>::put(&code_hash);
+			let expires_at = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::UpgradeAuthorizationPeriod::get());
+			AuthorizedUpgrade::<T>::put(CodeUpgradeAuthorization {
+				code_hash,
+				check_version,
+				expires_at,
+			});
 
 			Self::deposit_event(Event::UpgradeAuthorized(code_hash));
 			Ok(())
 		}
 
-		#[pallet::weight(1_000_000)]
+		/// Schedule the upgrade to the authorized `code`.
+		///
+		/// The weight is the one of `frame_system::set_code`, which covers reading the
+		/// `RuntimeVersion` of the code if the authorization requires to check it.
+		#[pallet::weight((T::BlockWeights::get().max_block, DispatchClass::Operational))]
 		pub fn enact_authorized_upgrade(
 			_: OriginFor<T>,
 			code: Vec<u8>,
@@ -402,6 +453,38 @@ pub mod pallet {
 			AuthorizedUpgrade::<T>::kill();
 			Ok(Pays::No.into())
 		}
+
+		/// Remove the current upgrade authorization.
+		#[pallet::weight((T::DbWeight::get().reads_writes(1, 1), DispatchClass::Operational))]
+		pub fn cancel_authorized_upgrade(origin: OriginFor<T>) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let authorization =
+				AuthorizedUpgrade::<T>::take().ok_or(Error::<T>::NothingAuthorized)?;
+
+			Self::deposit_event(Event::UpgradeAuthorizationCancelled(authorization.code_hash));
+			Ok(())
+		}
+
+		/// Discard the pending validation code before it is sent to the relay chain.
+		///
+		/// This is only possible in the block that scheduled the upgrade. Once the code was sent
+		/// to the relay chain, the relay chain upgrades the validation function of this
+		/// parachain and the code has to be applied when it signals to do so.
+		#[pallet::weight((T::DbWeight::get().reads_writes(2, 4), DispatchClass::Operational))]
+		pub fn cancel_pending_upgrade(origin: OriginFor<T>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<PendingValidationCode<T>>::exists(), Error::<T>::NotScheduled);
+			ensure!(<DidSetValidationCode<T>>::get(), Error::<T>::UpgradeAlreadySignalled);
+
+			<PendingValidationCode<T>>::kill();
+			<PendingUpgradeSchedule<T>>::kill();
+			<DidSetValidationCode<T>>::kill();
+			NewValidationCode::<T>::kill();
+
+			Self::deposit_event(Event::PendingUpgradeCancelled);
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
@@ -413,14 +496,40 @@ pub mod pallet {
 		ValidationFunctionApplied(RelayChainBlockNumber),
 		/// The relay-chain aborted the upgrade process.
 		ValidationFunctionDiscarded,
+		/// The pending validation function was discarded before it was sent to the relay chain.
+		PendingUpgradeCancelled,
 		/// An upgrade has been authorized.
 		UpgradeAuthorized(T::Hash),
+		/// The authorization of an upgrade has been cancelled.
+		/// \[ code_hash \]
+		UpgradeAuthorizationCancelled(T::Hash),
 		/// Some downward messages have been received and will be processed.
 		/// \[ count \]
 		DownwardMessagesReceived(u32),
 		/// Downward messages were processed using the given weight.
 		/// \[ weight_used, result_mqc_head \]
 		DownwardMessagesProcessed(Weight, relay_chain::Hash),
+		/// An upward message was queued to be sent to the relay chain.
+		/// \[ id, message_hash \]
+		UpwardMessageQueued(UpwardMessageId, T::Hash),
+		/// An upward message was sent to the relay chain.
+		/// \[ id \]
+		UpwardMessageSent(UpwardMessageId),
+		/// An upward message was dropped because it no longer fits the relay chain limits.
+		/// \[ id \]
+		UpwardMessageDropped(UpwardMessageId),
+		/// An HRMP channel with the given parachain was opened on the relay chain.
+		/// \[ para_id, direction \]
+		HrmpChannelOpened(ParaId, ChannelDirection),
+		/// An HRMP channel with the given parachain was closed on the relay chain.
+		/// \[ para_id, direction \]
+		HrmpChannelClosed(ParaId, ChannelDirection),
+		/// The host configuration of the relay chain changed.
+		HostConfigurationChanged,
+		/// The relay chain started to prohibit signalling a validation code upgrade.
+		UpgradeRestricted,
+		/// The relay chain allows signalling a validation code upgrade again.
+		UpgradeRestrictionLifted,
 	}
 
 	#[pallet::error]
@@ -438,10 +547,14 @@ pub mod pallet {
 		HostConfigurationNotAvailable,
 		/// No validation function upgrade is currently scheduled.
 		NotScheduled,
+		/// The pending validation function was already sent to the relay chain.
+		UpgradeAlreadySignalled,
 		/// No code upgrade has been authorized.
 		NothingAuthorized,
 		/// The given code upgrade has not been authorized.
 		Unauthorized,
+		/// The authorization of the code upgrade has expired.
+		AuthorizationExpired,
 	}
 
 	/// In case of a scheduled upgrade, this storage field contains the validation code to be applied.
@@ -454,6 +567,12 @@ pub mod pallet {
 	#[pallet::getter(fn new_validation_function)]
 	pub(super) type PendingValidationCode<T: Config> = StorageValue<_, Vec<u8>, ValueQuery>;
 
+	/// The relay parent number at which the [`PendingValidationCode`] was signalled to the relay
+	/// chain and the relay chain block from which on the upgrade is expected to be enacted.
+	#[pallet::storage]
+	pub(super) type PendingUpgradeSchedule<T: Config> =
+		StorageValue<_, (RelayChainBlockNumber, RelayChainBlockNumber)>;
+
 	/// Validation code that is set by the parachain and is to be communicated to collator and
 	/// consequently the relay-chain.
 	///
@@ -478,8 +597,8 @@ pub mod pallet {
 	/// candidate will be invalid.
 	///
 	/// This storage item is a mirror of the corresponding value for the current parachain from the
-	/// relay-chain. This value is ephemeral which means it doesn't hit the storage. This value is
-	/// set after the inherent.
+	/// relay-chain. This value is set by the inherent and kept until the inherent of the next
+	/// block, so that changes of the signal can be observed.
 	#[pallet::storage]
 	pub(super) type UpgradeRestrictionSignal<T: Config> =
 		StorageValue<_, Option<relay_chain::v1::UpgradeRestriction>, ValueQuery>;
@@ -546,10 +665,52 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type UpwardMessages<T: Config> = StorageValue<_, Vec<UpwardMessage>, ValueQuery>;
 
-	/// Upward messages that are still pending and not yet send to the relay chain.
+	/// The state of the queue of upward messages that are not yet sent to the relay chain.
+	#[pallet::storage]
+	pub(super) type UpwardQueue<T: Config> = StorageValue<_, UpwardQueueState, ValueQuery>;
+
+	/// The pages of upward messages that are not yet sent to the relay chain, along with their
+	/// ids. Each page holds up to `UPWARD_MESSAGES_PER_PAGE` messages.
+	#[pallet::storage]
+	pub(super) type PendingUpwardMessagePages<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		UpwardPageIndex,
+		Vec<(UpwardMessageId, UpwardMessage)>,
+		ValueQuery,
+	>;
+
+	/// The raw values of the [`Config::AdditionalRelayStorageKeys`] as proven by the relay chain
+	/// state proof of this block, keyed by the relay chain storage key. `None` values are absent
+	/// on the relay chain.
+	///
+	/// This will be cleared in `on_initialize` of each new block.
+	#[pallet::storage]
+	pub(super) type RelayStateEntries<T: Config> =
+		StorageMap<_, Twox64Concat, Vec<u8>, Option<Vec<u8>>>;
+
+	/// The latest known heads of the [`Config::TrackedSiblingParas`], along with the number of
+	/// the relay parent whose state they were read from.
+	///
+	/// Heads are kept if the relay chain state proof of a block doesn't contain them, the relay
+	/// parent number tells how recent they are.
+	#[pallet::storage]
+	#[pallet::getter(fn sibling_para_head)]
+	pub(super) type SiblingParaHeads<T: Config> =
+		StorageMap<_, Twox64Concat, ParaId, (RelayChainBlockNumber, HeadData)>;
+
+	/// The relay parent numbers and storage roots of the most recent blocks, ordered from the
+	/// oldest to the newest relay parent.
+	///
+	/// Holds at most [`Config::RelayParentHistoryLength`] entries, the oldest entry is dropped
+	/// when a new relay parent is noted.
 	#[pallet::storage]
-	pub(super) type PendingUpwardMessages<T: Config> =
-		StorageValue<_, Vec<UpwardMessage>, ValueQuery>;
+	#[pallet::getter(fn relay_parent_history)]
+	pub(super) type RelayParentHistory<T: Config> = StorageValue<
+		_,
+		BoundedVec<(RelayChainBlockNumber, relay_chain::Hash), T::RelayParentHistoryLength>,
+		ValueQuery,
+	>;
 
 	/// The number of HRMP messages we observed in `on_initialize` and thus used that number for
 	/// announcing the weight of `on_initialize` and `on_finalize`.
@@ -568,7 +729,8 @@ pub mod pallet {
 
 	/// The next authorized upgrade, if there is one.
 	#[pallet::storage]
-	pub(super) type AuthorizedUpgrade<T: Config> = StorageValue<_, T::Hash>;
+	pub(super) type AuthorizedUpgrade<T: Config> =
+		StorageValue<_, CodeUpgradeAuthorization<T::Hash, T::BlockNumber>>;
 
 	/// A custom head data that should be returned as result of `validate_block`.
 	///
@@ -615,11 +777,11 @@ pub mod pallet {
 
 		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
 			if let Call::enact_authorized_upgrade { ref code } = call {
-				if let Ok(hash) = Self::validate_authorized_upgrade(code) {
+				if let Ok(authorization) = Self::validate_authorized_upgrade(code) {
 					return Ok(ValidTransaction {
 						priority: 100,
 						requires: Vec::new(),
-						provides: vec![hash.as_ref().to_vec()],
+						provides: vec![authorization.code_hash.as_ref().to_vec()],
 						longevity: TransactionLongevity::max_value(),
 						propagate: true,
 					})
@@ -634,11 +796,25 @@ pub mod pallet {
 }
 
 impl<T: Config> Pallet<T> {
-	fn validate_authorized_upgrade(code: &[u8]) -> Result<T::Hash, DispatchError> {
-		let required_hash = AuthorizedUpgrade::<T>::get().ok_or(Error::<T>::NothingAuthorized)?;
+	/// Check that `code` is authorized to be enacted, including its `RuntimeVersion` if the
+	/// authorization requires it.
+	///
+	/// This is checked when validating the transaction as well, so code that can't be enacted
+	/// never makes it into a block for free.
+	fn validate_authorized_upgrade(
+		code: &[u8],
+	) -> Result<CodeUpgradeAuthorization<T::Hash, T::BlockNumber>, DispatchError> {
+		let authorization = AuthorizedUpgrade::<T>::get().ok_or(Error::<T>::NothingAuthorized)?;
+		ensure!(
+			frame_system::Pallet::<T>::block_number() <= authorization.expires_at,
+			Error::<T>::AuthorizationExpired
+		);
 		let actual_hash = T::Hashing::hash(&code[..]);
-		ensure!(actual_hash == required_hash, Error::<T>::Unauthorized);
-		Ok(actual_hash)
+		ensure!(actual_hash == authorization.code_hash, Error::<T>::Unauthorized);
+		if authorization.check_version {
+			frame_system::Pallet::<T>::can_set_code(code)?;
+		}
+		Ok(authorization)
 	}
 }
 
@@ -721,6 +897,151 @@ impl<T: Config> Pallet<T> {
 		});
 	}
 
+	/// The weight of [`Pallet::set_validation_data`] with the given `data`, excluding the weight
+	/// used by the message handlers.
+	///
+	/// Includes applying the pending validation code, in case the relay chain gives the go-ahead
+	/// for it.
+	fn validation_data_overhead(data: &ParachainInherentData) -> Weight {
+		let horizontal_messages =
+			data.horizontal_messages.values().map(|messages| messages.len() as u32).sum();
+		T::WeightInfo::set_validation_data(
+			data.downward_messages.len() as u32,
+			data.horizontal_messages.len() as u32,
+			horizontal_messages,
+		)
+		.saturating_add(T::WeightInfo::store_relay_state_entries(
+			T::AdditionalRelayStorageKeys::get().len() as u32,
+		))
+		.saturating_add(T::WeightInfo::update_sibling_para_heads(
+			T::TrackedSiblingParas::get().len() as u32,
+		))
+		.saturating_add(T::WeightInfo::note_relay_parent(T::RelayParentHistoryLength::get()))
+		.saturating_add(Self::pending_code_weight())
+	}
+
+	/// The weight of applying the [`PendingValidationCode`], if there is any.
+	fn pending_code_weight() -> Weight {
+		match <PendingValidationCode<T>>::decode_len() {
+			Some(len) if len > 0 => T::WeightInfo::apply_pending_validation_code(len as u32),
+			_ => 0,
+		}
+	}
+
+	/// Set the validation data and process the inbound messages with the given handlers.
+	///
+	/// Returns the weight used, including the weight used by the handlers. The pallet uses
+	/// [`Config::DmpMessageHandler`] and [`Config::XcmpMessageHandler`], the benchmark uses
+	/// handlers that do nothing to measure the weight of the pallet alone.
+	pub(crate) fn do_set_validation_data<Dmp: DmpMessageHandler, Xcmp: XcmpMessageHandler>(
+		data: ParachainInherentData,
+	) -> Weight {
+		assert!(
+			!<ValidationData<T>>::exists(),
+			"ValidationData must be updated only once in a block",
+		);
+
+		let mut overhead = Self::validation_data_overhead(&data);
+
+		let ParachainInherentData {
+			validation_data: vfp,
+			relay_chain_state,
+			downward_messages,
+			horizontal_messages,
+		} = data;
+
+		Self::validate_validation_data(&vfp);
+
+		let relay_state_proof = RelayChainStateProof::new(
+			T::SelfParaId::get(),
+			vfp.relay_parent_storage_root,
+			relay_chain_state,
+		)
+		.expect("Invalid relay chain state proof");
+
+		// initialization logic: we know that this runs exactly once every block,
+		// which means we can put the initialization logic here to remove the
+		// sequencing problem.
+		let upgrade_go_ahead_signal = relay_state_proof
+			.read_upgrade_go_ahead_signal()
+			.expect("Invalid upgrade go ahead signal");
+		if !matches!(upgrade_go_ahead_signal, Some(relay_chain::v1::UpgradeGoAhead::GoAhead)) {
+			// The overhead accounts for applying the pending code, which only happens on go-ahead.
+			overhead = overhead.saturating_sub(Self::pending_code_weight());
+		}
+		match upgrade_go_ahead_signal {
+			Some(relay_chain::v1::UpgradeGoAhead::GoAhead) =>
+				Self::apply_pending_validation_code(vfp.relay_parent_number),
+			Some(relay_chain::v1::UpgradeGoAhead::Abort) => {
+				<PendingValidationCode<T>>::kill();
+				<PendingUpgradeSchedule<T>>::kill();
+				Self::deposit_event(Event::ValidationFunctionDiscarded);
+			},
+			None => {},
+		}
+		let upgrade_restriction = relay_state_proof
+			.read_upgrade_restriction_signal()
+			.expect("Invalid upgrade restriction signal");
+		match (<UpgradeRestrictionSignal<T>>::get(), upgrade_restriction) {
+			(None, Some(_)) => Self::deposit_event(Event::UpgradeRestricted),
+			(Some(_), None) => Self::deposit_event(Event::UpgradeRestrictionLifted),
+			_ => {},
+		}
+		<UpgradeRestrictionSignal<T>>::put(upgrade_restriction);
+
+		let host_config = relay_state_proof
+			.read_abridged_host_configuration()
+			.expect("Invalid host configuration in relay chain state proof");
+		let relevant_messaging_state = relay_state_proof
+			.read_messaging_state_snapshot()
+			.expect("Invalid messaging state in relay chain state proof");
+
+		Self::store_relay_state_entries(&relay_state_proof, T::AdditionalRelayStorageKeys::get());
+		Self::update_sibling_para_heads(
+			&relay_state_proof,
+			vfp.relay_parent_number,
+			T::TrackedSiblingParas::get(),
+		);
+		Self::note_relay_parent(vfp.relay_parent_number, vfp.relay_parent_storage_root);
+
+		let previous_messaging_state = Self::relevant_messaging_state();
+		let previous_host_config = Self::host_configuration();
+
+		<ValidationData<T>>::put(&vfp);
+		<RelevantMessagingState<T>>::put(relevant_messaging_state.clone());
+		<HostConfiguration<T>>::put(&host_config);
+
+		<T::OnSystemEvent as OnSystemEvent>::on_validation_data(&vfp);
+		if let Some(previous) = previous_messaging_state {
+			Self::notify_channel_changes(&previous, &relevant_messaging_state);
+		}
+		if let Some(previous) = previous_host_config {
+			Self::notify_host_configuration_change(&previous, &host_config);
+		}
+
+		let mut total_weight = overhead;
+		total_weight += Self::process_inbound_downward_messages::<Dmp>(
+			relevant_messaging_state.dmq_mqc_head,
+			downward_messages,
+		);
+		total_weight += Self::process_inbound_horizontal_messages::<Xcmp>(
+			&relevant_messaging_state.ingress_channels,
+			horizontal_messages,
+			vfp.relay_parent_number,
+		);
+		total_weight
+	}
+
+	/// The weight that is available to the [`Config::DmpMessageHandler`] in a block.
+	fn reserved_dmp_weight() -> Weight {
+		<ReservedDmpWeightOverride<T>>::get().unwrap_or_else(T::ReservedDmpWeight::get)
+	}
+
+	/// The weight that is available to the [`Config::XcmpMessageHandler`] in a block.
+	fn reserved_xcmp_weight() -> Weight {
+		<ReservedXcmpWeightOverride<T>>::get().unwrap_or_else(T::ReservedXcmpWeight::get)
+	}
+
 	/// Process all inbound downward messages relayed by the collator.
 	///
 	/// Checks if the sequence of the messages is valid, dispatches them and communicates the
@@ -730,7 +1051,7 @@ impl<T: Config> Pallet<T> {
 	///
 	/// If it turns out that after processing all messages the Message Queue Chain
 	/// hash doesn't match the expected.
-	fn process_inbound_downward_messages(
+	fn process_inbound_downward_messages<Handler: DmpMessageHandler>(
 		expected_dmq_mqc_head: relay_chain::Hash,
 		downward_messages: Vec<InboundDownwardMessage>,
 	) -> Weight {
@@ -740,8 +1061,7 @@ impl<T: Config> Pallet<T> {
 		let mut weight_used = 0;
 		if dm_count != 0 {
 			Self::deposit_event(Event::DownwardMessagesReceived(dm_count));
-			let max_weight =
-				<ReservedDmpWeightOverride<T>>::get().unwrap_or_else(T::ReservedDmpWeight::get);
+			let max_weight = Self::reserved_dmp_weight();
 
 			let message_iter = downward_messages
 				.into_iter()
@@ -749,7 +1069,7 @@ impl<T: Config> Pallet<T> {
 					dmq_head.extend_downward(m);
 				})
 				.map(|m| (m.sent_at, m.msg));
-			weight_used += T::DmpMessageHandler::handle_dmp_messages(message_iter, max_weight);
+			weight_used += Handler::handle_dmp_messages(message_iter, max_weight);
 			<LastDmqMqcHead<T>>::put(&dmq_head);
 
 			Self::deposit_event(Event::DownwardMessagesProcessed(weight_used, dmq_head.head()));
@@ -776,8 +1096,8 @@ impl<T: Config> Pallet<T> {
 	///            a para which has no open channel to this parachain or if after processing
 	///            messages across all inbound channels MQCs were obtained which do not
 	///            correspond to the ones found on the relay-chain.
-	fn process_inbound_horizontal_messages(
-		ingress_channels: &[(ParaId, cumulus_primitives_core::AbridgedHrmpChannel)],
+	fn process_inbound_horizontal_messages<Handler: XcmpMessageHandler>(
+		ingress_channels: &[(ParaId, AbridgedHrmpChannel)],
 		horizontal_messages: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
 		relay_parent_number: relay_chain::v1::BlockNumber,
 	) -> Weight {
@@ -832,9 +1152,8 @@ impl<T: Config> Pallet<T> {
 			.iter()
 			.map(|&(sender, ref message)| (sender, message.sent_at, &message.data[..]));
 
-		let max_weight =
-			<ReservedXcmpWeightOverride<T>>::get().unwrap_or_else(T::ReservedXcmpWeight::get);
-		let weight_used = T::XcmpMessageHandler::handle_xcmp_messages(message_iter, max_weight);
+		let max_weight = Self::reserved_xcmp_weight();
+		let weight_used = Handler::handle_xcmp_messages(message_iter, max_weight);
 
 		// Check that the MQC heads for each channel provided by the relay chain match the MQC
 		// heads we have after processing all incoming messages.
@@ -885,9 +1204,11 @@ impl<T: Config> Pallet<T> {
 
 	/// The implementation of the runtime upgrade functionality for parachains.
 	pub fn schedule_code_upgrade(validation_function: Vec<u8>) -> DispatchResult {
-		// Ensure that `ValidationData` exists. We do not care about the validation data per se,
-		// but we do care about the [`UpgradeRestrictionSignal`] which arrives with the same inherent.
-		ensure!(<ValidationData<T>>::exists(), Error::<T>::ValidationDataNotAvailable,);
+		// Ensure that `ValidationData` exists. Besides the relay parent number, we do care about
+		// the [`UpgradeRestrictionSignal`] which arrives with the same inherent.
+		let relay_parent_number = <ValidationData<T>>::get()
+			.ok_or(Error::<T>::ValidationDataNotAvailable)?
+			.relay_parent_number;
 		ensure!(<UpgradeRestrictionSignal<T>>::get().is_none(), Error::<T>::ProhibitedByPolkadot);
 
 		ensure!(!<PendingValidationCode<T>>::exists(), Error::<T>::OverlappingUpgrades);
@@ -903,11 +1224,42 @@ impl<T: Config> Pallet<T> {
 		// be applied later: when the relay-chain communicates go-ahead signal to us.
 		Self::notify_polkadot_of_pending_upgrade(&validation_function);
 		<PendingValidationCode<T>>::put(validation_function);
+		<PendingUpgradeSchedule<T>>::put((
+			relay_parent_number,
+			relay_parent_number.saturating_add(cfg.validation_upgrade_delay),
+		));
 		Self::deposit_event(Event::ValidationFunctionStored);
 
 		Ok(())
 	}
 
+	/// Returns the state of the validation code upgrade process.
+	///
+	/// This is expected to be used by the `ParachainUpgradeApi` runtime api.
+	pub fn upgrade_status() -> UpgradeStatus<T::Hash> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let authorized_code_hash = AuthorizedUpgrade::<T>::get()
+			.filter(|authorization| now <= authorization.expires_at)
+			.map(|authorization| authorization.code_hash);
+
+		// The schedule is absent for code that was stored before it was tracked.
+		let pending = <PendingUpgradeSchedule<T>>::get().and_then(|(signalled_at, expected_at)| {
+			let code = <PendingValidationCode<T>>::get();
+			(!code.is_empty()).then(|| PendingUpgrade {
+				code_hash: T::Hashing::hash(&code),
+				code_size: code.len() as u32,
+				signalled_at,
+				expected_at,
+			})
+		});
+
+		UpgradeStatus {
+			authorized_code_hash,
+			pending,
+			restricted: <UpgradeRestrictionSignal<T>>::get().is_some(),
+		}
+	}
+
 	/// Returns the [`CollationInfo`] of the current active block.
 	///
 	/// The given `header` is the header of the built block we are collecting the collation info for.
@@ -944,6 +1296,173 @@ impl<T: Config> Pallet<T> {
 	pub fn set_custom_validation_head_data(head_data: Vec<u8>) {
 		CustomValidationHeadData::<T>::put(head_data);
 	}
+
+	/// Notify about the HRMP channels that were opened or closed on the relay chain since the
+	/// `previous` messaging state.
+	fn notify_channel_changes(previous: &MessagingStateSnapshot, current: &MessagingStateSnapshot) {
+		let changes = [
+			(&previous.ingress_channels, &current.ingress_channels, ChannelDirection::Inbound),
+			(&previous.egress_channels, &current.egress_channels, ChannelDirection::Outbound),
+		];
+		// Both lists of channels are sorted by the para id.
+		let contains = |channels: &[(ParaId, AbridgedHrmpChannel)], para_id: ParaId| {
+			channels.binary_search_by_key(&para_id, |(id, _)| *id).is_ok()
+		};
+
+		for (before, after, direction) in changes {
+			for (para_id, _) in after.iter().filter(|(id, _)| !contains(before, *id)) {
+				<T::OnSystemEvent as OnSystemEvent>::on_hrmp_channel_opened(*para_id, direction);
+				Self::deposit_event(Event::HrmpChannelOpened(*para_id, direction));
+			}
+			for (para_id, _) in before.iter().filter(|(id, _)| !contains(after, *id)) {
+				<T::OnSystemEvent as OnSystemEvent>::on_hrmp_channel_closed(*para_id, direction);
+				Self::deposit_event(Event::HrmpChannelClosed(*para_id, direction));
+			}
+		}
+	}
+
+	/// Notify about the host configuration if it changed since the `previous` one.
+	fn notify_host_configuration_change(
+		previous: &AbridgedHostConfiguration,
+		current: &AbridgedHostConfiguration,
+	) {
+		if previous.encode() != current.encode() {
+			<T::OnSystemEvent as OnSystemEvent>::on_host_configuration_changed(previous, current);
+			Self::deposit_event(Event::HostConfigurationChanged);
+		}
+	}
+
+	/// Returns the relay chain storage keys that should be proven in the relay chain state proof
+	/// of the next block.
+	///
+	/// This is expected to be used by the
+	/// [`RelayStorageKeysApi`](cumulus_primitives_core::RelayStorageKeysApi) runtime api.
+	pub fn additional_relay_storage_keys() -> Vec<Vec<u8>> {
+		let mut keys = T::AdditionalRelayStorageKeys::get();
+		keys.extend(T::TrackedSiblingParas::get().into_iter().map(para_head_key));
+		keys
+	}
+
+	/// Read and decode the relay chain storage entry under `key` as proven by the relay chain
+	/// state proof of the current block.
+	///
+	/// Only keys declared in [`Config::AdditionalRelayStorageKeys`] can be read and only after
+	/// the validation data inherent was applied. Other keys result in [`ReadEntryErr::Proof`].
+	/// Returns `None` if the entry is absent on the relay chain.
+	pub fn read_relay_entry<V: Decode>(key: &[u8]) -> Result<Option<V>, ReadEntryErr> {
+		match RelayStateEntries::<T>::get(key) {
+			None => Err(ReadEntryErr::Proof),
+			Some(None) => Ok(None),
+			Some(Some(raw)) => V::decode(&mut &raw[..]).map(Some).map_err(|_| ReadEntryErr::Decode),
+		}
+	}
+
+	/// Returns the decoded header of the latest known head of the given sibling parachain,
+	/// along with its state root and the number of the relay parent it was read at.
+	///
+	/// Only the heads of [`Config::TrackedSiblingParas`] are known. The state root can be used
+	/// to verify storage proofs of the sibling parachain. The head isn't updated if a collator
+	/// leaves it out of the relay chain state proof, so callers that need a recent head should
+	/// check the relay parent number. Returns `None` if the head is unknown or can't be decoded
+	/// as `H`.
+	pub fn sibling_para_header<H: HeaderT>(
+		para_id: ParaId,
+	) -> Option<(H, H::Hash, RelayChainBlockNumber)> {
+		let (relay_parent_number, head) = SiblingParaHeads::<T>::get(para_id)?;
+		let header = H::decode(&mut &head.0[..]).ok()?;
+		let state_root = *header.state_root();
+		Some((header, state_root, relay_parent_number))
+	}
+
+	/// Returns the storage root of the given relay parent, if it is in the
+	/// [`RelayParentHistory`].
+	///
+	/// This allows to verify relay chain storage proofs against a recent relay parent, for
+	/// example with [`RelayChainStateProof::new`].
+	pub fn relay_parent_storage_root(
+		relay_parent_number: RelayChainBlockNumber,
+	) -> Option<relay_chain::Hash> {
+		let history = <RelayParentHistory<T>>::get();
+		history
+			.binary_search_by_key(&relay_parent_number, |(number, _)| *number)
+			.ok()
+			.map(|index| history[index].1)
+	}
+
+	/// Apply the [`PendingValidationCode`] after the relay chain gave the go-ahead for it.
+	pub(crate) fn apply_pending_validation_code(relay_parent_number: RelayChainBlockNumber) {
+		assert!(
+			<PendingValidationCode<T>>::exists(),
+			"No new validation function found in storage, GoAhead signal is not expected",
+		);
+		let validation_code = <PendingValidationCode<T>>::take();
+		<PendingUpgradeSchedule<T>>::kill();
+
+		Self::put_parachain_code(&validation_code);
+		<T::OnSystemEvent as OnSystemEvent>::on_validation_code_applied();
+		Self::deposit_event(Event::ValidationFunctionApplied(relay_parent_number));
+	}
+
+	/// Store the values of the given relay chain storage `keys` in [`RelayStateEntries`].
+	///
+	/// Keys that are not covered by the proof are left out, reading them fails later on.
+	pub(crate) fn store_relay_state_entries(
+		relay_state_proof: &RelayChainStateProof,
+		keys: Vec<Vec<u8>>,
+	) {
+		for key in keys {
+			if let Ok(value) = relay_state_proof.read_raw_entry(&key) {
+				<RelayStateEntries<T>>::insert(key, value);
+			}
+		}
+	}
+
+	/// Add the relay parent of the current block to the [`RelayParentHistory`], dropping the
+	/// oldest entries that exceed [`Config::RelayParentHistoryLength`].
+	pub(crate) fn note_relay_parent(
+		relay_parent_number: RelayChainBlockNumber,
+		storage_root: relay_chain::Hash,
+	) {
+		let max_len = T::RelayParentHistoryLength::get() as usize;
+		<RelayParentHistory<T>>::mutate(|history| {
+			let mut entries = sp_std::mem::take(history).into_inner();
+			// Keep the history sorted, even if the relay parent didn't advance.
+			entries.retain(|(number, _)| *number < relay_parent_number);
+			entries.push((relay_parent_number, storage_root));
+			let excess = entries.len().saturating_sub(max_len);
+			entries.drain(..excess);
+			*history = BoundedVec::try_from(entries)
+				.expect("Entries exceeding the maximum length were dropped above; qed");
+		});
+	}
+
+	/// Update the [`SiblingParaHeads`] of the `tracked` paras from the relay chain state proof of
+	/// `relay_parent_number`.
+	///
+	/// Heads of paras that are no longer tracked or that were removed from the relay chain are
+	/// removed as well.
+	pub(crate) fn update_sibling_para_heads(
+		relay_state_proof: &RelayChainStateProof,
+		relay_parent_number: RelayChainBlockNumber,
+		tracked: Vec<ParaId>,
+	) {
+		let untracked = SiblingParaHeads::<T>::iter_keys()
+			.filter(|para_id| !tracked.contains(para_id))
+			.collect::<Vec<_>>();
+		for para_id in untracked {
+			SiblingParaHeads::<T>::remove(para_id);
+		}
+
+		for para_id in tracked {
+			match relay_state_proof.read_para_head(para_id) {
+				Ok(Some(head)) =>
+					SiblingParaHeads::<T>::insert(para_id, (relay_parent_number, head)),
+				Ok(None) => SiblingParaHeads::<T>::remove(para_id),
+				// The head isn't part of the proof, keep the one we know.
+				Err(_) => (),
+			}
+		}
+	}
 }
 
 pub struct ParachainSetCode<T>(sp_std::marker::PhantomData<T>);
@@ -985,8 +1504,75 @@ impl<T: Config> Pallet<T> {
 				// Thus fall through here.
 			},
 		};
-		<PendingUpwardMessages<T>>::append(message);
-		Ok(0)
+		Self::queue_upward_message(message)
+	}
+
+	/// Append `message` to the upward message queue. Returns the number of messages ahead of it.
+	fn queue_upward_message(message: UpwardMessage) -> Result<u32, MessageSendError> {
+		let mut queue = <UpwardQueue<T>>::get();
+		if queue.count >= T::MaxPendingUpwardMessages::get() {
+			return Err(MessageSendError::QueueFull)
+		}
+		let id = queue.next_id;
+		let hash = T::Hashing::hash(&message);
+		let last_page_full = queue.end_page == queue.first_page ||
+			<PendingUpwardMessagePages<T>>::decode_len(queue.end_page - 1).unwrap_or(0) >=
+				UPWARD_MESSAGES_PER_PAGE as usize;
+		if last_page_full {
+			queue.end_page += 1;
+		}
+		<PendingUpwardMessagePages<T>>::append(queue.end_page - 1, (id, message));
+		let ahead = queue.count;
+		queue.count += 1;
+		queue.next_id += 1;
+		<UpwardQueue<T>>::put(queue);
+		Self::deposit_event(Event::UpwardMessageQueued(id, hash));
+		Ok(ahead)
+	}
+
+	/// Take as many messages from the front of the upward message queue as fit into
+	/// `available_capacity` messages and `available_size` bytes.
+	///
+	/// Messages larger than `max_message_size` can never be sent and are dropped.
+	fn take_upward_messages(
+		mut available_capacity: u32,
+		mut available_size: u32,
+		max_message_size: u32,
+	) -> Vec<UpwardMessage> {
+		let mut queue = <UpwardQueue<T>>::get();
+		let mut result = Vec::new();
+		while queue.first_page < queue.end_page {
+			let mut page = <PendingUpwardMessagePages<T>>::get(queue.first_page);
+			let mut taken = 0;
+			for (id, message) in page.iter() {
+				let len = message.len() as u32;
+				if len > max_message_size {
+					Self::deposit_event(Event::UpwardMessageDropped(*id));
+				} else if available_capacity > 0 && len <= available_size {
+					available_capacity -= 1;
+					available_size -= len;
+					result.push(message.clone());
+					Self::deposit_event(Event::UpwardMessageSent(*id));
+				} else {
+					break
+				}
+				taken += 1;
+			}
+			queue.count = queue.count.saturating_sub(taken as u32);
+			if taken < page.len() {
+				page.drain(..taken);
+				<PendingUpwardMessagePages<T>>::insert(queue.first_page, page);
+				break
+			}
+			<PendingUpwardMessagePages<T>>::remove(queue.first_page);
+			queue.first_page += 1;
+		}
+		if queue.first_page == queue.end_page {
+			queue.first_page = 0;
+			queue.end_page = 0;
+		}
+		<UpwardQueue<T>>::put(queue);
+		result
 	}
 }
 
@@ -1021,6 +1607,16 @@ pub trait OnSystemEvent {
 	fn on_validation_data(data: &PersistedValidationData);
 	/// Called when the validation code is being applied, aka from the next block on this is the new runtime.
 	fn on_validation_code_applied();
+	/// Called when an HRMP channel with the given parachain was opened on the relay chain.
+	fn on_hrmp_channel_opened(_para_id: ParaId, _direction: ChannelDirection) {}
+	/// Called when an HRMP channel with the given parachain was closed on the relay chain.
+	fn on_hrmp_channel_closed(_para_id: ParaId, _direction: ChannelDirection) {}
+	/// Called when the host configuration of the relay chain changed from `previous` to `current`.
+	fn on_host_configuration_changed(
+		_previous: &AbridgedHostConfiguration,
+		_current: &AbridgedHostConfiguration,
+	) {
+	}
 }
 
 /// Implements [`BlockNumberProvider`] that returns relay chain block number fetched from
@@ -21,7 +21,7 @@ use frame_support::{
 };
 
 /// The current storage version.
-pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
 
 /// Call this during the next runtime upgrade for this module.
 pub fn on_runtime_upgrade<T: Config>() -> Weight {
@@ -34,6 +34,20 @@ pub fn on_runtime_upgrade<T: Config>() -> Weight {
 		StorageVersion::new(1).put::<Pallet<T>>();
 	}
 
+	if StorageVersion::get::<Pallet<T>>() == 1 {
+		weight = weight
+			.saturating_add(v2::migrate::<T>())
+			.saturating_add(T::DbWeight::get().writes(1));
+		StorageVersion::new(2).put::<Pallet<T>>();
+	}
+
+	if StorageVersion::get::<Pallet<T>>() == 2 {
+		weight = weight
+			.saturating_add(v3::migrate::<T>())
+			.saturating_add(T::DbWeight::get().writes(1));
+		StorageVersion::new(3).put::<Pallet<T>>();
+	}
+
 	weight
 }
 
@@ -48,3 +62,66 @@ mod v1 {
 		T::DbWeight::get().writes(1)
 	}
 }
+
+/// V2: The `PendingUpwardMessages` vector is replaced by the paged upward message queue, in
+/// which every message has an id.
+mod v2 {
+	use crate::{
+		Config, Pallet, PendingUpwardMessagePages, UpwardQueue, UpwardQueueState,
+		UPWARD_MESSAGES_PER_PAGE,
+	};
+	use cumulus_primitives_core::UpwardMessage;
+	use frame_support::{migration::take_storage_value, pallet_prelude::*};
+	use sp_std::prelude::*;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let pending = take_storage_value::<Vec<UpwardMessage>>(
+			<Pallet<T>>::name().as_bytes(),
+			b"PendingUpwardMessages",
+			b"",
+		)
+		.unwrap_or_default();
+
+		let mut queue = UpwardQueueState::default();
+		for chunk in pending.chunks(UPWARD_MESSAGES_PER_PAGE as usize) {
+			let page = chunk
+				.iter()
+				.cloned()
+				.enumerate()
+				.map(|(i, message)| (queue.next_id + i as u64, message))
+				.collect::<Vec<_>>();
+			queue.next_id += page.len() as u64;
+			queue.count += page.len() as u32;
+			<PendingUpwardMessagePages<T>>::insert(queue.end_page, page);
+			queue.end_page += 1;
+		}
+		let pages = queue.end_page as Weight;
+		<UpwardQueue<T>>::put(queue);
+
+		T::DbWeight::get().reads_writes(1, 2 + pages)
+	}
+}
+
+/// V3: `AuthorizedUpgrade` stores a `CodeUpgradeAuthorization` instead of only the code hash.
+///
+/// An existing authorization skips the version check and expires after the
+/// `UpgradeAuthorizationPeriod`, counted from the migration.
+mod v3 {
+	use crate::{AuthorizedUpgrade, CodeUpgradeAuthorization, Config};
+	use frame_support::pallet_prelude::*;
+	use sp_runtime::traits::Saturating;
+
+	pub fn migrate<T: Config>() -> Weight {
+		let expires_at = frame_system::Pallet::<T>::block_number()
+			.saturating_add(T::UpgradeAuthorizationPeriod::get());
+		let _ = AuthorizedUpgrade::<T>::translate::<T::Hash, _>(|code_hash| {
+			code_hash.map(|code_hash| CodeUpgradeAuthorization {
+				code_hash,
+				check_version: false,
+				expires_at,
+			})
+		});
+
+		T::DbWeight::get().reads_writes(2, 1)
+	}
+}
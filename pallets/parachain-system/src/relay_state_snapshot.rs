@@ -18,7 +18,9 @@ use codec::{Decode, Encode};
 use cumulus_primitives_core::{
 	relay_chain, AbridgedHostConfiguration, AbridgedHrmpChannel, ParaId,
 };
+use polkadot_parachain::primitives::HeadData;
 use scale_info::TypeInfo;
+use sp_io::hashing::{twox_128, twox_64};
 use sp_runtime::traits::HashFor;
 use sp_state_machine::{Backend, TrieBackend};
 use sp_std::vec::Vec;
@@ -81,6 +83,8 @@ pub enum Error {
 	HrmpEgressChannelIndex(ReadEntryErr),
 	/// The channel identified by the sender and receiver cannot be extracted.
 	HrmpChannel(ParaId, ParaId, ReadEntryErr),
+	/// The head of the given para cannot be extracted.
+	ParaHead(ParaId, ReadEntryErr),
 }
 
 #[derive(Debug)]
@@ -93,6 +97,13 @@ pub enum ReadEntryErr {
 	Absent,
 }
 
+/// The key of the head of the given para in the `Heads` map of the relay chain's `paras` pallet.
+pub fn para_head_key(para_id: ParaId) -> Vec<u8> {
+	let encoded_id = para_id.encode();
+	[&twox_128(b"Paras")[..], &twox_128(b"Heads")[..], &twox_64(&encoded_id)[..], &encoded_id[..]]
+		.concat()
+}
+
 /// Read an entry given by the key and try to decode it. If the value specified by the key according
 /// to the proof is empty, the `fallback` value will be returned.
 ///
@@ -241,6 +252,23 @@ impl RelayChainStateProof {
 			.map_err(Error::Slot)
 	}
 
+	/// Read the raw entry under the given `key` from the relay chain state proof.
+	///
+	/// Returns `None` if the value is absent on the relay chain and an error if the key isn't
+	/// covered by the proof.
+	pub fn read_raw_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ReadEntryErr> {
+		self.trie_backend.storage(key).map_err(|_| ReadEntryErr::Proof)
+	}
+
+	/// Read the head of the given para from the relay chain state proof.
+	///
+	/// Returns `None` if the para has no head on the relay chain, e.g. because it was offboarded.
+	/// Returns an error if anything failed at reading or decoding.
+	pub fn read_para_head(&self, para_id: ParaId) -> Result<Option<HeadData>, Error> {
+		read_optional_entry(&self.trie_backend, &para_head_key(para_id))
+			.map_err(|read_err| Error::ParaHead(para_id, read_err))
+	}
+
 	/// Read the go-ahead signal for the upgrade from the relay chain state proof.
 	///
 	/// The go-ahead specifies whether the parachain can apply the upgrade or should abort it. If
@@ -35,7 +35,7 @@ use relay_chain::v1::HrmpChannelId;
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup},
+	traits::{BlakeTwo256, IdentityLookup, ValidateUnsigned},
 };
 use sp_version::RuntimeVersion;
 use std::cell::RefCell;
@@ -71,6 +71,11 @@ parameter_types! {
 	pub const ParachainId: ParaId = ParaId::new(200);
 	pub const ReservedXcmpWeight: Weight = 0;
 	pub const ReservedDmpWeight: Weight = 0;
+	pub const MaxPendingUpwardMessages: u32 = 40;
+	pub AdditionalRelayStorageKeys: Vec<Vec<u8>> = vec![b"relay_entry".to_vec()];
+	pub TrackedSiblingParas: Vec<ParaId> = vec![ParaId::from(300)];
+	pub const UpgradeAuthorizationPeriod: u64 = 10;
+	pub const RelayParentHistoryLength: u32 = 2;
 }
 impl frame_system::Config for Test {
 	type Origin = Origin;
@@ -100,22 +105,41 @@ impl frame_system::Config for Test {
 }
 impl Config for Test {
 	type Event = Event;
-	type OnSystemEvent = ();
+	type OnSystemEvent = RecordSystemEvents;
 	type SelfParaId = ParachainId;
 	type OutboundXcmpMessageSource = FromThreadLocal;
 	type DmpMessageHandler = SaveIntoThreadLocal;
 	type ReservedDmpWeight = ReservedDmpWeight;
 	type XcmpMessageHandler = SaveIntoThreadLocal;
 	type ReservedXcmpWeight = ReservedXcmpWeight;
+	type MaxPendingUpwardMessages = MaxPendingUpwardMessages;
+	type AdditionalRelayStorageKeys = AdditionalRelayStorageKeys;
+	type TrackedSiblingParas = TrackedSiblingParas;
+	type UpgradeAuthorizationPeriod = UpgradeAuthorizationPeriod;
+	type RelayParentHistoryLength = RelayParentHistoryLength;
+	type WeightInfo = ();
 }
 
 pub struct FromThreadLocal;
 pub struct SaveIntoThreadLocal;
+pub struct RecordSystemEvents;
 
 std::thread_local! {
 	static HANDLED_DMP_MESSAGES: RefCell<Vec<(relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static HANDLED_XCMP_MESSAGES: RefCell<Vec<(ParaId, relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static SENT_MESSAGES: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+	static SYSTEM_EVENTS: RefCell<Vec<SystemEvent>> = RefCell::new(Vec::new());
+}
+
+#[derive(Debug, PartialEq)]
+enum SystemEvent {
+	ChannelOpened(ParaId, ChannelDirection),
+	ChannelClosed(ParaId, ChannelDirection),
+	HostConfigurationChanged,
+}
+
+fn take_system_events() -> Vec<SystemEvent> {
+	SYSTEM_EVENTS.with(|e| e.take())
 }
 
 fn send_message(dest: ParaId, message: Vec<u8>) {
@@ -145,6 +169,20 @@ impl XcmpMessageSource for FromThreadLocal {
 	}
 }
 
+impl OnSystemEvent for RecordSystemEvents {
+	fn on_validation_data(_: &PersistedValidationData) {}
+	fn on_validation_code_applied() {}
+	fn on_hrmp_channel_opened(para_id: ParaId, direction: ChannelDirection) {
+		SYSTEM_EVENTS.with(|e| e.borrow_mut().push(SystemEvent::ChannelOpened(para_id, direction)));
+	}
+	fn on_hrmp_channel_closed(para_id: ParaId, direction: ChannelDirection) {
+		SYSTEM_EVENTS.with(|e| e.borrow_mut().push(SystemEvent::ChannelClosed(para_id, direction)));
+	}
+	fn on_host_configuration_changed(_: &AbridgedHostConfiguration, _: &AbridgedHostConfiguration) {
+		SYSTEM_EVENTS.with(|e| e.borrow_mut().push(SystemEvent::HostConfigurationChanged));
+	}
+}
+
 impl DmpMessageHandler for SaveIntoThreadLocal {
 	fn handle_dmp_messages(
 		iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>,
@@ -175,7 +213,7 @@ impl XcmpMessageHandler for SaveIntoThreadLocal {
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
-fn new_test_ext() -> sp_io::TestExternalities {
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 	HANDLED_DMP_MESSAGES.with(|m| m.borrow_mut().clear());
 	HANDLED_XCMP_MESSAGES.with(|m| m.borrow_mut().clear());
 
@@ -508,6 +546,213 @@ fn checks_size() {
 		});
 }
 
+#[test]
+fn authorized_upgrade() {
+	let code = vec![1, 2, 3];
+	let code_hash = BlakeTwo256::hash(&code);
+
+	BlockTests::new().add(1, move || {
+		assert_ok!(ParachainSystem::authorize_upgrade(RawOrigin::Root.into(), code_hash, true));
+		assert_ok!(ParachainSystem::cancel_authorized_upgrade(RawOrigin::Root.into()));
+		assert_eq!(
+			ParachainSystem::enact_authorized_upgrade(RawOrigin::None.into(), code.clone()),
+			Err(Error::<Test>::NothingAuthorized.into()),
+		);
+
+		assert_ok!(ParachainSystem::authorize_upgrade(RawOrigin::Root.into(), code_hash, true));
+		assert_eq!(
+			ParachainSystem::enact_authorized_upgrade(RawOrigin::None.into(), vec![4, 5, 6]),
+			Err(Error::<Test>::Unauthorized.into()),
+		);
+		assert_ok!(ParachainSystem::enact_authorized_upgrade(RawOrigin::None.into(), code.clone()));
+		assert_eq!(<PendingValidationCode<Test>>::get(), code);
+		assert!(AuthorizedUpgrade::<Test>::get().is_none());
+	});
+}
+
+#[test]
+fn authorized_upgrade_checks_version_before_inclusion() {
+	let code = vec![1, 2, 3];
+	let code_hash = BlakeTwo256::hash(&code);
+	let call = crate::Call::<Test>::enact_authorized_upgrade { code: code.clone() };
+
+	BlockTests::new().add(1, move || {
+		assert_ok!(ParachainSystem::authorize_upgrade(RawOrigin::Root.into(), code_hash, true));
+		assert!(ParachainSystem::validate_unsigned(TransactionSource::External, &call).is_ok());
+	});
+
+	// The version of the code is the same as the one of the current runtime.
+	let version =
+		RuntimeVersion { spec_name: "test".into(), spec_version: 1, ..Default::default() };
+	let mut ext = new_test_ext();
+	ext.register_extension(sp_core::traits::ReadRuntimeVersionExt::new(ReadRuntimeVersion(
+		version.encode(),
+	)));
+	ext.execute_with(|| {
+		assert_ok!(ParachainSystem::authorize_upgrade(RawOrigin::Root.into(), code_hash, true));
+		let call = crate::Call::<Test>::enact_authorized_upgrade { code: code.clone() };
+		assert!(ParachainSystem::validate_unsigned(TransactionSource::External, &call).is_err());
+		assert_eq!(
+			ParachainSystem::enact_authorized_upgrade(RawOrigin::None.into(), code),
+			Err(frame_system::Error::<Test>::SpecVersionNeedsToIncrease.into()),
+		);
+	});
+}
+
+#[test]
+fn authorized_upgrade_expires() {
+	let code = vec![1, 2, 3];
+	let code_hash = BlakeTwo256::hash(&code);
+
+	BlockTests::new()
+		.add(1, move || {
+			assert_ok!(ParachainSystem::authorize_upgrade(
+				RawOrigin::Root.into(),
+				code_hash,
+				false
+			));
+		})
+		.add(12, move || {
+			assert_eq!(
+				ParachainSystem::enact_authorized_upgrade(RawOrigin::None.into(), code.clone()),
+				Err(Error::<Test>::AuthorizationExpired.into()),
+			);
+		});
+}
+
+#[test]
+fn cancel_pending_upgrade() {
+	BlockTests::new()
+		.add(123, || {
+			assert_eq!(
+				ParachainSystem::cancel_pending_upgrade(RawOrigin::Root.into()),
+				Err(Error::<Test>::NotScheduled.into()),
+			);
+			assert_ok!(System::set_code(RawOrigin::Root.into(), Default::default()));
+			assert_ok!(ParachainSystem::cancel_pending_upgrade(RawOrigin::Root.into()));
+			assert!(!<PendingValidationCode<Test>>::exists());
+			assert!(!NewValidationCode::<Test>::exists());
+			assert_eq!(
+				System::events().pop().unwrap().event,
+				Event::ParachainSystem(crate::Event::PendingUpgradeCancelled.into())
+			);
+		})
+		.add(1234, || {
+			// The code can be scheduled again, but not cancelled once it was sent.
+			assert_ok!(System::set_code(RawOrigin::Root.into(), Default::default()));
+		})
+		.add(1235, || {
+			assert_eq!(
+				ParachainSystem::cancel_pending_upgrade(RawOrigin::Root.into()),
+				Err(Error::<Test>::UpgradeAlreadySignalled.into()),
+			);
+			assert!(<PendingValidationCode<Test>>::exists());
+		});
+}
+
+#[test]
+fn upgrade_status() {
+	let code = vec![1, 2, 3];
+	let code_hash = BlakeTwo256::hash(&code);
+
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, relay_block_num, builder| match relay_block_num {
+			1 | 2 => {},
+			3 => {
+				builder.upgrade_restriction = Some(relay_chain::v1::UpgradeRestriction::Present);
+			},
+			4 => {
+				builder.upgrade_go_ahead = Some(relay_chain::v1::UpgradeGoAhead::GoAhead);
+			},
+			_ => unreachable!(),
+		})
+		.add(1, move || {
+			assert_ok!(ParachainSystem::authorize_upgrade(
+				RawOrigin::Root.into(),
+				code_hash,
+				false
+			));
+			assert_eq!(
+				ParachainSystem::upgrade_status(),
+				UpgradeStatus {
+					authorized_code_hash: Some(code_hash),
+					pending: None,
+					restricted: false,
+				},
+			);
+		})
+		.add(2, move || {
+			assert_ok!(ParachainSystem::enact_authorized_upgrade(
+				RawOrigin::None.into(),
+				code.clone()
+			));
+			assert_eq!(
+				ParachainSystem::upgrade_status(),
+				UpgradeStatus {
+					authorized_code_hash: None,
+					pending: Some(PendingUpgrade {
+						code_hash,
+						code_size: 3,
+						signalled_at: 2,
+						expected_at: 8,
+					}),
+					restricted: false,
+				},
+			);
+		})
+		.add_with_post_test(
+			3,
+			|| {},
+			|| {
+				assert!(ParachainSystem::upgrade_status().restricted);
+				assert!(System::events()
+					.iter()
+					.any(|r| r.event == Event::ParachainSystem(crate::Event::UpgradeRestricted)));
+			},
+		)
+		.add_with_post_test(
+			4,
+			|| {},
+			|| {
+				assert_eq!(
+					ParachainSystem::upgrade_status(),
+					UpgradeStatus { authorized_code_hash: None, pending: None, restricted: false },
+				);
+				assert!(System::events().iter().any(|r| r.event ==
+					Event::ParachainSystem(crate::Event::UpgradeRestrictionLifted)));
+			},
+		);
+}
+
+#[test]
+fn relay_parent_history() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, relay_block_num, builder| {
+			// Make the storage roots of the relay parents differ.
+			builder.current_slot = (relay_block_num as u64).into();
+		})
+		.add(1, || {})
+		.add(2, || {})
+		.add_with_post_test(
+			3,
+			|| {},
+			|| {
+				let storage_root = ValidationData::<Test>::get().unwrap().relay_parent_storage_root;
+				let history = ParachainSystem::relay_parent_history();
+				assert_eq!(
+					history.iter().map(|(number, _)| *number).collect::<Vec<_>>(),
+					vec![2, 3]
+				);
+				assert_eq!(history[1].1, storage_root);
+				assert_ne!(history[0].1, storage_root);
+
+				assert_eq!(ParachainSystem::relay_parent_storage_root(3), Some(storage_root));
+				assert_eq!(ParachainSystem::relay_parent_storage_root(2), Some(history[0].1));
+				assert_eq!(ParachainSystem::relay_parent_storage_root(1), None);
+			},
+		);
+}
+
 #[test]
 fn send_upward_message_num_per_candidate() {
 	BlockTests::new()
@@ -570,6 +815,269 @@ fn send_upward_message_relay_bottleneck() {
 		);
 }
 
+#[test]
+fn send_upward_message_queue_is_bounded() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.host_config.max_upward_message_num_per_candidate = 0;
+		})
+		.add(1, || {
+			for i in 0..MaxPendingUpwardMessages::get() {
+				assert_eq!(ParachainSystem::send_upward_message(vec![0u8; 8]), Ok(i));
+			}
+			assert_eq!(
+				ParachainSystem::send_upward_message(vec![0u8; 8]),
+				Err(MessageSendError::QueueFull),
+			);
+		});
+}
+
+#[test]
+fn send_upward_message_is_paged() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.host_config.max_upward_queue_count = 100;
+			sproof.host_config.max_upward_message_num_per_candidate = 25;
+		})
+		.add_with_post_test(
+			1,
+			|| {
+				for i in 0..MaxPendingUpwardMessages::get() as u8 {
+					ParachainSystem::send_upward_message(vec![i]).unwrap();
+				}
+				assert_eq!(<UpwardQueue<Test>>::get().end_page, 2);
+			},
+			|| {
+				let v = UpwardMessages::<Test>::get();
+				assert_eq!(v, (0..25).map(|i| vec![i]).collect::<Vec<_>>());
+				assert_eq!(<UpwardQueue<Test>>::get().count, 15);
+				assert_eq!(<PendingUpwardMessagePages<Test>>::get(0).len(), 7);
+			},
+		)
+		.add_with_post_test(
+			2,
+			|| { /* do nothing within block */ },
+			|| {
+				let v = UpwardMessages::<Test>::get();
+				assert_eq!(v, (25..40).map(|i| vec![i]).collect::<Vec<_>>());
+				assert_eq!(
+					<UpwardQueue<Test>>::get(),
+					UpwardQueueState { next_id: 40, ..Default::default() }
+				);
+			},
+		);
+}
+
+#[test]
+fn send_upward_message_events() {
+	BlockTests::new().add_with_post_test(
+		1,
+		|| {
+			ParachainSystem::send_upward_message(b"message 1".to_vec()).unwrap();
+		},
+		|| {
+			let events = System::events().into_iter().map(|r| r.event).collect::<Vec<_>>();
+			assert_eq!(
+				events,
+				vec![
+					Event::ParachainSystem(crate::Event::UpwardMessageQueued(
+						0,
+						BlakeTwo256::hash(b"message 1"),
+					)),
+					Event::ParachainSystem(crate::Event::UpwardMessageSent(0)),
+				]
+			);
+		},
+	);
+}
+
+#[test]
+fn send_upward_message_drops_messages_that_no_longer_fit() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, relay_block_num, sproof| match relay_block_num {
+			1 => sproof.host_config.max_upward_message_num_per_candidate = 0,
+			2 => sproof.host_config.max_upward_message_size = 50,
+			_ => unreachable!(),
+		})
+		.add_with_post_test(
+			1,
+			|| {
+				ParachainSystem::send_upward_message(vec![0u8; 100]).unwrap();
+				ParachainSystem::send_upward_message(vec![1u8; 10]).unwrap();
+			},
+			|| {
+				assert!(UpwardMessages::<Test>::get().is_empty());
+			},
+		)
+		.add_with_post_test(
+			2,
+			|| { /* do nothing within block */ },
+			|| {
+				assert_eq!(UpwardMessages::<Test>::get(), vec![vec![1u8; 10]]);
+				let events = System::events();
+				assert_eq!(
+					events[0].event,
+					Event::ParachainSystem(crate::Event::UpwardMessageDropped(0))
+				);
+				assert_eq!(
+					events[1].event,
+					Event::ParachainSystem(crate::Event::UpwardMessageSent(1))
+				);
+			},
+		);
+}
+
+#[test]
+fn read_relay_entry() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, _, sproof| {
+			sproof.additional_key_values = vec![(b"relay_entry".to_vec(), 42u32.encode())];
+		})
+		.add(1, || {
+			assert_eq!(ParachainSystem::read_relay_entry::<u32>(b"relay_entry").unwrap(), Some(42));
+			assert!(matches!(
+				ParachainSystem::read_relay_entry::<u64>(b"relay_entry"),
+				Err(ReadEntryErr::Decode)
+			));
+			assert!(matches!(
+				ParachainSystem::read_relay_entry::<u32>(b"undeclared"),
+				Err(ReadEntryErr::Proof)
+			));
+		});
+}
+
+#[test]
+fn relay_entries_are_cleared_in_the_next_block() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, block_number, sproof| {
+			if block_number == 1 {
+				sproof.additional_key_values = vec![(b"relay_entry".to_vec(), 42u32.encode())];
+			}
+		})
+		.add(1, || {
+			assert_eq!(ParachainSystem::read_relay_entry::<u32>(b"relay_entry").unwrap(), Some(42));
+		})
+		.add(2, || {
+			assert_eq!(
+				ParachainSystem::read_relay_entry::<u32>(b"relay_entry").ok().flatten(),
+				None
+			);
+		});
+}
+
+#[test]
+fn sibling_para_heads() {
+	let sibling_header = Header::new(
+		10,
+		H256::repeat_byte(1),
+		H256::repeat_byte(2),
+		H256::repeat_byte(3),
+		Default::default(),
+	);
+	let head = HeadData(sibling_header.encode());
+	let expected = sibling_header.clone();
+
+	BlockTests::new()
+		.with_relay_sproof_builder(move |_, relay_block_num, sproof| {
+			// The head of para 300 is left out of the proof after the first block.
+			if relay_block_num == 1 {
+				sproof.additional_key_values = vec![
+					(para_head_key(ParaId::from(300)), head.encode()),
+					(para_head_key(ParaId::from(400)), head.encode()),
+				];
+			}
+		})
+		.add(1, move || {
+			assert!(ParachainSystem::additional_relay_storage_keys()
+				.contains(&para_head_key(ParaId::from(300))));
+			assert_eq!(
+				ParachainSystem::sibling_para_header::<Header>(ParaId::from(300)),
+				Some((sibling_header.clone(), H256::repeat_byte(2), 1)),
+			);
+			// Only tracked paras are stored.
+			assert!(ParachainSystem::sibling_para_head(ParaId::from(400)).is_none());
+		})
+		.add(2, move || {
+			// The head is kept, but tells that it was read at an older relay parent.
+			assert_eq!(
+				ParachainSystem::sibling_para_header::<Header>(ParaId::from(300)),
+				Some((expected.clone(), H256::repeat_byte(2), 1)),
+			);
+		});
+}
+
+#[test]
+fn hrmp_channel_and_host_configuration_changes() {
+	BlockTests::new()
+		.with_relay_sproof_builder(|_, relay_block_num, sproof| {
+			let outbound_channel = || AbridgedHrmpChannel {
+				max_capacity: 1,
+				msg_count: 0,
+				max_total_size: 1024,
+				max_message_size: 8,
+				total_size: 0,
+				mqc_head: None,
+			};
+			match relay_block_num {
+				1 => {},
+				2 | 3 => {
+					sproof.hrmp_egress_channel_index = Some(vec![ParaId::from(400)]);
+					sproof.hrmp_channels.insert(
+						HrmpChannelId { sender: ParaId::from(200), recipient: ParaId::from(400) },
+						outbound_channel(),
+					);
+					if relay_block_num == 2 {
+						sproof.upsert_inbound_channel(ParaId::from(300));
+					} else {
+						sproof.host_config.max_upward_message_size = 128;
+					}
+				},
+				_ => unreachable!(),
+			}
+		})
+		.add(1, || {
+			// There is nothing to compare to in the first block.
+			assert!(take_system_events().is_empty());
+		})
+		.add_with_post_test(
+			2,
+			|| {
+				assert_eq!(
+					take_system_events(),
+					vec![
+						SystemEvent::ChannelOpened(ParaId::from(300), ChannelDirection::Inbound),
+						SystemEvent::ChannelOpened(ParaId::from(400), ChannelDirection::Outbound),
+					]
+				);
+			},
+			|| {
+				assert!(System::events().iter().any(|r| r.event ==
+					Event::ParachainSystem(crate::Event::HrmpChannelOpened(
+						ParaId::from(300),
+						ChannelDirection::Inbound
+					))));
+			},
+		)
+		.add_with_post_test(
+			3,
+			|| {
+				assert_eq!(
+					take_system_events(),
+					vec![
+						SystemEvent::ChannelClosed(ParaId::from(300), ChannelDirection::Inbound),
+						SystemEvent::HostConfigurationChanged,
+					]
+				);
+			},
+			|| {
+				assert!(System::events()
+					.iter()
+					.any(|r| r.event ==
+						Event::ParachainSystem(crate::Event::HostConfigurationChanged)));
+			},
+		);
+}
+
 #[test]
 fn send_hrmp_message_buffer_channel_close() {
 	BlockTests::new()
@@ -587,7 +1095,7 @@ fn send_hrmp_message_buffer_channel_close() {
 					max_total_size: 1024,
 					max_message_size: 8,
 					total_size: 0,
-					mqc_head: Default::default(),
+					mqc_head: None,
 				},
 			);
 			sproof.hrmp_channels.insert(
@@ -598,7 +1106,7 @@ fn send_hrmp_message_buffer_channel_close() {
 					max_total_size: 1024,
 					max_message_size: 8,
 					total_size: 0,
-					mqc_head: Default::default(),
+					mqc_head: None,
 				},
 			);
 
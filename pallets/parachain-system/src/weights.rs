@@ -0,0 +1,118 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `cumulus_pallet_parachain_system`.
+//!
+//! The values below are hand-written estimates, they were NOT generated by the benchmarking CLI.
+//! They need to be replaced with the output of the benchmarks of this pallet before they are
+//! relied upon.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// The weight info trait for `cumulus_pallet_parachain_system`.
+pub trait WeightInfo {
+	/// The weight of `set_validation_data` with `d` downward messages, `c` inbound HRMP
+	/// channels and `h` horizontal messages, excluding the weight used by the message handlers.
+	fn set_validation_data(d: u32, c: u32, h: u32) -> Weight;
+	/// The weight of storing `k` entries of the relay chain state proof in
+	/// `RelayStateEntries`.
+	fn store_relay_state_entries(k: u32) -> Weight;
+	/// The weight of updating the heads of `s` tracked sibling parachains.
+	fn update_sibling_para_heads(s: u32) -> Weight;
+	/// The weight of adding the relay parent to a `RelayParentHistory` of `r` entries.
+	fn note_relay_parent(r: u32) -> Weight;
+	/// The weight of applying pending validation code of `b` bytes on go-ahead.
+	fn apply_pending_validation_code(b: u32) -> Weight;
+}
+
+/// Weights for `cumulus_pallet_parachain_system` using the Substrate node and recommended
+/// hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn set_validation_data(d: u32, c: u32, h: u32) -> Weight {
+		(150_000_000 as Weight)
+			.saturating_add((20_000 as Weight).saturating_mul(d as Weight))
+			.saturating_add((60_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add((25_000 as Weight).saturating_mul(h as Weight))
+			.saturating_add(T::DbWeight::get().reads(12 as Weight))
+			.saturating_add(T::DbWeight::get().writes(10 as Weight))
+	}
+	fn store_relay_state_entries(k: u32) -> Weight {
+		(1_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(k as Weight))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(k as Weight)))
+	}
+	fn update_sibling_para_heads(s: u32) -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add((6_000_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(s as Weight)))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(s as Weight)))
+	}
+	fn note_relay_parent(r: u32) -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add((50_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn apply_pending_validation_code(b: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((2 as Weight).saturating_mul(b as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn set_validation_data(d: u32, c: u32, h: u32) -> Weight {
+		(150_000_000 as Weight)
+			.saturating_add((20_000 as Weight).saturating_mul(d as Weight))
+			.saturating_add((60_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add((25_000 as Weight).saturating_mul(h as Weight))
+			.saturating_add(RocksDbWeight::get().reads(12 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(10 as Weight))
+	}
+	fn store_relay_state_entries(k: u32) -> Weight {
+		(1_000_000 as Weight)
+			.saturating_add((4_000_000 as Weight).saturating_mul(k as Weight))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(k as Weight)))
+	}
+	fn update_sibling_para_heads(s: u32) -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add((6_000_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(s as Weight)))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(s as Weight)))
+	}
+	fn note_relay_parent(r: u32) -> Weight {
+		(2_000_000 as Weight)
+			.saturating_add((50_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn apply_pending_validation_code(b: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((2 as Weight).saturating_mul(b as Weight))
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+}
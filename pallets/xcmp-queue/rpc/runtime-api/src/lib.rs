@@ -0,0 +1,37 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for inspecting the queues of the XCMP queue pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use cumulus_pallet_xcmp_queue::{
+	InboundChannelInfo, InboundState, OutboundChannelInfo, OutboundState, OverweightMessageInfo,
+};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Runtime api to inspect the inbound, outbound and overweight queues of the XCMP queue.
+	pub trait XcmpQueueApi {
+		/// Information about every inbound channel which has messages waiting to be executed.
+		fn inbound_channels() -> Vec<InboundChannelInfo>;
+		/// Information about every outbound channel which is suspended or has messages or signals
+		/// waiting to be sent.
+		fn outbound_channels() -> Vec<OutboundChannelInfo>;
+		/// All messages in the overweight queue.
+		fn overweight_messages() -> Vec<OverweightMessageInfo>;
+	}
+}
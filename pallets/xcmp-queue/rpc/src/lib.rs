@@ -0,0 +1,92 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for inspecting the queues of the XCMP queue pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use cumulus_pallet_xcmp_queue_rpc_runtime_api::{
+	InboundChannelInfo, OutboundChannelInfo, OverweightMessageInfo,
+	XcmpQueueApi as XcmpQueueRuntimeApi,
+};
+
+/// Error code returned when the runtime api call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// XCMP queue RPC methods.
+#[rpc]
+pub trait XcmpQueueApi<BlockHash> {
+	/// The inbound channels with messages waiting to be executed.
+	#[rpc(name = "xcmpQueue_inboundChannels")]
+	fn inbound_channels(&self, at: Option<BlockHash>) -> Result<Vec<InboundChannelInfo>>;
+
+	/// The outbound channels which are suspended or have messages or signals waiting to be sent.
+	#[rpc(name = "xcmpQueue_outboundChannels")]
+	fn outbound_channels(&self, at: Option<BlockHash>) -> Result<Vec<OutboundChannelInfo>>;
+
+	/// The messages in the overweight queue.
+	#[rpc(name = "xcmpQueue_overweightMessages")]
+	fn overweight_messages(&self, at: Option<BlockHash>) -> Result<Vec<OverweightMessageInfo>>;
+}
+
+/// Implements the [`XcmpQueueApi`] RPC trait for inspecting the XCMP queue.
+pub struct XcmpQueue<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> XcmpQueue<C, B> {
+	/// Create new `XcmpQueue` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: PhantomData }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the XCMP queue.".into(),
+		data: Some(e.to_string().into()),
+	}
+}
+
+impl<C, Block> XcmpQueueApi<<Block as BlockT>::Hash> for XcmpQueue<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: XcmpQueueRuntimeApi<Block>,
+{
+	fn inbound_channels(&self, at: Option<Block::Hash>) -> Result<Vec<InboundChannelInfo>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().inbound_channels(&at).map_err(runtime_error)
+	}
+
+	fn outbound_channels(&self, at: Option<Block::Hash>) -> Result<Vec<OutboundChannelInfo>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().outbound_channels(&at).map_err(runtime_error)
+	}
+
+	fn overweight_messages(&self, at: Option<Block::Hash>) -> Result<Vec<OverweightMessageInfo>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().overweight_messages(&at).map_err(runtime_error)
+	}
+}
@@ -0,0 +1,138 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarking setup for cumulus-pallet-xcmp-queue
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::EnsureOrigin;
+
+/// The maximum number of inbound channels with queued messages.
+const MAX_INBOUND_CHANNELS: u32 = 100;
+/// The number of messages in a benchmarked page.
+const MESSAGES_PER_PAGE: usize = 100;
+
+/// The `i`th sibling parachain used in the benchmarks.
+fn sender(i: u32) -> ParaId {
+	ParaId::from(1_000 + i)
+}
+
+/// An XCM that doesn't do anything. It weighs nothing, so it passes the usual barriers.
+fn empty_xcm<T: Config>() -> Vec<u8> {
+	VersionedXcm::<T::Call>::from(Xcm::<T::Call>(vec![])).encode()
+}
+
+/// Put `n` empty XCMs into the overweight queue.
+fn stash_overweights<T: Config>(n: u32) {
+	for _ in 0..n {
+		Pallet::<T>::stash_overweight(sender(0), 1, empty_xcm::<T>());
+	}
+}
+
+/// A page of `MESSAGES_PER_PAGE` empty XCMs, excluding the message format.
+fn xcm_page<T: Config>() -> Vec<u8> {
+	(0..MESSAGES_PER_PAGE).flat_map(|_| empty_xcm::<T>()).collect()
+}
+
+benchmarks! {
+	service_overweight {
+		stash_overweights::<T>(1);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, 0, 0)
+	verify {
+		assert!(!Overweight::<T>::contains_key(0));
+	}
+
+	discard_overweight {
+		stash_overweights::<T>(1);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, 0, false)
+	verify {
+		assert!(!Overweight::<T>::contains_key(0));
+	}
+
+	discard_overweights {
+		let n in 1 .. T::MaxDiscard::get();
+		stash_overweights::<T>(n);
+		let origin = T::ExecuteOverweightOrigin::successful_origin();
+	}: _<T::Origin>(origin, (0..n as OverweightIndex).collect(), false)
+	verify {
+		assert_eq!(Overweight::<T>::iter().count(), 0);
+	}
+
+	set_config_with_u32 {
+		let origin = T::ControllerOrigin::successful_origin();
+	}: update_suspend_threshold<T::Origin>(origin, 3)
+	verify {
+		assert_eq!(QueueConfig::<T>::get().suspend_threshold, 3);
+	}
+
+	set_config_with_weight {
+		let origin = T::ControllerOrigin::successful_origin();
+	}: update_weight_restrict_decay<T::Origin>(origin, 3)
+	verify {
+		assert_eq!(QueueConfig::<T>::get().weight_restrict_decay, 3);
+	}
+
+	update_inbound_share {
+		let origin = T::ControllerOrigin::successful_origin();
+	}: _<T::Origin>(origin, sender(0), Some(5))
+	verify {
+		assert_eq!(InboundShares::<T>::get(sender(0)), Some(5));
+	}
+
+	// Servicing the queue with no weight, so the page is only recorded.
+	enqueue_xcmp_page {
+		let mut page = XcmpMessageFormat::ConcatenatedVersionedXcm.encode();
+		page.extend(xcm_page::<T>());
+	}: {
+		Pallet::<T>::handle_xcmp_messages(sp_std::iter::once((sender(0), 1, &page[..])), 0);
+	}
+	verify {
+		assert!(InboundXcmpMessages::<T>::contains_key(sender(0), 1));
+	}
+
+	process_xcmp_page {
+		InboundXcmpMessages::<T>::insert(sender(0), 1, xcm_page::<T>());
+		let page = (1, XcmpMessageFormat::ConcatenatedVersionedXcm);
+	}: {
+		Pallet::<T>::process_xcmp_message(sender(0), page, Weight::max_value(), Weight::max_value());
+	}
+	verify {
+		assert!(!InboundXcmpMessages::<T>::contains_key(sender(0), 1));
+	}
+
+	// The channels don't have any pages, so only the bookkeeping of the channels is measured.
+	service_xcmp_queue {
+		let c in 1 .. MAX_INBOUND_CHANNELS;
+		let status = (0..c)
+			.map(|i| InboundChannelDetails {
+				sender: sender(i),
+				state: InboundState::Ok,
+				message_metadata: Vec::new(),
+			})
+			.collect::<Vec<_>>();
+		InboundXcmpStatus::<T>::put(status);
+	}: {
+		Pallet::<T>::service_xcmp_queue(Weight::max_value());
+	}
+	verify {
+		assert!(InboundXcmpStatus::<T>::get().is_empty());
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -26,6 +26,10 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 pub mod migration;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod mock;
@@ -33,12 +37,16 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
-use codec::{Decode, DecodeAll, DecodeLimit, Encode};
+use codec::{Decode, DecodeLimit, Encode};
 use cumulus_primitives_core::{
 	relay_chain::BlockNumber as RelayBlockNumber, ChannelStatus, GetChannelInfo, MessageSendError,
-	ParaId, XcmpMessageFormat, XcmpMessageHandler, XcmpMessageSource,
+	ParaId, ServiceQuality, XcmpMessageFormat, XcmpMessageHandler, XcmpMessageSource,
+};
+use frame_support::{
+	dispatch::DispatchResult,
+	ensure,
+	weights::{constants::WEIGHT_PER_MILLIS, Weight},
 };
-use frame_support::weights::{constants::WEIGHT_PER_MILLIS, Weight};
 use rand_chacha::{
 	rand_core::{RngCore, SeedableRng},
 	ChaChaRng,
@@ -47,12 +55,53 @@ use scale_info::TypeInfo;
 use sp_runtime::{traits::Hash, RuntimeDebug};
 use sp_std::{convert::TryFrom, prelude::*};
 use xcm::{latest::prelude::*, VersionedXcm, WrapVersion, MAX_XCM_DECODE_DEPTH};
+use xcm_executor::traits::{DropAssets, FilterAssetLocation, TransactAsset};
 
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 /// Index used to identify overweight XCMs.
 pub type OverweightIndex = u64;
 
+/// The share of the inbound queue servicing weight of a sender without an entry in
+/// `InboundShares`.
+pub const DEFAULT_INBOUND_SHARE: u32 = 1;
+
+/// Error returned by a [`BlobHandler`].
+#[derive(Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum BlobHandlerError {
+	/// The blob needs the given amount of weight, which is more than it was allowed to use. It
+	/// is retried later, unless the required weight exceeds the maximum individual weight.
+	WeightLimitReached(Weight),
+	/// The blob could not be handled. It is dropped.
+	Unhandled,
+}
+
+/// Handler for raw blob messages, i.e. `XcmpMessageFormat::ConcatenatedEncodedBlob`, received from
+/// sibling parachains.
+pub trait BlobHandler {
+	/// Handle the `blob` which `sender` sent at relay block `sent_at`, using no more than
+	/// `weight_limit`. Returns the weight used.
+	fn handle_blob(
+		sender: ParaId,
+		sent_at: RelayBlockNumber,
+		blob: Vec<u8>,
+		weight_limit: Weight,
+	) -> Result<Weight, BlobHandlerError>;
+}
+
+/// Does not handle any blob; every blob is dropped.
+impl BlobHandler for () {
+	fn handle_blob(
+		_sender: ParaId,
+		_sent_at: RelayBlockNumber,
+		_blob: Vec<u8>,
+		_weight_limit: Weight,
+	) -> Result<Weight, BlobHandlerError> {
+		Err(BlobHandlerError::Unhandled)
+	}
+}
+
 const LOG_TARGET: &str = "xcmp_queue";
 
 #[frame_support::pallet]
@@ -81,6 +130,40 @@ pub mod pallet {
 
 		/// The origin that is allowed to execute overweight messages.
 		type ExecuteOverweightOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The origin that is allowed to change the `QueueConfig` of the XCMP queue.
+		type ControllerOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Where assets carried by discarded overweight XCMs are trapped, if requested.
+		type AssetTrap: DropAssets;
+
+		/// Which assets a sibling may deposit as their reserve. Should be the XCM executor's
+		/// `IsReserve`, assets of discarded XCMs which don't pass it are not trapped.
+		type IsReserve: FilterAssetLocation;
+
+		/// Which assets a sibling may teleport to us. Should be the XCM executor's `IsTeleporter`,
+		/// assets of discarded XCMs which don't pass it are not trapped.
+		type IsTeleporter: FilterAssetLocation;
+
+		/// Used to check in the teleported assets of discarded XCMs before they are trapped. Should
+		/// be the XCM executor's `AssetTransactor`.
+		type AssetTransactor: TransactAsset;
+
+		/// The maximum weight of trapping the assets of a single discarded XCM, including the
+		/// check in of teleported assets.
+		#[pallet::constant]
+		type TrapAssetsWeight: Get<Weight>;
+
+		/// The maximum number of overweight XCMs which can be discarded by a single call to
+		/// `discard_overweights`.
+		#[pallet::constant]
+		type MaxDiscard: Get<u32>;
+
+		/// Something to handle the raw, non-XCM blob messages sent by sibling parachains.
+		type BlobHandler: BlobHandler;
+
+		/// Weight information for the extrinsics and the queue servicing of this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	#[pallet::hooks]
@@ -110,7 +193,7 @@ pub mod pallet {
 		///
 		/// Events:
 		/// - `OverweightServiced`: On success.
-		#[pallet::weight(weight_limit.saturating_add(1_000_000))]
+		#[pallet::weight(weight_limit.saturating_add(T::WeightInfo::service_overweight()))]
 		pub fn service_overweight(
 			origin: OriginFor<T>,
 			index: OverweightIndex,
@@ -127,7 +210,209 @@ pub mod pallet {
 				.map_err(|_| Error::<T>::WeightOverLimit)?;
 			Overweight::<T>::remove(index);
 			Self::deposit_event(Event::OverweightServiced(index, used));
-			Ok(Some(used.saturating_add(1_000_000)).into())
+			Ok(Some(used.saturating_add(T::WeightInfo::service_overweight())).into())
+		}
+
+		/// Discards a single overweight XCM without executing it.
+		///
+		/// - `origin`: Must pass `ExecuteOverweightOrigin`.
+		/// - `index`: The index of the overweight XCM to discard.
+		/// - `trap_assets`: Whether the assets the XCM would have placed into holding should be
+		///   handed to `AssetTrap`, so that they can be claimed later. The assets are only trapped
+		///   if they pass the same reserve and teleport checks as when the XCM is executed.
+		///
+		/// Errors:
+		/// - `BadOverweightIndex`: XCM under `index` is not found in the `Overweight` storage map.
+		///
+		/// Events:
+		/// - `OverweightDiscarded`: On success.
+		#[pallet::weight(
+			T::WeightInfo::discard_overweight()
+				.saturating_add(Pallet::<T>::trap_weight(1, *trap_assets))
+		)]
+		pub fn discard_overweight(
+			origin: OriginFor<T>,
+			index: OverweightIndex,
+			trap_assets: bool,
+		) -> DispatchResultWithPostInfo {
+			T::ExecuteOverweightOrigin::ensure_origin(origin)?;
+
+			let (sender, _, data) =
+				Overweight::<T>::take(index).ok_or(Error::<T>::BadOverweightIndex)?;
+			let used = if trap_assets { Self::trap_overweight_assets(sender, &data) } else { 0 };
+			Self::deposit_event(Event::OverweightDiscarded(index));
+			Ok(Some(T::WeightInfo::discard_overweight().saturating_add(used)).into())
+		}
+
+		/// Discards a number of overweight XCMs without executing them.
+		///
+		/// Indices which are not found in the `Overweight` storage map are skipped.
+		///
+		/// - `origin`: Must pass `ExecuteOverweightOrigin`.
+		/// - `indices`: The indices of the overweight XCMs to discard, at most `MaxDiscard`.
+		/// - `trap_assets`: As for `discard_overweight`.
+		///
+		/// Errors:
+		/// - `TooManyIndices`: More than `MaxDiscard` indices are given.
+		///
+		/// Events:
+		/// - `OverweightDiscarded`: For each discarded XCM.
+		#[pallet::weight(
+			T::WeightInfo::discard_overweights(indices.len() as u32)
+				.saturating_add(Pallet::<T>::trap_weight(indices.len() as u32, *trap_assets))
+		)]
+		pub fn discard_overweights(
+			origin: OriginFor<T>,
+			indices: Vec<OverweightIndex>,
+			trap_assets: bool,
+		) -> DispatchResultWithPostInfo {
+			T::ExecuteOverweightOrigin::ensure_origin(origin)?;
+			ensure!(indices.len() as u32 <= T::MaxDiscard::get(), Error::<T>::TooManyIndices);
+
+			let mut used = T::WeightInfo::discard_overweights(indices.len() as u32);
+			for index in indices {
+				if let Some((sender, _, data)) = Overweight::<T>::take(index) {
+					if trap_assets {
+						used = used.saturating_add(Self::trap_overweight_assets(sender, &data));
+					}
+					Self::deposit_event(Event::OverweightDiscarded(index));
+				}
+			}
+			Ok(Some(used).into())
+		}
+
+		/// Overwrites the number of pages of messages which must be in the queue for the other side
+		/// to be told to suspend their sending.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.suspend_threshold`.
+		///
+		/// Errors:
+		/// - `BadQueueConfig`: `new` is not between `resume_threshold` and `drop_threshold`.
+		///
+		/// Events:
+		/// - `SuspendThresholdUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_u32(), DispatchClass::Operational))]
+		pub fn update_suspend_threshold(origin: OriginFor<T>, new: u32) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.suspend_threshold = new)?;
+			Self::deposit_event(Event::SuspendThresholdUpdated(new));
+			Ok(())
+		}
+
+		/// Overwrites the number of pages of messages which must be in the queue after which we
+		/// drop any further messages from the channel.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.drop_threshold`.
+		///
+		/// Errors:
+		/// - `BadQueueConfig`: `new` is not greater than `suspend_threshold`.
+		///
+		/// Events:
+		/// - `DropThresholdUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_u32(), DispatchClass::Operational))]
+		pub fn update_drop_threshold(origin: OriginFor<T>, new: u32) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.drop_threshold = new)?;
+			Self::deposit_event(Event::DropThresholdUpdated(new));
+			Ok(())
+		}
+
+		/// Overwrites the number of pages of messages which the queue must be reduced to before it
+		/// signals that message sending may recommence after it has been suspended.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.resume_threshold`.
+		///
+		/// Errors:
+		/// - `BadQueueConfig`: `new` is not less than `suspend_threshold`.
+		///
+		/// Events:
+		/// - `ResumeThresholdUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_u32(), DispatchClass::Operational))]
+		pub fn update_resume_threshold(origin: OriginFor<T>, new: u32) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.resume_threshold = new)?;
+			Self::deposit_event(Event::ResumeThresholdUpdated(new));
+			Ok(())
+		}
+
+		/// Overwrites the amount of remaining weight under which we stop processing messages.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.threshold_weight`. Must be non-zero.
+		///
+		/// Events:
+		/// - `ThresholdWeightUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_weight(), DispatchClass::Operational))]
+		pub fn update_threshold_weight(origin: OriginFor<T>, new: Weight) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.threshold_weight = new)?;
+			Self::deposit_event(Event::ThresholdWeightUpdated(new));
+			Ok(())
+		}
+
+		/// Overwrites the speed to which the available weight approaches the maximum weight.
+		/// A lower number results in a faster progression. A value of 1 makes the entire weight
+		/// available initially.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.weight_restrict_decay`.
+		///
+		/// Events:
+		/// - `WeightRestrictDecayUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_weight(), DispatchClass::Operational))]
+		pub fn update_weight_restrict_decay(origin: OriginFor<T>, new: Weight) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.weight_restrict_decay = new)?;
+			Self::deposit_event(Event::WeightRestrictDecayUpdated(new));
+			Ok(())
+		}
+
+		/// Overwrite the maximum amount of weight any individual message may consume.
+		/// Messages above this weight go into the overweight queue and may only be serviced
+		/// explicitly.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `new`: Desired value for `QueueConfigData.xcmp_max_individual_weight`.
+		///
+		/// Errors:
+		/// - `BadQueueConfig`: `new` is zero.
+		///
+		/// Events:
+		/// - `XcmpMaxIndividualWeightUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::set_config_with_weight(), DispatchClass::Operational))]
+		pub fn update_xcmp_max_individual_weight(
+			origin: OriginFor<T>,
+			new: Weight,
+		) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			Self::update_queue_config(|data| data.xcmp_max_individual_weight = new)?;
+			Self::deposit_event(Event::XcmpMaxIndividualWeightUpdated(new));
+			Ok(())
+		}
+
+		/// Set the share of the weight for servicing the inbound XCMP queue that messages from
+		/// `sender` are guaranteed, relative to the shares of the other senders with queued
+		/// messages. Senders without an explicit share have `DEFAULT_INBOUND_SHARE`.
+		///
+		/// - `origin`: Must pass `ControllerOrigin`.
+		/// - `sender`: The sibling parachain whose share is set.
+		/// - `share`: The new share, or `None` to return to the default.
+		///
+		/// Events:
+		/// - `InboundShareUpdated`: On success.
+		#[pallet::weight((T::WeightInfo::update_inbound_share(), DispatchClass::Operational))]
+		pub fn update_inbound_share(
+			origin: OriginFor<T>,
+			sender: ParaId,
+			share: Option<u32>,
+		) -> DispatchResult {
+			T::ControllerOrigin::ensure_origin(origin)?;
+			<InboundShares<T>>::set(sender, share);
+			Self::deposit_event(Event::InboundShareUpdated(sender, share));
+			Ok(())
 		}
 	}
 
@@ -150,6 +435,41 @@ pub mod pallet {
 		OverweightEnqueued(ParaId, RelayBlockNumber, OverweightIndex, Weight),
 		/// An XCM from the overweight queue was executed with the given actual weight used.
 		OverweightServiced(OverweightIndex, Weight),
+		/// A blob message was handled by the `BlobHandler`.
+		/// \[ sender, hash, weight \]
+		BlobHandled(ParaId, T::Hash, Weight),
+		/// A blob message could not be handled and was dropped.
+		/// \[ sender, hash \]
+		BlobUnhandled(ParaId, T::Hash),
+		/// An outbound message was dropped, because its channel closed or because it no longer
+		/// fits into the channel. The hash is that of the encoded message, as in
+		/// `XcmpMessageSent`.
+		/// \[ recipient, message_hash \]
+		XcmpMessageDropped(ParaId, T::Hash),
+		/// An XCM from the overweight queue was discarded without being executed.
+		/// \[ index \]
+		OverweightDiscarded(OverweightIndex),
+		/// The suspend threshold of the queue configuration was changed.
+		/// \[ new \]
+		SuspendThresholdUpdated(u32),
+		/// The drop threshold of the queue configuration was changed.
+		/// \[ new \]
+		DropThresholdUpdated(u32),
+		/// The resume threshold of the queue configuration was changed.
+		/// \[ new \]
+		ResumeThresholdUpdated(u32),
+		/// The threshold weight of the queue configuration was changed.
+		/// \[ new \]
+		ThresholdWeightUpdated(Weight),
+		/// The weight restrict decay of the queue configuration was changed.
+		/// \[ new \]
+		WeightRestrictDecayUpdated(Weight),
+		/// The maximum individual message weight of the queue configuration was changed.
+		/// \[ new \]
+		XcmpMaxIndividualWeightUpdated(Weight),
+		/// The share of the inbound queue servicing weight of a sender was changed.
+		/// \[ sender, share \]
+		InboundShareUpdated(ParaId, Option<u32>),
 	}
 
 	#[pallet::error]
@@ -164,8 +484,18 @@ pub mod pallet {
 		BadOverweightIndex,
 		/// Provided weight is possibly not enough to execute the message.
 		WeightOverLimit,
+		/// The new queue configuration would break its invariants. Thresholds must satisfy
+		/// `resume_threshold < suspend_threshold < drop_threshold`, and the threshold weight and the
+		/// maximum individual weight must be non-zero.
+		BadQueueConfig,
+		/// More overweight XCMs than `MaxDiscard` are to be discarded at once.
+		TooManyIndices,
 	}
 
+	/// The shares of the inbound queue servicing weight which are set for particular senders.
+	#[pallet::storage]
+	pub(super) type InboundShares<T: Config> = StorageMap<_, Twox64Concat, ParaId, u32>;
+
 	/// Status of the inbound XCMP channels.
 	#[pallet::storage]
 	pub(super) type InboundXcmpStatus<T: Config> =
@@ -204,6 +534,18 @@ pub mod pallet {
 	pub(super) type SignalMessages<T: Config> =
 		StorageMap<_, Blake2_128Concat, ParaId, Vec<u8>, ValueQuery>;
 
+	/// The index of the first and one past the last page in `FastOutboundXcmpMessages` of the
+	/// XCMP channels which have fast messages waiting to be sent.
+	#[pallet::storage]
+	pub(super) type FastOutboundXcmpIndices<T: Config> =
+		StorageMap<_, Blake2_128Concat, ParaId, (u16, u16)>;
+
+	/// The pages of messages sent with `ServiceQuality::Fast` in a given XCMP channel. These are
+	/// sent ahead of the pages in `OutboundXcmpMessages`.
+	#[pallet::storage]
+	pub(super) type FastOutboundXcmpMessages<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, ParaId, Twox64Concat, u16, Vec<u8>, ValueQuery>;
+
 	/// The configuration which controls the dynamics of the outbound queue.
 	#[pallet::storage]
 	pub(super) type QueueConfig<T: Config> = StorageValue<_, QueueConfigData, ValueQuery>;
@@ -223,12 +565,14 @@ pub mod pallet {
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub enum InboundState {
 	Ok,
 	Suspended,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutboundState {
 	Ok,
 	Suspended,
@@ -306,6 +650,21 @@ pub struct QueueConfigData {
 	xcmp_max_individual_weight: Weight,
 }
 
+impl QueueConfigData {
+	/// Whether the configuration is internally consistent.
+	///
+	/// The resume threshold has to be below the suspend threshold, which in turn has to be below
+	/// the drop threshold, otherwise a channel could never be resumed or would drop messages
+	/// without ever being suspended. The threshold weight has to be non-zero, otherwise servicing
+	/// the queue would only stop once all of the weight is used up.
+	pub fn is_valid(&self) -> bool {
+		self.resume_threshold < self.suspend_threshold &&
+			self.suspend_threshold < self.drop_threshold &&
+			self.threshold_weight > 0 &&
+			self.xcmp_max_individual_weight > 0
+	}
+}
+
 impl Default for QueueConfigData {
 	fn default() -> Self {
 		Self {
@@ -325,6 +684,55 @@ pub enum ChannelSignal {
 	Resume,
 }
 
+/// Summary of an inbound XCMP channel, as reported by the `XcmpQueueApi` runtime API.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct InboundChannelInfo {
+	/// The `ParaId` of the parachain that this channel is connected with.
+	pub sender: ParaId,
+	/// The state of the channel.
+	pub state: InboundState,
+	/// The number of pages, i.e. relay blocks worth of messages, waiting to be executed.
+	pub queued_pages: u32,
+	/// The total size in bytes of the pages waiting to be executed.
+	pub queued_bytes: u32,
+}
+
+/// Summary of an outbound XCMP channel, as reported by the `XcmpQueueApi` runtime API.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OutboundChannelInfo {
+	/// The `ParaId` of the parachain that this channel is connected with.
+	pub recipient: ParaId,
+	/// The state of the channel.
+	pub state: OutboundState,
+	/// The number of pages waiting to be sent.
+	pub queued_pages: u32,
+	/// The total size in bytes of the pages waiting to be sent.
+	pub queued_bytes: u32,
+	/// The number of pages in the fast lane waiting to be sent, ahead of the other pages.
+	pub queued_fast_pages: u32,
+	/// The number of signals waiting to be sent.
+	pub pending_signals: u32,
+}
+
+/// An entry of the overweight queue, as reported by the `XcmpQueueApi` runtime API.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OverweightMessageInfo {
+	/// The index under which the XCM is stored.
+	pub index: OverweightIndex,
+	/// The `ParaId` of the parachain that sent the XCM.
+	pub sender: ParaId,
+	/// The relay block number at which the XCM was sent.
+	pub sent_at: RelayBlockNumber,
+	/// The size in bytes of the encoded XCM.
+	pub size: u32,
+}
+
 impl<T: Config> Pallet<T> {
 	/// Place a message `fragment` on the outgoing XCMP queue for `recipient`.
 	///
@@ -346,10 +754,14 @@ impl<T: Config> Pallet<T> {
 	/// length prefixed and can thus decode each fragment from the aggregate stream. With this,
 	/// we can concatenate them into a single aggregate blob without needing to be concerned
 	/// about encoding fragment boundaries.
+	///
+	/// Fragments sent with `ServiceQuality::Fast` go into a separate lane of pages, which is
+	/// drained before any of the `Ordered` pages of the channel.
 	fn send_fragment<Fragment: Encode>(
 		recipient: ParaId,
 		format: XcmpMessageFormat,
 		fragment: Fragment,
+		qos: ServiceQuality,
 	) -> Result<u32, MessageSendError> {
 		let data = fragment.encode();
 
@@ -367,21 +779,30 @@ impl<T: Config> Pallet<T> {
 			s.push(OutboundChannelDetails::new(recipient));
 			s.len() - 1
 		});
+		if qos == ServiceQuality::Fast {
+			let (first_index, mut last_index) =
+				<FastOutboundXcmpIndices<T>>::get(recipient).unwrap_or_default();
+			let appended = last_index > first_index &&
+				<FastOutboundXcmpMessages<T>>::mutate(recipient, last_index - 1, |page| {
+					Self::append_to_page(page, format, &data[..], max_message_size)
+				});
+			if !appended {
+				if last_index == u16::MAX {
+					return Err(MessageSendError::QueueFull)
+				}
+				let mut new_page = format.encode();
+				new_page.extend_from_slice(&data[..]);
+				<FastOutboundXcmpMessages<T>>::insert(recipient, last_index, new_page);
+				last_index += 1;
+				<FastOutboundXcmpIndices<T>>::insert(recipient, (first_index, last_index));
+			}
+			<OutboundXcmpStatus<T>>::put(s);
+			return Ok((last_index - first_index - 1) as u32)
+		}
 		let have_active = s[index].last_index > s[index].first_index;
 		let appended = have_active &&
-			<OutboundXcmpMessages<T>>::mutate(recipient, s[index].last_index - 1, |s| {
-				if XcmpMessageFormat::decode_and_advance_with_depth_limit(
-					MAX_XCM_DECODE_DEPTH,
-					&mut &s[..],
-				) != Ok(format)
-				{
-					return false
-				}
-				if s.len() + data.len() > max_message_size {
-					return false
-				}
-				s.extend_from_slice(&data[..]);
-				return true
+			<OutboundXcmpMessages<T>>::mutate(recipient, s[index].last_index - 1, |page| {
+				Self::append_to_page(page, format, &data[..], max_message_size)
 			});
 		if appended {
 			Ok((s[index].last_index - s[index].first_index - 1) as u32)
@@ -398,6 +819,173 @@ impl<T: Config> Pallet<T> {
 		}
 	}
 
+	/// Append the encoded fragment `data` to `page`, if `page` holds fragments of the same
+	/// `format` and there is enough room left in it.
+	fn append_to_page(
+		page: &mut Vec<u8>,
+		format: XcmpMessageFormat,
+		data: &[u8],
+		max_message_size: usize,
+	) -> bool {
+		if XcmpMessageFormat::decode_and_advance_with_depth_limit(
+			MAX_XCM_DECODE_DEPTH,
+			&mut &page[..],
+		) != Ok(format)
+		{
+			return false
+		}
+		if page.len() + data.len() > max_message_size {
+			return false
+		}
+		page.extend_from_slice(data);
+		true
+	}
+
+	/// Split an outbound `page` into its format and the encoded fragments it holds.
+	fn decode_page(page: &[u8]) -> Option<(XcmpMessageFormat, Vec<&[u8]>)> {
+		let mut data = page;
+		let format =
+			XcmpMessageFormat::decode_and_advance_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut data)
+				.ok()?;
+		let mut fragments = Vec::new();
+		while !data.is_empty() {
+			let remaining = data;
+			let decoded = match format {
+				XcmpMessageFormat::ConcatenatedVersionedXcm =>
+					VersionedXcm::<()>::decode_and_advance_with_depth_limit(
+						MAX_XCM_DECODE_DEPTH,
+						&mut data,
+					)
+					.is_ok(),
+				XcmpMessageFormat::ConcatenatedEncodedBlob => <Vec<u8>>::decode(&mut data).is_ok(),
+				XcmpMessageFormat::Signals => false,
+			};
+			if !decoded {
+				return None
+			}
+			fragments.push(&remaining[..remaining.len() - data.len()]);
+		}
+		Some((format, fragments))
+	}
+
+	/// Deposit an `XcmpMessageDropped` event for each of the messages in the outbound `page`.
+	fn report_dropped_page(recipient: ParaId, page: &[u8]) {
+		match Self::decode_page(page) {
+			Some((_, fragments)) =>
+				for fragment in fragments {
+					let hash = T::Hashing::hash(fragment);
+					Self::deposit_event(Event::XcmpMessageDropped(recipient, hash));
+				},
+			None => log::warn!("Dropping undecodable XCMP page for {:?}.", recipient),
+		}
+	}
+
+	/// Split the outbound `page` for `recipient` into pages no bigger than `max_size`. Messages
+	/// which can never fit are dropped.
+	fn split_page(recipient: ParaId, page: &[u8], max_size: usize) -> Vec<Vec<u8>> {
+		let (format, fragments) = match Self::decode_page(page) {
+			Some(decoded) => decoded,
+			None => {
+				log::warn!("Dropping undecodable XCMP page for {:?}.", recipient);
+				return Vec::new()
+			},
+		};
+		let header = format.encode();
+		let mut pages: Vec<Vec<u8>> = Vec::new();
+		for fragment in fragments {
+			if header.len() + fragment.len() > max_size {
+				let hash = T::Hashing::hash(fragment);
+				Self::deposit_event(Event::XcmpMessageDropped(recipient, hash));
+				continue
+			}
+			match pages.last_mut() {
+				Some(page) if page.len() + fragment.len() <= max_size =>
+					page.extend_from_slice(fragment),
+				_ => {
+					let mut page = header.clone();
+					page.extend_from_slice(fragment);
+					pages.push(page);
+				},
+			}
+		}
+		pages
+	}
+
+	/// Replace the first page, at `first_index`, of the outbound `Pages` for `recipient` with
+	/// `pages`, moving the later pages back as needed. Returns the new first and last index of
+	/// the queue.
+	fn replace_first_page<Pages>(
+		recipient: ParaId,
+		first_index: u16,
+		last_index: u16,
+		mut pages: Vec<Vec<u8>>,
+	) -> (u16, u16)
+	where
+		Pages: frame_support::storage::StorageDoubleMap<ParaId, u16, Vec<u8>, Query = Vec<u8>>,
+	{
+		Pages::remove(recipient, first_index);
+		if pages.is_empty() {
+			return (first_index + 1, last_index)
+		}
+		let room = (u16::MAX - last_index) as usize;
+		if pages.len() - 1 > room {
+			for page in pages.drain(room + 1..) {
+				Self::report_dropped_page(recipient, &page);
+			}
+		}
+		let extra = (pages.len() - 1) as u16;
+		if extra > 0 {
+			for i in (first_index + 1..last_index).rev() {
+				let page = Pages::take(recipient, i);
+				Pages::insert(recipient, i + extra, page);
+			}
+		}
+		for (i, page) in pages.into_iter().enumerate() {
+			Pages::insert(recipient, first_index + i as u16, page);
+		}
+		(first_index, last_index + extra)
+	}
+
+	/// Take the first page of the fast lane of `recipient` if it is smaller than `max_size_now`,
+	/// splitting it first if it is bigger than `max_size_ever`.
+	fn take_fast_page(
+		recipient: ParaId,
+		max_size_now: usize,
+		max_size_ever: usize,
+	) -> Option<Vec<u8>> {
+		let (mut first_index, mut last_index) = <FastOutboundXcmpIndices<T>>::get(recipient)?;
+		if <FastOutboundXcmpMessages<T>>::decode_len(recipient, first_index).unwrap_or(0) >
+			max_size_ever
+		{
+			// The channel's maximum message size has shrunk since the page was filled.
+			let page = <FastOutboundXcmpMessages<T>>::get(recipient, first_index);
+			let split = Self::split_page(recipient, &page, max_size_ever);
+			let (first, last) = Self::replace_first_page::<FastOutboundXcmpMessages<T>>(
+				recipient,
+				first_index,
+				last_index,
+				split,
+			);
+			first_index = first;
+			last_index = last;
+		}
+		let mut page = None;
+		if first_index < last_index {
+			let first = <FastOutboundXcmpMessages<T>>::get(recipient, first_index);
+			if first.len() < max_size_now {
+				<FastOutboundXcmpMessages<T>>::remove(recipient, first_index);
+				first_index += 1;
+				page = Some(first);
+			}
+		}
+		if first_index == last_index {
+			<FastOutboundXcmpIndices<T>>::remove(recipient);
+		} else {
+			<FastOutboundXcmpIndices<T>>::insert(recipient, (first_index, last_index));
+		}
+		page
+	}
+
 	/// Sends a signal to the `dest` chain over XCMP. This is guaranteed to be dispatched on this
 	/// block.
 	fn send_signal(dest: ParaId, signal: ChannelSignal) -> Result<(), ()> {
@@ -420,14 +1008,116 @@ impl<T: Config> Pallet<T> {
 	}
 
 	pub fn send_blob_message(recipient: ParaId, blob: Vec<u8>) -> Result<u32, MessageSendError> {
-		Self::send_fragment(recipient, XcmpMessageFormat::ConcatenatedEncodedBlob, blob)
+		Self::send_blob_message_with_qos(recipient, blob, ServiceQuality::Ordered)
+	}
+
+	/// Send a blob message to `recipient`, in the fast lane if `qos` is `ServiceQuality::Fast`.
+	pub fn send_blob_message_with_qos(
+		recipient: ParaId,
+		blob: Vec<u8>,
+		qos: ServiceQuality,
+	) -> Result<u32, MessageSendError> {
+		Self::send_fragment(recipient, XcmpMessageFormat::ConcatenatedEncodedBlob, blob, qos)
 	}
 
 	pub fn send_xcm_message(
 		recipient: ParaId,
 		xcm: VersionedXcm<()>,
 	) -> Result<u32, MessageSendError> {
-		Self::send_fragment(recipient, XcmpMessageFormat::ConcatenatedVersionedXcm, xcm)
+		Self::send_xcm_message_with_qos(recipient, xcm, ServiceQuality::Ordered)
+	}
+
+	/// Send an XCM to `recipient`, in the fast lane if `qos` is `ServiceQuality::Fast`.
+	///
+	/// Unlike sending through `SendXcm`, this doesn't wrap `xcm` into the version supported by
+	/// `recipient`.
+	pub fn send_xcm_message_with_qos(
+		recipient: ParaId,
+		xcm: VersionedXcm<()>,
+		qos: ServiceQuality,
+	) -> Result<u32, MessageSendError> {
+		Self::send_fragment(recipient, XcmpMessageFormat::ConcatenatedVersionedXcm, xcm, qos)
+	}
+
+	/// Information about every inbound XCMP channel which has messages waiting to be executed.
+	pub fn inbound_channels() -> Vec<InboundChannelInfo> {
+		<InboundXcmpStatus<T>>::get()
+			.into_iter()
+			.map(|channel| {
+				let queued_bytes = channel
+					.message_metadata
+					.iter()
+					.map(|(sent_at, _)| {
+						<InboundXcmpMessages<T>>::decode_len(channel.sender, sent_at).unwrap_or(0)
+					})
+					.sum::<usize>();
+				InboundChannelInfo {
+					sender: channel.sender,
+					state: channel.state,
+					queued_pages: channel.message_metadata.len() as u32,
+					queued_bytes: queued_bytes as u32,
+				}
+			})
+			.collect()
+	}
+
+	/// Information about every outbound XCMP channel which is suspended or has messages or
+	/// signals waiting to be sent.
+	pub fn outbound_channels() -> Vec<OutboundChannelInfo> {
+		<OutboundXcmpStatus<T>>::get()
+			.into_iter()
+			.map(|channel| {
+				let queued_bytes = (channel.first_index..channel.last_index)
+					.map(|i| {
+						<OutboundXcmpMessages<T>>::decode_len(channel.recipient, i).unwrap_or(0)
+					})
+					.sum::<usize>();
+				let pending_signals = if channel.signals_exist {
+					let page = <SignalMessages<T>>::get(channel.recipient);
+					let mut data = &page[..];
+					let _ = XcmpMessageFormat::decode(&mut data);
+					let mut count = 0;
+					while !data.is_empty() && ChannelSignal::decode(&mut data).is_ok() {
+						count += 1;
+					}
+					count
+				} else {
+					0
+				};
+				OutboundChannelInfo {
+					recipient: channel.recipient,
+					state: channel.state,
+					queued_pages: (channel.last_index - channel.first_index) as u32,
+					queued_bytes: queued_bytes as u32,
+					queued_fast_pages: <FastOutboundXcmpIndices<T>>::get(channel.recipient)
+						.map_or(0, |(first, last)| (last - first) as u32),
+					pending_signals,
+				}
+			})
+			.collect()
+	}
+
+	/// All entries of the overweight queue, ordered by their index.
+	pub fn overweight_messages() -> Vec<OverweightMessageInfo> {
+		let mut messages = <Overweight<T>>::iter()
+			.map(|(index, (sender, sent_at, data))| OverweightMessageInfo {
+				index,
+				sender,
+				sent_at,
+				size: data.len() as u32,
+			})
+			.collect::<Vec<_>>();
+		messages.sort_by_key(|m| m.index);
+		messages
+	}
+
+	/// Apply `f` to the stored `QueueConfig`, only writing it back if the result is still valid.
+	fn update_queue_config(f: impl FnOnce(&mut QueueConfigData)) -> DispatchResult {
+		<QueueConfig<T>>::try_mutate(|data| -> DispatchResult {
+			f(data);
+			ensure!(data.is_valid(), Error::<T>::BadQueueConfig);
+			Ok(())
+		})
 	}
 
 	fn create_shuffle(len: usize) -> Vec<usize> {
@@ -448,14 +1138,32 @@ impl<T: Config> Pallet<T> {
 		shuffled
 	}
 
+	/// Hands a blob message over to `T::BlobHandler`.
+	///
+	/// Returns `Err(true)` if the blob should be retried later because it needs more than
+	/// `weight_limit`, or `Err(false)` if it should be dropped.
 	fn handle_blob_message(
-		_sender: ParaId,
-		_sent_at: RelayBlockNumber,
-		_blob: Vec<u8>,
-		_weight_limit: Weight,
+		sender: ParaId,
+		sent_at: RelayBlockNumber,
+		blob: Vec<u8>,
+		weight_limit: Weight,
+		max_individual_weight: Weight,
 	) -> Result<Weight, bool> {
-		debug_assert!(false, "Blob messages not handled.");
-		Err(false)
+		let hash = T::Hashing::hash(&blob);
+		log::debug!("Processing XCMP-Blob: {:?}", &hash);
+		match T::BlobHandler::handle_blob(sender, sent_at, blob, weight_limit) {
+			Ok(used) => {
+				Self::deposit_event(Event::BlobHandled(sender, hash, used));
+				Ok(used)
+			},
+			Err(BlobHandlerError::WeightLimitReached(required))
+				if required <= max_individual_weight =>
+				Err(true),
+			Err(_) => {
+				Self::deposit_event(Event::BlobUnhandled(sender, hash));
+				Err(false)
+			},
+		}
 	}
 
 	fn handle_xcm_message(
@@ -517,9 +1225,7 @@ impl<T: Config> Pallet<T> {
 								let e = Event::OverweightEnqueued(sender, sent_at, index, required);
 								Self::deposit_event(e);
 							},
-							Err(XcmError::WeightLimitReached(required))
-								if required <= max_weight =>
-							{
+							Err(XcmError::WeightLimitReached(_)) => {
 								// That message didn't get processed this time because of being
 								// too heavy. We leave it around for next time and bail.
 								remaining_fragments = last_remaining_fragments;
@@ -538,10 +1244,16 @@ impl<T: Config> Pallet<T> {
 			XcmpMessageFormat::ConcatenatedEncodedBlob => {
 				while !remaining_fragments.is_empty() {
 					last_remaining_fragments = remaining_fragments;
-					match <Vec<u8>>::decode_all(&mut remaining_fragments) {
+					match <Vec<u8>>::decode(&mut remaining_fragments) {
 						Ok(blob) if remaining_fragments.len() < last_remaining_fragments.len() => {
 							let weight = max_weight - weight_used;
-							match Self::handle_blob_message(sender, sent_at, blob, weight) {
+							match Self::handle_blob_message(
+								sender,
+								sent_at,
+								blob,
+								weight,
+								max_individual_weight,
+							) {
 								Ok(used) => weight_used = weight_used.saturating_add(used),
 								Err(true) => {
 									// That message didn't get processed this time because of being
@@ -550,7 +1262,7 @@ impl<T: Config> Pallet<T> {
 									break
 								},
 								Err(false) => {
-									// Message invalid; don't attempt to retry
+									// Blob could not be handled; don't attempt to retry
 								},
 							}
 						},
@@ -591,9 +1303,70 @@ impl<T: Config> Pallet<T> {
 		index
 	}
 
+	/// The maximum weight of trapping the assets of `n` discarded overweight XCMs, which is zero
+	/// unless `trap_assets` is set.
+	fn trap_weight(n: u32, trap_assets: bool) -> Weight {
+		if trap_assets {
+			T::TrapAssetsWeight::get().saturating_mul(n as Weight)
+		} else {
+			0
+		}
+	}
+
+	/// Hands the assets which the overweight XCM `data` from `sender` would have placed into the
+	/// holding register over to `T::AssetTrap`.
+	///
+	/// Only assets which arrive with the message (i.e. through `ReserveAssetDeposited` or
+	/// `ReceiveTeleportedAsset`) are trapped; anything else was never moved and so is left alone.
+	/// The assets have to pass the same checks as when the XCM is executed: deposited assets must
+	/// pass `T::IsReserve`, teleported assets must pass `T::IsTeleporter` and are checked in with
+	/// `T::AssetTransactor`. If any of the assets fails these checks, nothing is trapped.
+	///
+	/// Returns the weight used, which is `T::TrapAssetsWeight` if the assets were trapped.
+	fn trap_overweight_assets(sender: ParaId, data: &[u8]) -> Weight {
+		let xcm = VersionedXcm::<()>::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, data)
+			.ok()
+			.and_then(|xcm| Xcm::<()>::try_from(xcm).ok());
+		let location: MultiLocation = (1, Parachain(sender.into())).into();
+		let assets = match xcm.and_then(|xcm| xcm.0.into_iter().next()) {
+			Some(ReserveAssetDeposited(assets)) => {
+				if !assets
+					.inner()
+					.iter()
+					.all(|asset| T::IsReserve::filter_asset_location(asset, &location))
+				{
+					return 0
+				}
+				assets
+			},
+			Some(ReceiveTeleportedAsset(assets)) => {
+				if !assets.inner().iter().all(|asset| {
+					T::IsTeleporter::filter_asset_location(asset, &location) &&
+						T::AssetTransactor::can_check_in(&location, asset).is_ok()
+				}) {
+					return 0
+				}
+				for asset in assets.inner() {
+					T::AssetTransactor::check_in(&location, asset);
+				}
+				assets
+			},
+			_ => return 0,
+		};
+		T::AssetTrap::drop_assets(&location, assets.into());
+		// The weight reported by the trap doesn't cover the check in, so we always account for
+		// the maximum.
+		T::TrapAssetsWeight::get()
+	}
+
 	/// Service the incoming XCMP message queue attempting to execute up to `max_weight` execution
 	/// weight of messages.
 	///
+	/// First, every channel gets to use its share of `max_weight`, in proportion to the shares
+	/// in `InboundShares` of all the channels with queued messages (see `service_shares`). This
+	/// ensures that a sender which floods us cannot starve the others. Whatever weight is left
+	/// is then apportioned as described below.
+	///
 	/// Channels are first shuffled and then processed in this random one page at a time, order over
 	/// and over until either `max_weight` is exhausted or no channel has messages that can be
 	/// processed any more.
@@ -618,11 +1391,15 @@ impl<T: Config> Pallet<T> {
 	/// half of the `max_weight` available for the first page, then a quarter plus the remainder
 	/// for the second &c. though empirical and or practical factors may give rise to adjusting it
 	/// further.
+	///
+	/// The returned weight includes the bookkeeping of the queue, as given by `T::WeightInfo`.
 	fn service_xcmp_queue(max_weight: Weight) -> Weight {
 		let mut status = <InboundXcmpStatus<T>>::get(); // <- sorted.
+		let overhead = T::WeightInfo::service_xcmp_queue(status.len() as u32);
 		if status.len() == 0 {
-			return 0
+			return overhead
 		}
+		let max_weight = max_weight.saturating_sub(overhead);
 
 		let QueueConfigData {
 			resume_threshold,
@@ -632,9 +1409,18 @@ impl<T: Config> Pallet<T> {
 			..
 		} = <QueueConfig<T>>::get();
 
+		let page_weight = T::WeightInfo::process_xcmp_page();
 		let mut shuffled = Self::create_shuffle(status.len());
-		let mut weight_used = 0;
-		let mut weight_available = 0;
+		let mut weight_used = Self::service_shares(
+			&mut status,
+			&shuffled,
+			max_weight,
+			resume_threshold,
+			xcmp_max_individual_weight,
+		);
+		let mut weight_available = weight_used;
+		shuffled.retain(|&index| !status[index].message_metadata.is_empty());
+		let channels = shuffled.len();
 
 		// We don't want the possibility of a chain sending a series of really heavy messages and
 		// tying up the block's execution time from other chains. Therefore we execute any remaining
@@ -646,7 +1432,7 @@ impl<T: Config> Pallet<T> {
 
 		let mut shuffle_index = 0;
 		while shuffle_index < shuffled.len() &&
-			max_weight.saturating_sub(weight_used) >= threshold_weight
+			max_weight.saturating_sub(weight_used) >= threshold_weight.max(page_weight)
 		{
 			let index = shuffled[shuffle_index];
 			let sender = status[index].sender;
@@ -655,10 +1441,10 @@ impl<T: Config> Pallet<T> {
 				// Get incrementally closer to freeing up max_weight for message execution over the
 				// first round. For the second round we unlock all weight. If we come close enough
 				// on the first round to unlocking everything, then we do so.
-				if shuffle_index < status.len() {
+				if shuffle_index < channels {
 					weight_available +=
-						(max_weight - weight_available) / (weight_restrict_decay + 1);
-					if weight_available + threshold_weight > max_weight {
+						max_weight.saturating_sub(weight_available) / (weight_restrict_decay + 1);
+					if weight_available.saturating_add(threshold_weight) > max_weight {
 						weight_available = max_weight;
 					}
 				} else {
@@ -666,33 +1452,30 @@ impl<T: Config> Pallet<T> {
 				}
 			}
 
+			// The weight of processing the page itself has to fit in the available weight as well.
+			let weight_remaining = weight_available.saturating_sub(weight_used);
 			let weight_processed = if status[index].message_metadata.is_empty() {
 				debug_assert!(false, "channel exists in status; there must be messages; qed");
 				0
+			} else if weight_remaining < page_weight {
+				0
 			} else {
 				// Process up to one block's worth for now.
-				let weight_remaining = weight_available.saturating_sub(weight_used);
 				let (weight_processed, is_empty) = Self::process_xcmp_message(
 					sender,
 					status[index].message_metadata[0],
-					weight_remaining,
+					weight_remaining - page_weight,
 					xcmp_max_individual_weight,
 				);
 				if is_empty {
 					status[index].message_metadata.remove(0);
 				}
+				weight_used = weight_used.saturating_add(page_weight);
 				weight_processed
 			};
-			weight_used += weight_processed;
+			weight_used = weight_used.saturating_add(weight_processed);
 
-			if status[index].message_metadata.len() as u32 <= resume_threshold &&
-				status[index].state == InboundState::Suspended
-			{
-				// Resume
-				let r = Self::send_signal(sender, ChannelSignal::Resume);
-				debug_assert!(r.is_ok(), "WARNING: Failed sending resume into suspended channel");
-				status[index].state = InboundState::Ok;
-			}
+			Self::resume_if_drained(&mut status[index], resume_threshold);
 
 			// If there are more and we're making progress, we process them after we've given the
 			// other channels a look in. If we've still not unlocked all weight, then we set them
@@ -713,9 +1496,70 @@ impl<T: Config> Pallet<T> {
 		status.retain(|item| !item.message_metadata.is_empty());
 
 		<InboundXcmpStatus<T>>::put(status);
+		weight_used.saturating_add(overhead)
+	}
+
+	/// Let each channel of `status`, in the given `order`, execute messages using up to its share
+	/// of `max_weight`. The weight of processing a page counts towards the share, so the returned
+	/// weight used never exceeds `max_weight`.
+	fn service_shares(
+		status: &mut [InboundChannelDetails],
+		order: &[usize],
+		max_weight: Weight,
+		resume_threshold: u32,
+		max_individual_weight: Weight,
+	) -> Weight {
+		let shares = status
+			.iter()
+			.map(|channel| {
+				<InboundShares<T>>::get(channel.sender).unwrap_or(DEFAULT_INBOUND_SHARE) as u128
+			})
+			.collect::<Vec<_>>();
+		let total_shares = shares.iter().sum::<u128>();
+		if total_shares == 0 {
+			return 0
+		}
+
+		let page_weight = T::WeightInfo::process_xcmp_page();
+		let mut weight_used: Weight = 0;
+		for &index in order {
+			let mut quota = (max_weight as u128 * shares[index] / total_shares) as Weight;
+			let sender = status[index].sender;
+			while quota > page_weight {
+				let page = match status[index].message_metadata.first() {
+					Some(page) => *page,
+					None => break,
+				};
+				let (weight_processed, is_empty) = Self::process_xcmp_message(
+					sender,
+					page,
+					quota - page_weight,
+					max_individual_weight,
+				);
+				let weight_processed = weight_processed.saturating_add(page_weight);
+				quota = quota.saturating_sub(weight_processed);
+				weight_used = weight_used.saturating_add(weight_processed);
+				if !is_empty {
+					break
+				}
+				status[index].message_metadata.remove(0);
+			}
+			Self::resume_if_drained(&mut status[index], resume_threshold);
+		}
 		weight_used
 	}
 
+	/// Resume a suspended inbound channel once its queue has shrunk to `resume_threshold`.
+	fn resume_if_drained(channel: &mut InboundChannelDetails, resume_threshold: u32) {
+		if channel.message_metadata.len() as u32 <= resume_threshold &&
+			channel.state == InboundState::Suspended
+		{
+			let r = Self::send_signal(channel.sender, ChannelSignal::Resume);
+			debug_assert!(r.is_ok(), "WARNING: Failed sending resume into suspended channel");
+			channel.state = InboundState::Ok;
+		}
+	}
+
 	fn suspend_channel(target: ParaId) {
 		<OutboundXcmpStatus<T>>::mutate(|s| {
 			if let Some(index) = s.iter().position(|item| item.recipient == target) {
@@ -736,7 +1580,11 @@ impl<T: Config> Pallet<T> {
 					suspended,
 					"WARNING: Attempt to resume channel that was not suspended."
 				);
-				if s[index].first_index == s[index].last_index {
+				// Keep the channel around if it still has pages in the fast lane or signals.
+				if s[index].first_index == s[index].last_index &&
+					!s[index].signals_exist &&
+					!<FastOutboundXcmpIndices<T>>::contains_key(target)
+				{
 					s.remove(index);
 				} else {
 					s[index].state = OutboundState::Ok;
@@ -757,7 +1605,10 @@ impl<T: Config> XcmpMessageHandler for Pallet<T> {
 
 		let QueueConfigData { suspend_threshold, drop_threshold, .. } = <QueueConfig<T>>::get();
 
+		let mut weight_used: Weight = 0;
 		for (sender, sent_at, data) in iter {
+			weight_used = weight_used.saturating_add(T::WeightInfo::enqueue_xcmp_page());
+
 			// Figure out the message format.
 			let mut data_ref = data;
 			let format = match XcmpMessageFormat::decode_and_advance_with_depth_limit(
@@ -819,7 +1670,7 @@ impl<T: Config> XcmpMessageHandler for Pallet<T> {
 		status.sort();
 		<InboundXcmpStatus<T>>::put(status);
 
-		Self::service_xcmp_queue(max_weight)
+		weight_used.saturating_add(Self::service_xcmp_queue(max_weight.saturating_sub(weight_used)))
 	}
 }
 
@@ -830,6 +1681,11 @@ impl<T: Config> XcmpMessageSource for Pallet<T> {
 		let max_message_count = statuses.len().min(maximum_channels);
 		let mut result = Vec::with_capacity(max_message_count);
 
+		// If not every channel can be serviced, give the ones with fast messages precedence.
+		if max_message_count < statuses.len() {
+			statuses.sort_by_key(|s| !<FastOutboundXcmpIndices<T>>::contains_key(s.recipient));
+		}
+
 		for status in statuses.iter_mut() {
 			let OutboundChannelDetails {
 				recipient: para_id,
@@ -850,9 +1706,16 @@ impl<T: Config> XcmpMessageSource for Pallet<T> {
 			let (max_size_now, max_size_ever) = match T::ChannelInfo::get_channel_status(para_id) {
 				ChannelStatus::Closed => {
 					// This means that there is no such channel anymore. Nothing to be done but
-					// swallow the messages and discard the status.
+					// swallow the messages, letting the senders know, and discard the status.
+					if let Some((first, last)) = <FastOutboundXcmpIndices<T>>::take(para_id) {
+						for i in first..last {
+							let page = <FastOutboundXcmpMessages<T>>::take(para_id, i);
+							Self::report_dropped_page(para_id, &page);
+						}
+					}
 					for i in first_index..last_index {
-						<OutboundXcmpMessages<T>>::remove(para_id, i);
+						let page = <OutboundXcmpMessages<T>>::take(para_id, i);
+						Self::report_dropped_page(para_id, &page);
 					}
 					if signals_exist {
 						<SignalMessages<T>>::remove(para_id);
@@ -873,8 +1736,31 @@ impl<T: Config> XcmpMessageSource for Pallet<T> {
 				} else {
 					continue
 				}
+			} else if <FastOutboundXcmpIndices<T>>::contains_key(para_id) {
+				match Self::take_fast_page(para_id, max_size_now, max_size_ever) {
+					Some(page) => page,
+					None => continue,
+				}
 			} else if last_index > first_index {
-				let page = <OutboundXcmpMessages<T>>::get(para_id, first_index);
+				let mut page = <OutboundXcmpMessages<T>>::get(para_id, first_index);
+				if page.len() > max_size_ever {
+					// The channel's maximum message size has shrunk since the page was filled.
+					let split = Self::split_page(para_id, &page, max_size_ever);
+					let (first, last) = Self::replace_first_page::<OutboundXcmpMessages<T>>(
+						para_id,
+						first_index,
+						last_index,
+						split,
+					);
+					first_index = first;
+					last_index = last;
+					status.first_index = first_index;
+					status.last_index = last_index;
+					if first_index == last_index {
+						continue
+					}
+					page = <OutboundXcmpMessages<T>>::get(para_id, first_index);
+				}
 				if page.len() < max_size_now {
 					<OutboundXcmpMessages<T>>::remove(para_id, first_index);
 					first_index += 1;
@@ -891,10 +1777,9 @@ impl<T: Config> XcmpMessageSource for Pallet<T> {
 			}
 
 			if page.len() > max_size_ever {
-				// TODO: #274 This means that the channel's max message size has changed since
-				//   the message was sent. We should parse it and split into smaller mesasges but
-				//   since it's so unlikely then for now we just drop it.
-				log::warn!("WARNING: oversize message in queue. silently dropping.");
+				// Message pages are split above, so this can only be a page of signals, which
+				// should never get this big.
+				log::warn!("WARNING: oversize signal page in queue. silently dropping.");
 			} else {
 				result.push((para_id, page));
 			}
@@ -921,14 +1806,18 @@ impl<T: Config> XcmpMessageSource for Pallet<T> {
 		// To mitigate this we shift all processed elements towards the end of the vector using
 		// `rotate_left`. To get intuition how it works see the examples in its rustdoc.
 		statuses.retain(|x| {
-			x.state == OutboundState::Suspended || x.signals_exist || x.first_index < x.last_index
+			x.state == OutboundState::Suspended ||
+				x.signals_exist ||
+				x.first_index < x.last_index ||
+				<FastOutboundXcmpIndices<T>>::contains_key(x.recipient)
 		});
 
 		// old_status_len must be >= status.len() since we never add anything to status.
 		let pruned = old_statuses_len - statuses.len();
 		// removing an item from status implies a message being sent, so the result messages must
 		// be no less than the pruned channels.
-		statuses.rotate_left(result.len() - pruned);
+		// Closed channels are pruned without a message being sent though.
+		statuses.rotate_left(result.len().saturating_sub(pruned));
 
 		<OutboundXcmpStatus<T>>::put(statuses);
 
@@ -951,6 +1840,7 @@ impl<T: Config> SendXcm for Pallet<T> {
 					(*id).into(),
 					XcmpMessageFormat::ConcatenatedVersionedXcm,
 					versioned_xcm,
+					ServiceQuality::Ordered,
 				)
 				.map_err(|e| SendError::Transport(<&'static str>::from(e)))?;
 				Self::deposit_event(Event::XcmpMessageSent(Some(hash)));
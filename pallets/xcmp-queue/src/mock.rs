@@ -22,8 +22,10 @@ use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
 };
+use std::cell::{Cell, RefCell};
 use xcm_builder::{
 	CurrencyAdapter, FixedWeightBounds, IsConcrete, LocationInverter, NativeAsset, ParentIsDefault,
+	TakeWeightCredit,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -105,6 +107,12 @@ impl cumulus_pallet_parachain_system::Config for Test {
 	type ReservedDmpWeight = ();
 	type XcmpMessageHandler = XcmpQueue;
 	type ReservedXcmpWeight = ();
+	type MaxPendingUpwardMessages = frame_support::traits::ConstU32<1_000>;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = frame_support::traits::ConstU64<100>;
+	type RelayParentHistoryLength = frame_support::traits::ConstU32<16>;
+	type WeightInfo = ();
 }
 
 parameter_types! {
@@ -140,7 +148,7 @@ impl xcm_executor::Config for XcmConfig {
 	type IsReserve = NativeAsset;
 	type IsTeleporter = NativeAsset;
 	type LocationInverter = LocationInverter<Ancestry>;
-	type Barrier = ();
+	type Barrier = TakeWeightCredit;
 	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
 	type Trader = ();
 	type ResponseHandler = ();
@@ -149,17 +157,154 @@ impl xcm_executor::Config for XcmConfig {
 	type SubscriptionService = ();
 }
 
+thread_local! {
+	pub static TRAPPED_ASSETS: RefCell<Vec<(MultiLocation, Vec<MultiAsset>)>> =
+		RefCell::new(Vec::new());
+}
+
+pub fn take_trapped_assets() -> Vec<(MultiLocation, Vec<MultiAsset>)> {
+	TRAPPED_ASSETS.with(|t| t.take())
+}
+
+/// Asset trap which only records what it was given.
+pub struct MockAssetTrap;
+impl DropAssets for MockAssetTrap {
+	fn drop_assets(origin: &MultiLocation, assets: xcm_executor::Assets) -> Weight {
+		TRAPPED_ASSETS.with(|t| t.borrow_mut().push((origin.clone(), assets.into())));
+		1_000
+	}
+}
+
+thread_local! {
+	pub static HANDLED_BLOBS: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+}
+
+pub fn take_handled_blobs() -> Vec<(ParaId, Vec<u8>)> {
+	HANDLED_BLOBS.with(|b| b.take())
+}
+
+/// Blob handler which records the blobs it was given, charging 1_000 weight per byte.
+///
+/// Empty blobs are rejected.
+pub struct MockBlobHandler;
+impl BlobHandler for MockBlobHandler {
+	fn handle_blob(
+		sender: ParaId,
+		_sent_at: RelayBlockNumber,
+		blob: Vec<u8>,
+		weight_limit: Weight,
+	) -> Result<Weight, BlobHandlerError> {
+		if blob.is_empty() {
+			return Err(BlobHandlerError::Unhandled)
+		}
+		let required = blob.len() as Weight * 1_000;
+		if required > weight_limit {
+			return Err(BlobHandlerError::WeightLimitReached(required))
+		}
+		HANDLED_BLOBS.with(|b| b.borrow_mut().push((sender, blob)));
+		Ok(required)
+	}
+}
+
+thread_local! {
+	pub static OPEN_CHANNELS: RefCell<Vec<(ParaId, usize)>> = RefCell::new(Vec::new());
+}
+
+/// The maximum message size of the channels opened with `open_channel`.
+pub const MAX_MESSAGE_SIZE: usize = 100;
+
+/// Open an outbound channel to `recipient`.
+pub fn open_channel(recipient: ParaId) {
+	OPEN_CHANNELS.with(|c| c.borrow_mut().push((recipient, MAX_MESSAGE_SIZE)));
+}
+
+/// Change the maximum message size of the open channel to `recipient`.
+pub fn set_max_message_size(recipient: ParaId, max_message_size: usize) {
+	OPEN_CHANNELS.with(|c| {
+		for channel in c.borrow_mut().iter_mut().filter(|(id, _)| *id == recipient) {
+			channel.1 = max_message_size;
+		}
+	});
+}
+
+/// Channel info which only knows about the channels opened with `open_channel`, which always
+/// have room for another message.
+pub struct MockChannelInfo;
+impl GetChannelInfo for MockChannelInfo {
+	fn get_channel_status(id: ParaId) -> ChannelStatus {
+		match Self::get_channel_max(id) {
+			Some(max) => ChannelStatus::Ready(MAX_MESSAGE_SIZE, max),
+			None => ChannelStatus::Closed,
+		}
+	}
+
+	fn get_channel_max(id: ParaId) -> Option<usize> {
+		OPEN_CHANNELS.with(|c| c.borrow().iter().find(|(c, _)| *c == id).map(|(_, max)| *max))
+	}
+}
+
 pub type XcmRouter = (
 	// XCMP to communicate with the sibling chains.
 	XcmpQueue,
 );
 
+thread_local! {
+	pub static PAGE_WEIGHT: Cell<Weight> = Cell::new(0);
+}
+
+/// Charge `weight` for processing every page from now on.
+pub fn set_page_weight(weight: Weight) {
+	PAGE_WEIGHT.with(|w| w.set(weight));
+}
+
+/// Weights which only charge for processing pages, as set with `set_page_weight`, so that the
+/// tests can reason about the weight used by the handled messages.
+pub struct MockWeightInfo;
+impl WeightInfo for MockWeightInfo {
+	fn service_overweight() -> Weight {
+		0
+	}
+	fn discard_overweight() -> Weight {
+		0
+	}
+	fn discard_overweights(_n: u32) -> Weight {
+		0
+	}
+	fn set_config_with_u32() -> Weight {
+		0
+	}
+	fn set_config_with_weight() -> Weight {
+		0
+	}
+	fn update_inbound_share() -> Weight {
+		0
+	}
+	fn enqueue_xcmp_page() -> Weight {
+		0
+	}
+	fn process_xcmp_page() -> Weight {
+		PAGE_WEIGHT.with(|w| w.get())
+	}
+	fn service_xcmp_queue(_c: u32) -> Weight {
+		0
+	}
+}
+
 impl Config for Test {
 	type Event = Event;
 	type XcmExecutor = xcm_executor::XcmExecutor<XcmConfig>;
-	type ChannelInfo = ParachainSystem;
+	type ChannelInfo = MockChannelInfo;
 	type VersionWrapper = ();
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = MockAssetTrap;
+	type IsReserve = NativeAsset;
+	type IsTeleporter = NativeAsset;
+	type AssetTransactor = LocalAssetTransactor;
+	type TrapAssetsWeight = frame_support::traits::ConstU64<1_000>;
+	type MaxDiscard = frame_support::traits::ConstU32<3>;
+	type BlobHandler = MockBlobHandler;
+	type WeightInfo = MockWeightInfo;
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
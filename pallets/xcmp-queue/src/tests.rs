@@ -15,8 +15,12 @@
 
 use super::*;
 use cumulus_primitives_core::XcmpMessageHandler;
-use frame_support::assert_noop;
-use mock::{new_test_ext, Origin, Test, XcmpQueue};
+use frame_support::{assert_noop, assert_ok};
+use mock::{
+	new_test_ext, open_channel, set_max_message_size, set_page_weight, take_handled_blobs,
+	take_trapped_assets, Origin, System, Test, XcmpQueue, MAX_MESSAGE_SIZE,
+};
+use sp_runtime::traits::{BadOrigin, BlakeTwo256};
 
 #[test]
 fn one_message_does_not_panic() {
@@ -47,19 +51,19 @@ fn bad_message_is_handled() {
 }
 
 #[test]
-#[should_panic = "Invalid incoming blob message data"]
-#[cfg(debug_assertions)]
 fn other_bad_message_is_handled() {
 	new_test_ext().execute_with(|| {
+		// Although garbage, this happens to be a single well-formed 64 byte blob.
 		let bad_data = vec![
 			1, 1, 1, 1, 3, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 64, 239,
 			139, 0, 0, 0, 64, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 0, 0, 0, 0, 0, 0, 0,
 			37, 0, 0, 0, 0, 0, 0, 0, 16, 0, 127, 147,
 		];
-		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, bad_data);
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, bad_data.clone());
 		let format = XcmpMessageFormat::ConcatenatedEncodedBlob;
-		// This should exit with an error.
 		XcmpQueue::process_xcmp_message(1000.into(), (1, format), 10_000_000_000, 10_000_000_000);
+		assert_eq!(take_handled_blobs(), vec![(1000.into(), bad_data[2..].to_vec())]);
+		assert!(!InboundXcmpMessages::<Test>::contains_key(ParaId::from(1000), 1));
 	});
 }
 
@@ -82,3 +86,710 @@ fn service_overweight_bad_xcm_format() {
 		assert_noop!(XcmpQueue::service_overweight(Origin::root(), 0, 1000), Error::<Test>::BadXcm);
 	});
 }
+
+#[test]
+fn service_overweight_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let xcm = VersionedXcm::<()>::from(Xcm::<()>(vec![])).encode();
+		Overweight::<Test>::insert(0, (ParaId::from(1000), 0, xcm));
+
+		assert_noop!(XcmpQueue::service_overweight(Origin::signed(1), 0, 1000), BadOrigin);
+		assert_ok!(XcmpQueue::service_overweight(Origin::root(), 0, 1000));
+		assert!(!Overweight::<Test>::contains_key(0));
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::OverweightServiced(0, 0)));
+	});
+}
+
+#[test]
+fn update_suspend_threshold_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.suspend_threshold, 2);
+		assert_ok!(XcmpQueue::update_suspend_threshold(Origin::root(), 3));
+		assert_noop!(XcmpQueue::update_suspend_threshold(Origin::signed(2), 5), BadOrigin);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.suspend_threshold, 3);
+	});
+}
+
+#[test]
+fn update_drop_threshold_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.drop_threshold, 5);
+		assert_ok!(XcmpQueue::update_drop_threshold(Origin::root(), 6));
+		assert_noop!(XcmpQueue::update_drop_threshold(Origin::signed(2), 7), BadOrigin);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.drop_threshold, 6);
+	});
+}
+
+#[test]
+fn update_resume_threshold_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.resume_threshold, 1);
+		assert_ok!(XcmpQueue::update_resume_threshold(Origin::root(), 0));
+		assert_noop!(XcmpQueue::update_resume_threshold(Origin::signed(7), 1), BadOrigin);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.resume_threshold, 0);
+	});
+}
+
+#[test]
+fn update_threshold_weight_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.threshold_weight, 100_000);
+		assert_ok!(XcmpQueue::update_threshold_weight(Origin::root(), 10_000));
+		assert_noop!(XcmpQueue::update_threshold_weight(Origin::signed(5), 10_000_000), BadOrigin);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.threshold_weight, 10_000);
+	});
+}
+
+#[test]
+fn update_weight_restrict_decay_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.weight_restrict_decay, 2);
+		assert_ok!(XcmpQueue::update_weight_restrict_decay(Origin::root(), 5));
+		assert_noop!(XcmpQueue::update_weight_restrict_decay(Origin::signed(6), 4), BadOrigin);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.weight_restrict_decay, 5);
+	});
+}
+
+#[test]
+fn update_xcmp_max_individual_weight_works() {
+	new_test_ext().execute_with(|| {
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+		assert_eq!(data.xcmp_max_individual_weight, 20 * WEIGHT_PER_MILLIS);
+		assert_ok!(XcmpQueue::update_xcmp_max_individual_weight(
+			Origin::root(),
+			30 * WEIGHT_PER_MILLIS
+		));
+		assert_noop!(
+			XcmpQueue::update_xcmp_max_individual_weight(Origin::signed(3), 10 * WEIGHT_PER_MILLIS),
+			BadOrigin
+		);
+		let data: QueueConfigData = <QueueConfig<Test>>::get();
+
+		assert_eq!(data.xcmp_max_individual_weight, 30 * WEIGHT_PER_MILLIS);
+	});
+}
+
+#[test]
+fn update_queue_config_rejects_broken_thresholds() {
+	new_test_ext().execute_with(|| {
+		// Defaults are resume = 1, suspend = 2, drop = 5.
+		assert_noop!(
+			XcmpQueue::update_suspend_threshold(Origin::root(), 1),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_noop!(
+			XcmpQueue::update_suspend_threshold(Origin::root(), 5),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_noop!(
+			XcmpQueue::update_drop_threshold(Origin::root(), 2),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_noop!(
+			XcmpQueue::update_resume_threshold(Origin::root(), 2),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_noop!(
+			XcmpQueue::update_threshold_weight(Origin::root(), 0),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_noop!(
+			XcmpQueue::update_xcmp_max_individual_weight(Origin::root(), 0),
+			Error::<Test>::BadQueueConfig
+		);
+		assert_eq!(<QueueConfig<Test>>::get(), QueueConfigData::default());
+	});
+}
+
+#[test]
+fn update_queue_config_deposits_event() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(XcmpQueue::update_drop_threshold(Origin::root(), 8));
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::DropThresholdUpdated(8)));
+	});
+}
+
+#[test]
+fn discard_overweight_unknown() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmpQueue::discard_overweight(Origin::root(), 0, false),
+			Error::<Test>::BadOverweightIndex,
+		);
+	});
+}
+
+#[test]
+fn discard_overweight_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let xcm = VersionedXcm::<()>::from(Xcm::<()>(vec![ClearOrigin])).encode();
+		Overweight::<Test>::insert(0, (ParaId::from(1000), 0, xcm));
+
+		assert_noop!(XcmpQueue::discard_overweight(Origin::signed(1), 0, false), BadOrigin);
+		assert_ok!(XcmpQueue::discard_overweight(Origin::root(), 0, true));
+		assert!(!Overweight::<Test>::contains_key(0));
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::OverweightDiscarded(0)));
+		// Nothing was carried by the message, so nothing is trapped.
+		assert!(take_trapped_assets().is_empty());
+	});
+}
+
+#[test]
+fn discard_overweight_traps_carried_assets() {
+	new_test_ext().execute_with(|| {
+		let assets: MultiAssets = vec![((Parent, Parachain(1000)), 1_000u128).into()].into();
+		let xcm = |assets| {
+			VersionedXcm::<()>::from(Xcm::<()>(vec![ReserveAssetDeposited(assets), ClearOrigin]))
+				.encode()
+		};
+		Overweight::<Test>::insert(0, (ParaId::from(1000), 0, xcm(assets.clone())));
+		Overweight::<Test>::insert(1, (ParaId::from(1000), 0, xcm(assets.clone())));
+
+		// Not trapping leaves the assets alone.
+		assert_ok!(XcmpQueue::discard_overweight(Origin::root(), 0, false));
+		assert!(take_trapped_assets().is_empty());
+
+		assert_ok!(XcmpQueue::discard_overweight(Origin::root(), 1, true));
+		assert_eq!(take_trapped_assets(), vec![((1, Parachain(1000)).into(), assets.drain())]);
+	});
+}
+
+#[test]
+fn discard_overweight_does_not_trap_untrusted_assets() {
+	new_test_ext().execute_with(|| {
+		// The sibling is neither a reserve nor a teleporter of the relay chain's asset.
+		let assets: MultiAssets = vec![(Parent, 1_000u128).into()].into();
+		let reserve =
+			VersionedXcm::<()>::from(Xcm::<()>(vec![ReserveAssetDeposited(assets.clone())]));
+		let teleport = VersionedXcm::<()>::from(Xcm::<()>(vec![ReceiveTeleportedAsset(assets)]));
+		Overweight::<Test>::insert(0, (ParaId::from(1000), 0, reserve.encode()));
+		Overweight::<Test>::insert(1, (ParaId::from(1000), 0, teleport.encode()));
+
+		assert_ok!(XcmpQueue::discard_overweights(Origin::root(), vec![0, 1], true));
+		assert_eq!(Overweight::<Test>::iter().count(), 0);
+		assert!(take_trapped_assets().is_empty());
+	});
+}
+
+#[test]
+fn discard_overweights_skips_unknown_indices() {
+	new_test_ext().execute_with(|| {
+		let xcm = VersionedXcm::<()>::from(Xcm::<()>(vec![ClearOrigin])).encode();
+		Overweight::<Test>::insert(0, (ParaId::from(1000), 0, xcm.clone()));
+		Overweight::<Test>::insert(2, (ParaId::from(1001), 0, xcm));
+
+		assert_noop!(XcmpQueue::discard_overweights(Origin::signed(1), vec![0], false), BadOrigin);
+		assert_ok!(XcmpQueue::discard_overweights(Origin::root(), vec![0, 1, 2], false));
+		assert_eq!(Overweight::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn discard_overweights_is_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmpQueue::discard_overweights(Origin::root(), vec![0, 1, 2, 3], false),
+			Error::<Test>::TooManyIndices,
+		);
+	});
+}
+
+#[test]
+fn inbound_channels_reports_queued_pages() {
+	new_test_ext().execute_with(|| {
+		let mut message = XcmpMessageFormat::ConcatenatedEncodedBlob.encode();
+		message.extend(vec![1u8, 2, 3].encode());
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, &message[1..]);
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 2, &message[1..]);
+		InboundXcmpStatus::<Test>::put(vec![InboundChannelDetails {
+			sender: 1000.into(),
+			state: InboundState::Suspended,
+			message_metadata: vec![
+				(1, XcmpMessageFormat::ConcatenatedEncodedBlob),
+				(2, XcmpMessageFormat::ConcatenatedEncodedBlob),
+			],
+		}]);
+
+		assert_eq!(
+			XcmpQueue::inbound_channels(),
+			vec![InboundChannelInfo {
+				sender: 1000.into(),
+				state: InboundState::Suspended,
+				queued_pages: 2,
+				queued_bytes: 2 * (message.len() as u32 - 1),
+			}]
+		);
+	});
+}
+
+#[test]
+fn outbound_channels_reports_pages_and_signals() {
+	new_test_ext().execute_with(|| {
+		OutboundXcmpMessages::<Test>::insert(ParaId::from(2000), 1, vec![0u8; 10]);
+		OutboundXcmpMessages::<Test>::insert(ParaId::from(2000), 2, vec![0u8; 5]);
+		OutboundXcmpStatus::<Test>::put(vec![OutboundChannelDetails {
+			recipient: 2000.into(),
+			state: OutboundState::Ok,
+			signals_exist: false,
+			first_index: 1,
+			last_index: 3,
+		}]);
+		assert_ok!(XcmpQueue::send_signal(2000.into(), ChannelSignal::Suspend));
+		assert_ok!(XcmpQueue::send_signal(2000.into(), ChannelSignal::Resume));
+
+		assert_eq!(
+			XcmpQueue::outbound_channels(),
+			vec![OutboundChannelInfo {
+				recipient: 2000.into(),
+				state: OutboundState::Ok,
+				queued_pages: 2,
+				queued_bytes: 15,
+				queued_fast_pages: 0,
+				pending_signals: 2,
+			}]
+		);
+	});
+}
+
+#[test]
+fn overweight_messages_are_listed_in_order() {
+	new_test_ext().execute_with(|| {
+		for index in (0..3).rev() {
+			Overweight::<Test>::insert(index, (ParaId::from(1000 + index as u32), 7, vec![0; 4]));
+		}
+
+		let overweight = XcmpQueue::overweight_messages();
+		assert_eq!(overweight.iter().map(|m| m.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+		assert_eq!(
+			overweight[1],
+			OverweightMessageInfo { index: 1, sender: 1001.into(), sent_at: 7, size: 4 }
+		);
+	});
+}
+
+fn blob_page(blobs: &[Vec<u8>]) -> Vec<u8> {
+	blobs.iter().flat_map(|b| b.encode()).collect()
+}
+
+#[test]
+fn blob_messages_are_handed_to_blob_handler() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let blobs = vec![vec![1, 2, 3], vec![4, 5]];
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, blob_page(&blobs));
+
+		let format = XcmpMessageFormat::ConcatenatedEncodedBlob;
+		let (used, is_empty) =
+			XcmpQueue::process_xcmp_message(1000.into(), (1, format), 10_000, 10_000);
+
+		assert_eq!((used, is_empty), (5_000, true));
+		assert_eq!(
+			take_handled_blobs(),
+			vec![(1000.into(), blobs[0].clone()), (1000.into(), blobs[1].clone())]
+		);
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::BlobHandled(
+			1000.into(),
+			BlakeTwo256::hash(&blobs[1]),
+			2_000,
+		)));
+		assert!(!InboundXcmpMessages::<Test>::contains_key(ParaId::from(1000), 1));
+	});
+}
+
+#[test]
+fn heavy_blob_is_left_for_later() {
+	new_test_ext().execute_with(|| {
+		let blobs = vec![vec![1, 2, 3], vec![4, 5]];
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, blob_page(&blobs));
+
+		let format = XcmpMessageFormat::ConcatenatedEncodedBlob;
+		let (used, is_empty) =
+			XcmpQueue::process_xcmp_message(1000.into(), (1, format), 4_000, 10_000);
+
+		assert_eq!((used, is_empty), (3_000, false));
+		assert_eq!(take_handled_blobs(), vec![(1000.into(), blobs[0].clone())]);
+		assert_eq!(InboundXcmpMessages::<Test>::get(ParaId::from(1000), 1), blob_page(&blobs[1..]));
+	});
+}
+
+#[test]
+fn unhandled_and_overweight_blobs_are_dropped() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let blobs = vec![vec![], vec![0; 20], vec![1]];
+		InboundXcmpMessages::<Test>::insert(ParaId::from(1000), 1, blob_page(&blobs));
+
+		let format = XcmpMessageFormat::ConcatenatedEncodedBlob;
+		let (used, is_empty) =
+			XcmpQueue::process_xcmp_message(1000.into(), (1, format), 10_000, 10_000);
+
+		assert_eq!((used, is_empty), (1_000, true));
+		assert_eq!(take_handled_blobs(), vec![(1000.into(), blobs[2].clone())]);
+		for blob in &blobs[..2] {
+			let event = mock::Event::XcmpQueue(crate::Event::BlobUnhandled(
+				1000.into(),
+				BlakeTwo256::hash(blob),
+			));
+			assert!(System::events().iter().any(|r| r.event == event));
+		}
+	});
+}
+
+fn send_blob(recipient: u32, blob: Vec<u8>, qos: ServiceQuality) {
+	assert_ok!(XcmpQueue::send_blob_message_with_qos(recipient.into(), blob, qos));
+}
+
+fn blob_page_message(blobs: &[Vec<u8>]) -> Vec<u8> {
+	let mut page = XcmpMessageFormat::ConcatenatedEncodedBlob.encode();
+	page.extend(blob_page(blobs));
+	page
+}
+
+#[test]
+fn fast_messages_are_sent_ahead_of_ordered_pages() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		send_blob(2000, vec![1; 60], ServiceQuality::Ordered);
+		send_blob(2000, vec![2; 60], ServiceQuality::Ordered);
+		send_blob(2000, vec![3], ServiceQuality::Fast);
+		send_blob(2000, vec![4], ServiceQuality::Fast);
+
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![3], vec![4]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![1; 60]]))]
+		);
+		send_blob(2000, vec![5], ServiceQuality::Fast);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![5]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![2; 60]]))]
+		);
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+		assert!(OutboundXcmpStatus::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn fast_messages_are_paged() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		let blob = vec![1; MAX_MESSAGE_SIZE / 2];
+		for _ in 0..3 {
+			send_blob(2000, blob.clone(), ServiceQuality::Fast);
+		}
+
+		assert_eq!(XcmpQueue::outbound_channels()[0].queued_fast_pages, 3);
+		for _ in 0..3 {
+			assert_eq!(
+				XcmpQueue::take_outbound_messages(10),
+				vec![(2000.into(), blob_page_message(&[blob.clone()]))]
+			);
+		}
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+	});
+}
+
+#[test]
+fn channels_with_fast_messages_take_precedence() {
+	new_test_ext().execute_with(|| {
+		for recipient in [2000, 2001, 2002] {
+			open_channel(recipient.into());
+			send_blob(recipient, vec![1], ServiceQuality::Ordered);
+		}
+		send_blob(2002, vec![2], ServiceQuality::Fast);
+
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(1),
+			vec![(2002.into(), blob_page_message(&[vec![2]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(3),
+			vec![
+				(2000.into(), blob_page_message(&[vec![1]])),
+				(2001.into(), blob_page_message(&[vec![1]])),
+				(2002.into(), blob_page_message(&[vec![1]])),
+			]
+		);
+	});
+}
+
+#[test]
+fn fast_messages_are_dropped_when_channel_closes() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		send_blob(2000, vec![1], ServiceQuality::Fast);
+		mock::OPEN_CHANNELS.with(|c| c.borrow_mut().clear());
+
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+		assert!(!FastOutboundXcmpIndices::<Test>::contains_key(ParaId::from(2000)));
+		assert_eq!(FastOutboundXcmpMessages::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn fast_messages_are_kept_when_channel_resumes() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		XcmpQueue::suspend_channel(2000.into());
+		send_blob(2000, vec![1], ServiceQuality::Fast);
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+
+		XcmpQueue::resume_channel(2000.into());
+		assert_eq!(XcmpQueue::outbound_channels()[0].queued_fast_pages, 1);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![1]]))]
+		);
+		assert!(OutboundXcmpStatus::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn messages_dropped_on_channel_close_are_reported() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		open_channel(2000.into());
+		send_blob(2000, vec![1], ServiceQuality::Ordered);
+		send_blob(2000, vec![2], ServiceQuality::Ordered);
+		send_blob(2000, vec![3], ServiceQuality::Fast);
+		mock::OPEN_CHANNELS.with(|c| c.borrow_mut().clear());
+
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+		let dropped = System::events()
+			.into_iter()
+			.filter_map(|r| match r.event {
+				mock::Event::XcmpQueue(crate::Event::XcmpMessageDropped(recipient, hash)) =>
+					Some((recipient, hash)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		let hash = |blob: Vec<u8>| BlakeTwo256::hash(&blob.encode());
+		assert_eq!(
+			dropped,
+			vec![
+				(2000.into(), hash(vec![3])),
+				(2000.into(), hash(vec![1])),
+				(2000.into(), hash(vec![2])),
+			]
+		);
+		assert!(OutboundXcmpStatus::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn oversized_pages_are_split() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		for i in 0..3 {
+			send_blob(2000, vec![i; 30], ServiceQuality::Ordered);
+		}
+		send_blob(2000, vec![3; 60], ServiceQuality::Ordered);
+		assert_eq!(XcmpQueue::outbound_channels()[0].queued_pages, 2);
+		set_max_message_size(2000.into(), 70);
+
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![0; 30], vec![1; 30]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![2; 30]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![3; 60]]))]
+		);
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+		assert!(OutboundXcmpStatus::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn messages_which_no_longer_fit_are_dropped() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		open_channel(2000.into());
+		for i in 0..3 {
+			send_blob(2000, vec![i; 30], ServiceQuality::Ordered);
+		}
+		send_blob(2000, vec![3; 60], ServiceQuality::Ordered);
+		set_max_message_size(2000.into(), 40);
+
+		for i in 0..3 {
+			assert_eq!(
+				XcmpQueue::take_outbound_messages(10),
+				vec![(2000.into(), blob_page_message(&[vec![i; 30]]))]
+			);
+		}
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::XcmpMessageDropped(
+			2000.into(),
+			BlakeTwo256::hash(&vec![3u8; 60].encode()),
+		)));
+		assert!(OutboundXcmpStatus::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn oversized_fast_pages_are_split() {
+	new_test_ext().execute_with(|| {
+		open_channel(2000.into());
+		for i in 0..3 {
+			send_blob(2000, vec![i; 30], ServiceQuality::Fast);
+		}
+		set_max_message_size(2000.into(), 70);
+
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![0; 30], vec![1; 30]]))]
+		);
+		assert_eq!(
+			XcmpQueue::take_outbound_messages(10),
+			vec![(2000.into(), blob_page_message(&[vec![2; 30]]))]
+		);
+		assert!(XcmpQueue::take_outbound_messages(10).is_empty());
+	});
+}
+
+#[test]
+fn update_inbound_share_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			XcmpQueue::update_inbound_share(Origin::signed(1), 1000.into(), Some(3)),
+			BadOrigin
+		);
+
+		assert_ok!(XcmpQueue::update_inbound_share(Origin::root(), 1000.into(), Some(3)));
+		assert_eq!(InboundShares::<Test>::get(ParaId::from(1000)), Some(3));
+		System::assert_last_event(mock::Event::XcmpQueue(crate::Event::InboundShareUpdated(
+			1000.into(),
+			Some(3),
+		)));
+
+		assert_ok!(XcmpQueue::update_inbound_share(Origin::root(), 1000.into(), None));
+		assert_eq!(InboundShares::<Test>::get(ParaId::from(1000)), None);
+	});
+}
+
+/// Queue `pages` pages of ten 10-byte blobs, each weighing 10_000, from every sender.
+fn flood(senders: &[u32], pages: u32) {
+	let page = blob_page_message(&vec![vec![0u8; 10]; 10]);
+	for sent_at in 1..=pages {
+		let messages = senders.iter().map(|&sender| (sender.into(), sent_at, &page[..]));
+		XcmpQueue::handle_xcmp_messages(messages, 0);
+	}
+}
+
+fn handled_per_sender(senders: &[u32]) -> Vec<usize> {
+	let handled = take_handled_blobs();
+	senders
+		.iter()
+		.map(|&sender| handled.iter().filter(|(s, _)| *s == sender.into()).count())
+		.collect()
+}
+
+/// Make all of the weight left after servicing the shares available at once.
+fn unlock_leftover_weight_at_once() {
+	assert_ok!(XcmpQueue::update_threshold_weight(Origin::root(), 10_000));
+	assert_ok!(XcmpQueue::update_weight_restrict_decay(Origin::root(), 0));
+}
+
+#[test]
+fn flooding_sender_cannot_starve_others() {
+	new_test_ext().execute_with(|| {
+		flood(&[1000], 4);
+		flood(&[2000, 3000], 1);
+
+		XcmpQueue::service_xcmp_queue(60_000);
+
+		assert_eq!(handled_per_sender(&[1000, 2000, 3000]), vec![2, 2, 2]);
+	});
+}
+
+#[test]
+fn weight_is_shared_according_to_inbound_shares() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmpQueue::update_inbound_share(Origin::root(), 2000.into(), Some(3)));
+		flood(&[1000, 2000], 1);
+
+		XcmpQueue::service_xcmp_queue(80_000);
+
+		assert_eq!(handled_per_sender(&[1000, 2000]), vec![2, 6]);
+	});
+}
+
+#[test]
+fn sender_without_share_only_gets_leftover_weight() {
+	new_test_ext().execute_with(|| {
+		unlock_leftover_weight_at_once();
+		assert_ok!(XcmpQueue::update_inbound_share(Origin::root(), 1000.into(), Some(0)));
+		flood(&[1000, 2000], 2);
+
+		XcmpQueue::service_xcmp_queue(250_000);
+
+		assert_eq!(handled_per_sender(&[1000, 2000]), vec![5, 20]);
+	});
+}
+
+#[test]
+fn leftover_weight_is_used_by_busy_senders() {
+	new_test_ext().execute_with(|| {
+		unlock_leftover_weight_at_once();
+		flood(&[1000], 3);
+		flood(&[2000], 1);
+
+		XcmpQueue::service_xcmp_queue(300_000);
+
+		assert_eq!(handled_per_sender(&[1000, 2000]), vec![20, 10]);
+	});
+}
+
+#[test]
+fn processed_pages_are_weighed() {
+	new_test_ext().execute_with(|| {
+		set_page_weight(5_000);
+		flood(&[1000], 2);
+
+		// Two pages of ten blobs weighing 10_000 each.
+		assert_eq!(XcmpQueue::service_xcmp_queue(1_000_000), 2 * (100_000 + 5_000));
+		assert_eq!(handled_per_sender(&[1000]), vec![20]);
+	});
+}
+
+#[test]
+fn page_weight_counts_towards_the_share() {
+	new_test_ext().execute_with(|| {
+		set_page_weight(5_000);
+		flood(&[1000, 2000], 1);
+
+		// Each share of 30_000 leaves room for two blobs once the page is paid for.
+		assert_eq!(XcmpQueue::service_xcmp_queue(60_000), 2 * (20_000 + 5_000));
+		assert_eq!(handled_per_sender(&[1000, 2000]), vec![2, 2]);
+	});
+}
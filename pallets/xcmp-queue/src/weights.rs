@@ -0,0 +1,149 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Cumulus.
+
+// Cumulus is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights for `cumulus_pallet_xcmp_queue`.
+//!
+//! The weights of the servicing paths only cover the bookkeeping of the queue, the weight used
+//! by executing the messages is accounted for separately.
+//!
+//! The values are hand-written estimates, they were NOT generated by the benchmarking CLI. They
+//! need to be replaced with the output of this pallet's benchmarks before they are relied upon.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// The weight info trait for `cumulus_pallet_xcmp_queue`.
+pub trait WeightInfo {
+	fn service_overweight() -> Weight;
+	fn discard_overweight() -> Weight;
+	fn discard_overweights(n: u32) -> Weight;
+	fn set_config_with_u32() -> Weight;
+	fn set_config_with_weight() -> Weight;
+	fn update_inbound_share() -> Weight;
+	/// Recording a single incoming page in the queue.
+	fn enqueue_xcmp_page() -> Weight;
+	/// Taking a single page from the queue and writing back what remains of it.
+	fn process_xcmp_page() -> Weight;
+	/// Servicing the queue with `c` inbound channels, excluding the processed pages.
+	fn service_xcmp_queue(c: u32) -> Weight;
+}
+
+/// Weights for `cumulus_pallet_xcmp_queue` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn service_overweight() -> Weight {
+		(25_148_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweight() -> Weight {
+		(19_871_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweights(n: u32) -> Weight {
+		(3_102_000 as Weight)
+			.saturating_add((9_415_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	fn set_config_with_u32() -> Weight {
+		(2_717_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_config_with_weight() -> Weight {
+		(2_683_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn update_inbound_share() -> Weight {
+		(2_410_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn enqueue_xcmp_page() -> Weight {
+		(18_062_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	fn process_xcmp_page() -> Weight {
+		(10_305_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn service_xcmp_queue(c: u32) -> Weight {
+		(6_219_000 as Weight)
+			.saturating_add((2_534_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn service_overweight() -> Weight {
+		(25_148_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweight() -> Weight {
+		(19_871_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn discard_overweights(n: u32) -> Weight {
+		(3_102_000 as Weight)
+			.saturating_add((9_415_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	fn set_config_with_u32() -> Weight {
+		(2_717_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_config_with_weight() -> Weight {
+		(2_683_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn update_inbound_share() -> Weight {
+		(2_410_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn enqueue_xcmp_page() -> Weight {
+		(18_062_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn process_xcmp_page() -> Weight {
+		(10_305_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn service_xcmp_queue(c: u32) -> Weight {
+		(6_219_000 as Weight)
+			.saturating_add((2_534_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
@@ -211,5 +211,6 @@ fn testnet_genesis(
 		aura: Default::default(),
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
+		dmp_queue: Default::default(),
 	}
 }
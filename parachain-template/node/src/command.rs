@@ -266,13 +266,23 @@ pub fn run() -> Result<()> {
 					.map(|e| e.para_id)
 					.ok_or_else(|| "Could not find parachain ID in chain-spec.")?;
 
+				let id = ParaId::from(para_id);
+
+				if let Some(sealing) = cli.run.sealing() {
+					info!("Parachain id: {:?}", id);
+					info!("Authoring blocks without a relay chain, sealing: {:?}", sealing);
+
+					return crate::service::start_dev_node(config, id, sealing)
+						.await
+						.map(|r| r.0)
+						.map_err(Into::into)
+				}
+
 				let polkadot_cli = RelayChainCli::new(
 					&config,
 					[RelayChainCli::executable_name()].iter().chain(cli.relay_chain_args.iter()),
 				);
 
-				let id = ParaId::from(para_id);
-
 				let parachain_account =
 					AccountIdConversion::<polkadot_primitives::v0::AccountId>::into_account(&id);
 
@@ -292,10 +302,19 @@ pub fn run() -> Result<()> {
 				info!("Parachain genesis state: {}", genesis_state);
 				info!("Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
-				crate::service::start_parachain_node(config, polkadot_config, id)
-					.await
-					.map(|r| r.0)
-					.map_err(Into::into)
+				let full_node_relay_chain_rpc_url = cli.run.full_node_relay_chain_rpc_url.clone();
+				let pov_recovery = cli.run.pov_recovery.config();
+
+				crate::service::start_parachain_node(
+					config,
+					polkadot_config,
+					id,
+					full_node_relay_chain_rpc_url,
+					pov_recovery,
+				)
+				.await
+				.map(|r| r.0)
+				.map_err(Into::into)
 			})
 		},
 	}
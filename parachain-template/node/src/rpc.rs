@@ -7,9 +7,13 @@
 
 use std::sync::Arc;
 
-use parachain_template_runtime::{opaque::Block, AccountId, Balance, Index as Nonce};
+use parachain_template_runtime::{opaque::Block, AccountId, Balance, Hash, Index as Nonce};
 
+use cumulus_client_dev::MessageInjector;
+use cumulus_primitives_core::ParaId;
+use futures::channel::mpsc;
 use sc_client_api::AuxStore;
+use sc_consensus_manual_seal::EngineCommand;
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -55,3 +59,40 @@ where
 
 	io
 }
+
+/// Dependencies of the RPCs that are only available in development mode.
+pub struct DevDeps {
+	/// The parachain id of the node.
+	pub para_id: ParaId,
+	/// Channel for sending commands to the manual seal authorship task.
+	pub command_sink: mpsc::Sender<EngineCommand<Hash>>,
+	/// Queue of the messages to inject into the next block.
+	pub message_injector: MessageInjector,
+}
+
+/// Instantiate all RPC extensions of a node running in development mode.
+pub fn create_dev<C, P>(deps: FullDeps<C, P>, dev_deps: DevDeps) -> RpcExtension
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ AuxStore
+		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+	C::Api: BlockBuilder<Block>,
+	P: TransactionPool + Sync + Send + 'static,
+{
+	use cumulus_client_dev::rpc::{DevApi, DevRpc};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApi};
+
+	let mut io = create_full(deps);
+	let DevDeps { para_id, command_sink, message_injector } = dev_deps;
+
+	io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	io.extend_with(DevApi::to_delegate(DevRpc::new(para_id, message_injector)));
+
+	io
+}
@@ -2,6 +2,7 @@
 
 // std
 use std::{sync::Arc, time::Duration};
+use url::Url;
 
 // Local Runtime Types
 use parachain_template_runtime::{
@@ -9,22 +10,35 @@ use parachain_template_runtime::{
 };
 
 // Cumulus Imports
+use cumulus_client_cli::Sealing;
 use cumulus_client_consensus_aura::{AuraConsensus, BuildAuraConsensusParams, SlotProportion};
 use cumulus_client_consensus_common::ParachainConsensus;
+use cumulus_client_dev::MessageInjector;
 use cumulus_client_network::BlockAnnounceValidator;
+use cumulus_client_pov_recovery::PoVRecoveryConfig;
 use cumulus_client_service::{
 	prepare_node_config, start_collator, start_full_node, StartCollatorParams, StartFullNodeParams,
 };
 use cumulus_primitives_core::ParaId;
-use cumulus_relay_chain_interface::RelayChainInterface;
-use cumulus_relay_chain_local::build_relay_chain_interface;
+use cumulus_relay_chain_interface::{
+	CachingRelayChainInterface, RelayChainInterface, DEFAULT_CACHE_SIZE,
+};
+use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
+use polkadot_service::CollatorPair;
 
 // Substrate Imports
+use futures::StreamExt;
 use sc_client_api::ExecutorProvider;
+use sc_consensus::LongestChain;
+use sc_consensus_manual_seal::{
+	consensus::{aura::AuraConsensusDataProvider, timestamp::SlotTimestampProvider},
+	run_manual_seal, EngineCommand, ManualSealParams,
+};
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::NetworkService;
 use sc_service::{Configuration, PartialComponents, Role, TFullBackend, TFullClient, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
+use sc_transaction_pool_api::TransactionPool;
 use sp_api::ConstructRuntimeApi;
 use sp_consensus::SlotData;
 use sp_keystore::SyncCryptoStorePtr;
@@ -161,6 +175,51 @@ where
 	Ok(params)
 }
 
+/// Build the interface to the relay chain.
+///
+/// With a `full_node_relay_chain_rpc_url` the interface talks to the relay chain node at that
+/// address, otherwise an embedded relay chain node is started. Only the embedded node provides a
+/// collator key, as collating needs the overseer of the embedded node and is not supported over
+/// RPC. Either way the interface caches the results of its most common requests per relay chain
+/// block.
+async fn build_relay_chain_interface(
+	polkadot_config: Configuration,
+	telemetry_worker_handle: Option<TelemetryWorkerHandle>,
+	task_manager: &mut TaskManager,
+	full_node_relay_chain_rpc_url: Option<Url>,
+	prometheus_registry: Option<&Registry>,
+) -> sc_service::error::Result<(Arc<dyn RelayChainInterface + 'static>, Option<CollatorPair>)> {
+	let (relay_chain_interface, collator_key) = match full_node_relay_chain_rpc_url {
+		Some(url) => {
+			let relay_chain_interface =
+				RelayChainRPCInterface::new(url.as_str()).await.map_err(|e| {
+					format!("Failed to connect to the relay chain node at `{}`: {}", url, e)
+				})?;
+
+			(Arc::new(relay_chain_interface) as Arc<dyn RelayChainInterface>, None)
+		},
+		None => cumulus_relay_chain_local::build_relay_chain_interface(
+			polkadot_config,
+			telemetry_worker_handle,
+			task_manager,
+		)
+		.map(|(relay_chain_interface, collator_key)| (relay_chain_interface, Some(collator_key)))
+		.map_err(|e| match e {
+			polkadot_service::Error::Sub(x) => x,
+			s => format!("{}", s).into(),
+		})?,
+	};
+
+	let relay_chain_interface = CachingRelayChainInterface::new(
+		relay_chain_interface,
+		DEFAULT_CACHE_SIZE,
+		&task_manager.spawn_handle(),
+		prometheus_registry,
+	)?;
+
+	Ok((Arc::new(relay_chain_interface), collator_key))
+}
+
 /// Start a node with the given parachain `Configuration` and relay chain `Configuration`.
 ///
 /// This is the actual implementation that is abstract over the executor and the runtime api.
@@ -169,6 +228,8 @@ async fn start_node_impl<RuntimeApi, Executor, RB, BIQ, BIC>(
 	parachain_config: Configuration,
 	polkadot_config: Configuration,
 	id: ParaId,
+	full_node_relay_chain_rpc_url: Option<Url>,
+	pov_recovery: Option<PoVRecoveryConfig>,
 	_rpc_ext_builder: RB,
 	build_import_queue: BIQ,
 	build_consensus: BIC,
@@ -241,14 +302,17 @@ where
 	let backend = params.backend.clone();
 	let mut task_manager = params.task_manager;
 
-	let (relay_chain_interface, collator_key) =
-		build_relay_chain_interface(polkadot_config, telemetry_worker_handle, &mut task_manager)
-			.map_err(|e| match e {
-				polkadot_service::Error::Sub(x) => x,
-				s => format!("{}", s).into(),
-			})?;
+	let (relay_chain_interface, collator_key) = build_relay_chain_interface(
+		polkadot_config,
+		telemetry_worker_handle,
+		&mut task_manager,
+		full_node_relay_chain_rpc_url,
+		parachain_config.prometheus_registry(),
+	)
+	.await?;
 
-	let block_announce_validator = BlockAnnounceValidator::new(relay_chain_interface.clone(), id);
+	let block_announce_validator = BlockAnnounceValidator::new(relay_chain_interface.clone(), id)
+		.with_metrics(parachain_config.prometheus_registry())?;
 
 	let force_authoring = parachain_config.force_authoring;
 	let validator = parachain_config.role.is_authority();
@@ -302,6 +366,10 @@ where
 	};
 
 	if validator {
+		let collator_key = collator_key.ok_or_else(|| {
+			"Collators need an embedded relay chain node, `--full-node-relay-chain-rpc-url` is not supported"
+		})?;
+
 		let parachain_consensus = build_consensus(
 			client.clone(),
 			prometheus_registry.as_ref(),
@@ -328,6 +396,7 @@ where
 			import_queue,
 			collator_key,
 			slot_duration: Duration::from_secs(6),
+			prometheus_registry: prometheus_registry.as_ref(),
 		};
 
 		start_collator(params).await?;
@@ -338,6 +407,9 @@ where
 			task_manager: &mut task_manager,
 			para_id: id,
 			relay_chain_interface,
+			import_queue,
+			pov_recovery,
+			prometheus_registry: prometheus_registry.as_ref(),
 		};
 
 		start_full_node(params)?;
@@ -399,6 +471,8 @@ pub async fn start_parachain_node(
 	parachain_config: Configuration,
 	polkadot_config: Configuration,
 	id: ParaId,
+	full_node_relay_chain_rpc_url: Option<Url>,
+	pov_recovery: Option<PoVRecoveryConfig>,
 ) -> sc_service::error::Result<(
 	TaskManager,
 	Arc<TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<TemplateRuntimeExecutor>>>,
@@ -407,6 +481,8 @@ pub async fn start_parachain_node(
 		parachain_config,
 		polkadot_config,
 		id,
+		full_node_relay_chain_rpc_url,
+		pov_recovery,
 		|_| Ok(Default::default()),
 		parachain_build_import_queue,
 		|client,
@@ -474,3 +550,149 @@ pub async fn start_parachain_node(
 	)
 	.await
 }
+
+/// Start a parachain node in development mode.
+///
+/// The node doesn't follow any relay chain. Blocks are authored with manual seal on top of a
+/// simulated relay chain, and downward and horizontal messages can be injected into them over RPC.
+pub async fn start_dev_node(
+	parachain_config: Configuration,
+	id: ParaId,
+	sealing: Sealing,
+) -> sc_service::error::Result<(
+	TaskManager,
+	Arc<TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<TemplateRuntimeExecutor>>>,
+)> {
+	if matches!(parachain_config.role, Role::Light) {
+		return Err("Light client not supported!".into())
+	}
+
+	let params = new_partial::<RuntimeApi, TemplateRuntimeExecutor, _>(
+		&parachain_config,
+		|client, config, _, task_manager| {
+			Ok(sc_consensus_manual_seal::import_queue(
+				Box::new(client),
+				&task_manager.spawn_essential_handle(),
+				config.prometheus_registry(),
+			))
+		},
+	)?;
+	let (mut telemetry, _) = params.other;
+
+	let client = params.client.clone();
+	let backend = params.backend.clone();
+
+	let mut task_manager = params.task_manager;
+	let prometheus_registry = parachain_config.prometheus_registry().cloned();
+	let transaction_pool = params.transaction_pool.clone();
+	let (network, system_rpc_tx, start_network) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &parachain_config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue: params.import_queue,
+			block_announce_validator_builder: None,
+			warp_sync: None,
+		})?;
+
+	let (command_sink, commands_stream) = futures::channel::mpsc::channel(1024);
+	let message_injector = MessageInjector::new();
+
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let transaction_pool = transaction_pool.clone();
+		let message_injector = message_injector.clone();
+
+		Box::new(move |deny_unsafe, _| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: transaction_pool.clone(),
+				deny_unsafe,
+			};
+			let dev_deps = crate::rpc::DevDeps {
+				para_id: id,
+				command_sink: command_sink.clone(),
+				message_injector: message_injector.clone(),
+			};
+
+			Ok(crate::rpc::create_dev(deps, dev_deps))
+		})
+	};
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		rpc_extensions_builder,
+		client: client.clone(),
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut task_manager,
+		config: parachain_config,
+		keystore: params.keystore_container.sync_keystore(),
+		backend: backend.clone(),
+		network,
+		system_rpc_tx,
+		telemetry: telemetry.as_mut(),
+	})?;
+
+	// With instant sealing every imported transaction triggers a new block, next to the blocks
+	// requested over RPC.
+	let commands_stream = match sealing {
+		Sealing::Instant => futures::stream::select(
+			transaction_pool
+				.import_notification_stream()
+				.map(|_| EngineCommand::SealNewBlock {
+					create_empty: false,
+					finalize: false,
+					parent_hash: None,
+					sender: None,
+				}),
+			commands_stream,
+		)
+		.boxed(),
+		Sealing::Manual => commands_stream.boxed(),
+	};
+
+	let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|t| t.handle()),
+	);
+
+	let client_for_cidp = client.clone();
+
+	task_manager.spawn_essential_handle().spawn_blocking(
+		"manual-seal",
+		None,
+		run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool,
+			commands_stream,
+			select_chain: LongestChain::new(backend),
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+			create_inherent_data_providers: move |parent: Hash, ()| {
+				let client = client_for_cidp.clone();
+				let messages = message_injector.take();
+
+				async move {
+					let time = SlotTimestampProvider::new_aura(client.clone())?;
+
+					let slot =
+						sp_consensus_aura::inherents::InherentDataProvider::new(time.slot().into());
+
+					let parachain_inherent =
+						cumulus_client_dev::mock_parachain_inherent::<Block, _, _>(
+							&*client, parent, id, messages,
+						)?;
+					Ok((time, slot, parachain_inherent))
+				}
+			},
+		}),
+	);
+
+	start_network.start_network();
+
+	Ok((task_manager, client))
+}
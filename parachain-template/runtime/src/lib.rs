@@ -367,6 +367,9 @@ impl pallet_transaction_payment::Config for Runtime {
 parameter_types! {
 	pub const ReservedXcmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
 	pub const ReservedDmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
+	pub const MaxPendingUpwardMessages: u32 = 1_000;
+	pub const UpgradeAuthorizationPeriod: BlockNumber = 7 * DAYS;
+	pub const RelayParentHistoryLength: u32 = 16;
 }
 
 impl cumulus_pallet_parachain_system::Config for Runtime {
@@ -378,6 +381,12 @@ impl cumulus_pallet_parachain_system::Config for Runtime {
 	type OutboundXcmpMessageSource = XcmpQueue;
 	type XcmpMessageHandler = XcmpQueue;
 	type ReservedXcmpWeight = ReservedXcmpWeight;
+	type MaxPendingUpwardMessages = MaxPendingUpwardMessages;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = UpgradeAuthorizationPeriod;
+	type RelayParentHistoryLength = RelayParentHistoryLength;
+	type WeightInfo = cumulus_pallet_parachain_system::weights::SubstrateWeight<Runtime>;
 }
 
 impl parachain_info::Config for Runtime {}
@@ -519,18 +528,42 @@ impl cumulus_pallet_xcm::Config for Runtime {
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 }
 
+parameter_types! {
+	/// Trapping the assets of a discarded overweight message writes to the asset trap and, for
+	/// teleports, to the checking account.
+	pub TrapAssetsWeight: Weight = RocksDbWeight::get().reads_writes(2, 2) + UnitWeightCost::get();
+	pub const MaxDiscard: u32 = 100;
+}
+
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ChannelInfo = ParachainSystem;
 	type VersionWrapper = ();
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type BlobHandler = ();
+	type WeightInfo = cumulus_pallet_xcmp_queue::weights::SubstrateWeight<Runtime>;
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type WeightInfo = cumulus_pallet_dmp_queue::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -622,7 +655,7 @@ construct_runtime!(
 		XcmpQueue: cumulus_pallet_xcmp_queue::{Pallet, Call, Storage, Event<T>} = 30,
 		PolkadotXcm: pallet_xcm::{Pallet, Call, Event<T>, Origin} = 31,
 		CumulusXcm: cumulus_pallet_xcm::{Pallet, Event<T>, Origin} = 32,
-		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Storage, Event<T>} = 33,
+		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Config, Storage, Event<T>} = 33,
 
 		// Template
 		TemplatePallet: pallet_template::{Pallet, Call, Storage, Event<T>}  = 40,
@@ -254,17 +254,27 @@ impl pallet_sudo::Config for Runtime {
 parameter_types! {
 	pub const ReservedXcmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
 	pub const ReservedDmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
+	pub const MaxPendingUpwardMessages: u32 = 1_000;
+	pub const UpgradeAuthorizationPeriod: BlockNumber = 7 * DAYS;
+	pub const RelayParentHistoryLength: u32 = 16;
 }
 
 impl cumulus_pallet_parachain_system::Config for Runtime {
 	type Event = Event;
-	type OnSystemEvent = ();
+	type OnSystemEvent = HrmpChannels;
 	type SelfParaId = parachain_info::Pallet<Runtime>;
 	type OutboundXcmpMessageSource = XcmpQueue;
-	type DmpMessageHandler = DmpQueue;
+	type DmpMessageHandler =
+		cumulus_pallet_hrmp_channels::HandleHrmpNotifications<Runtime, DmpQueue>;
 	type ReservedDmpWeight = ReservedDmpWeight;
 	type XcmpMessageHandler = XcmpQueue;
 	type ReservedXcmpWeight = ReservedXcmpWeight;
+	type MaxPendingUpwardMessages = MaxPendingUpwardMessages;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = UpgradeAuthorizationPeriod;
+	type RelayParentHistoryLength = RelayParentHistoryLength;
+	type WeightInfo = cumulus_pallet_parachain_system::weights::SubstrateWeight<Runtime>;
 }
 
 impl parachain_info::Config for Runtime {}
@@ -448,18 +458,61 @@ impl cumulus_pallet_xcm::Config for Runtime {
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 }
 
+parameter_types! {
+	/// Trapping the assets of a discarded overweight message writes to the asset trap and, for
+	/// teleports, to the checking account.
+	pub TrapAssetsWeight: Weight = RocksDbWeight::get().reads_writes(2, 2) + UnitWeightCost::get();
+	pub const MaxDiscard: u32 = 100;
+}
+
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ChannelInfo = ParachainSystem;
 	type VersionWrapper = ();
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type BlobHandler = ();
+	type WeightInfo = cumulus_pallet_xcmp_queue::weights::SubstrateWeight<Runtime>;
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ExecuteOverweightOrigin = frame_system::EnsureRoot<AccountId>;
+	type ControllerOrigin = frame_system::EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type WeightInfo = cumulus_pallet_dmp_queue::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	/// The index of the `hrmp` pallet in the Rococo runtime.
+	pub const RelayHrmpPalletIndex: u8 = 60;
+	pub const RelayHrmpFee: u128 = 100 * MILLIROC;
+	pub const RelayHrmpCallWeight: Weight = 1_000_000_000;
+}
+
+impl cumulus_pallet_hrmp_channels::Config for Runtime {
+	type Event = Event;
+	type SelfParaId = parachain_info::Pallet<Runtime>;
+	type OpenChannels = ParachainSystem;
+	type UmpSender = cumulus_primitives_utility::ParentAsUmp<ParachainSystem, ()>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AutoAcceptFrom = frame_support::traits::Nothing;
+	type HrmpPalletIndex = RelayHrmpPalletIndex;
+	type RelayFee = RelayHrmpFee;
+	type RelayCallWeight = RelayHrmpCallWeight;
 }
 
 impl cumulus_ping::Config for Runtime {
@@ -533,7 +586,8 @@ construct_runtime! {
 		XcmpQueue: cumulus_pallet_xcmp_queue::{Pallet, Call, Storage, Event<T>} = 50,
 		PolkadotXcm: pallet_xcm::{Pallet, Call, Event<T>, Origin} = 51,
 		CumulusXcm: cumulus_pallet_xcm::{Pallet, Call, Event<T>, Origin} = 52,
-		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Storage, Event<T>} = 53,
+		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Config, Storage, Event<T>} = 53,
+		HrmpChannels: cumulus_pallet_hrmp_channels::{Pallet, Call, Storage, Event<T>} = 54,
 
 		Spambot: cumulus_ping::{Pallet, Call, Storage, Event<T>} = 99,
 	}
@@ -701,6 +755,42 @@ impl_runtime_apis! {
 			ParachainSystem::collect_collation_info(header)
 		}
 	}
+
+	impl cumulus_primitives_core::RelayStorageKeysApi<Block> for Runtime {
+		fn additional_relay_storage_keys() -> Vec<Vec<u8>> {
+			ParachainSystem::additional_relay_storage_keys()
+		}
+	}
+
+	impl cumulus_pallet_xcmp_queue_rpc_runtime_api::XcmpQueueApi<Block> for Runtime {
+		fn inbound_channels() -> Vec<cumulus_pallet_xcmp_queue::InboundChannelInfo> {
+			XcmpQueue::inbound_channels()
+		}
+
+		fn outbound_channels() -> Vec<cumulus_pallet_xcmp_queue::OutboundChannelInfo> {
+			XcmpQueue::outbound_channels()
+		}
+
+		fn overweight_messages() -> Vec<cumulus_pallet_xcmp_queue::OverweightMessageInfo> {
+			XcmpQueue::overweight_messages()
+		}
+	}
+
+	impl cumulus_pallet_dmp_queue_rpc_runtime_api::DmpQueueApi<Block> for Runtime {
+		fn queue_info() -> cumulus_pallet_dmp_queue::QueueInfo {
+			DmpQueue::queue_info()
+		}
+
+		fn overweight_indices() -> Vec<cumulus_pallet_dmp_queue::OverweightIndex> {
+			DmpQueue::overweight_indices()
+		}
+	}
+
+	impl cumulus_pallet_parachain_system_rpc_runtime_api::ParachainUpgradeApi<Block> for Runtime {
+		fn upgrade_status() -> cumulus_pallet_parachain_system::UpgradeStatus<<Block as BlockT>::Hash> {
+			ParachainSystem::upgrade_status()
+		}
+	}
 }
 
 struct CheckInherents;
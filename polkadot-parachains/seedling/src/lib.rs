@@ -161,6 +161,12 @@ impl cumulus_pallet_parachain_system::Config for Runtime {
 	type ReservedDmpWeight = ();
 	type XcmpMessageHandler = ();
 	type ReservedXcmpWeight = ();
+	type MaxPendingUpwardMessages = frame_support::traits::ConstU32<1_000>;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = frame_support::traits::ConstU32<100_800>;
+	type RelayParentHistoryLength = frame_support::traits::ConstU32<16>;
+	type WeightInfo = cumulus_pallet_parachain_system::weights::SubstrateWeight<Runtime>;
 }
 
 impl parachain_info::Config for Runtime {}
@@ -189,6 +189,7 @@ fn testnet_genesis(
 		aura: rococo_parachain_runtime::AuraConfig { authorities: initial_authorities },
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
+		dmp_queue: Default::default(),
 	}
 }
 
@@ -392,6 +393,7 @@ fn statemint_genesis(
 		aura: Default::default(),
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
+		dmp_queue: Default::default(),
 	}
 }
 
@@ -576,6 +578,7 @@ fn statemine_genesis(
 		aura: Default::default(),
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
+		dmp_queue: Default::default(),
 	}
 }
 
@@ -755,5 +758,6 @@ fn westmint_genesis(
 		aura: Default::default(),
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
+		dmp_queue: Default::default(),
 	}
 }
@@ -86,6 +86,7 @@ impl<T: sc_service::ChainSpec + 'static> IdentifyChain for T {
 
 fn load_spec(id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
 	Ok(match id {
+		"dev" => Box::new(chain_spec::get_chain_spec()),
 		"staging" => Box::new(chain_spec::staging_test_net()),
 		"tick" => Box::new(chain_spec::ChainSpec::from_json_bytes(
 			&include_bytes!("../res/tick.json")[..],
@@ -459,6 +460,49 @@ pub fn run() -> Result<()> {
 					.map(|e| e.para_id)
 					.ok_or_else(|| "Could not find parachain extension in chain-spec.")?;
 
+				let id = ParaId::from(para_id);
+
+				if let Some(sealing) = cli.run.sealing() {
+					info!("Parachain id: {:?}", id);
+					info!("Authoring blocks without a relay chain, sealing: {:?}", sealing);
+
+					return if config.chain_spec.is_statemint() {
+						crate::service::start_dev_node::<
+							statemint_runtime::RuntimeApi,
+							StatemintRuntimeExecutor,
+						>(config, id, sealing)
+						.await
+						.map(|r| r.0)
+						.map_err(Into::into)
+					} else if config.chain_spec.is_statemine() {
+						crate::service::start_dev_node::<
+							statemine_runtime::RuntimeApi,
+							StatemineRuntimeExecutor,
+						>(config, id, sealing)
+						.await
+						.map(|r| r.0)
+						.map_err(Into::into)
+					} else if config.chain_spec.is_westmint() {
+						crate::service::start_dev_node::<
+							westmint_runtime::RuntimeApi,
+							WestmintRuntimeExecutor,
+						>(config, id, sealing)
+						.await
+						.map(|r| r.0)
+						.map_err(Into::into)
+					} else if config.chain_spec.is_shell() || config.chain_spec.is_seedling() {
+						Err("Shell runtimes can not run without a relay chain.".into())
+					} else {
+						crate::service::start_dev_node::<
+							rococo_parachain_runtime::RuntimeApi,
+							RococoParachainRuntimeExecutor,
+						>(config, id, sealing)
+						.await
+						.map(|r| r.0)
+						.map_err(Into::into)
+					}
+				}
+
 				let polkadot_cli = RelayChainCli::new(
 					&config,
 					[RelayChainCli::executable_name().to_string()]
@@ -466,8 +510,6 @@ pub fn run() -> Result<()> {
 						.chain(cli.relaychain_args.iter()),
 				);
 
-				let id = ParaId::from(para_id);
-
 				let parachain_account =
 					AccountIdConversion::<polkadot_primitives::v0::AccountId>::into_account(&id);
 
@@ -489,11 +531,14 @@ pub fn run() -> Result<()> {
 				info!("Parachain genesis state: {}", genesis_state);
 				info!("Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
+				let full_node_relay_chain_rpc_url = cli.run.full_node_relay_chain_rpc_url.clone();
+				let pov_recovery = cli.run.pov_recovery.config();
+
 				if config.chain_spec.is_statemint() {
 					crate::service::start_statemint_node::<
 						statemint_runtime::RuntimeApi,
 						StatemintRuntimeExecutor,
-					>(config, polkadot_config, id)
+					>(config, polkadot_config, id, full_node_relay_chain_rpc_url, pov_recovery)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
@@ -501,7 +546,7 @@ pub fn run() -> Result<()> {
 					crate::service::start_statemint_node::<
 						statemine_runtime::RuntimeApi,
 						StatemineRuntimeExecutor,
-					>(config, polkadot_config, id)
+					>(config, polkadot_config, id, full_node_relay_chain_rpc_url, pov_recovery)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
@@ -509,7 +554,7 @@ pub fn run() -> Result<()> {
 					crate::service::start_statemint_node::<
 						westmint_runtime::RuntimeApi,
 						WestmintRuntimeExecutor,
-					>(config, polkadot_config, id)
+					>(config, polkadot_config, id, full_node_relay_chain_rpc_url, pov_recovery)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
@@ -517,7 +562,7 @@ pub fn run() -> Result<()> {
 					crate::service::start_shell_node::<
 						shell_runtime::RuntimeApi,
 						ShellRuntimeExecutor,
-					>(config, polkadot_config, id)
+					>(config, polkadot_config, id, full_node_relay_chain_rpc_url, pov_recovery)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
@@ -525,15 +570,21 @@ pub fn run() -> Result<()> {
 					crate::service::start_shell_node::<
 						seedling_runtime::RuntimeApi,
 						SeedlingRuntimeExecutor,
-					>(config, polkadot_config, id)
+					>(config, polkadot_config, id, full_node_relay_chain_rpc_url, pov_recovery)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
 				} else {
-					crate::service::start_rococo_parachain_node(config, polkadot_config, id)
-						.await
-						.map(|r| r.0)
-						.map_err(Into::into)
+					crate::service::start_rococo_parachain_node(
+						config,
+						polkadot_config,
+						id,
+						full_node_relay_chain_rpc_url,
+						pov_recovery,
+					)
+					.await
+					.map(|r| r.0)
+					.map_err(Into::into)
 				}
 			})
 		},
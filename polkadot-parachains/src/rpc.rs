@@ -20,14 +20,18 @@
 
 use std::sync::Arc;
 
+use cumulus_client_dev::MessageInjector;
+use cumulus_primitives_core::ParaId;
+use futures::channel::mpsc;
 use sc_client_api::AuxStore;
+use sc_consensus_manual_seal::EngineCommand;
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
-use parachains_common::{AccountId, Balance, Block, Index as Nonce};
+use parachains_common::{AccountId, Balance, Block, Hash, Index as Nonce};
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
@@ -54,9 +58,15 @@ where
 		+ 'static,
 	C::Api: frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: cumulus_pallet_xcmp_queue_rpc::XcmpQueueRuntimeApi<Block>,
+	C::Api: cumulus_pallet_dmp_queue_rpc::DmpQueueRuntimeApi<Block>,
+	C::Api: cumulus_pallet_parachain_system_rpc::ParachainUpgradeRuntimeApi<Block>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + Sync + Send + 'static,
 {
+	use cumulus_pallet_dmp_queue_rpc::{DmpQueue, DmpQueueApi};
+	use cumulus_pallet_parachain_system_rpc::{ParachainUpgrade, ParachainUpgradeApi};
+	use cumulus_pallet_xcmp_queue_rpc::{XcmpQueue, XcmpQueueApi};
 	use frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 
@@ -65,6 +75,52 @@ where
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
+	io.extend_with(XcmpQueueApi::to_delegate(XcmpQueue::new(client.clone())));
+	io.extend_with(DmpQueueApi::to_delegate(DmpQueue::new(client.clone())));
+	io.extend_with(ParachainUpgradeApi::to_delegate(ParachainUpgrade::new(client.clone())));
+
+	io
+}
+
+/// Dependencies of the RPCs that are only available in development mode.
+pub struct DevDeps {
+	/// The parachain id of the node.
+	pub para_id: ParaId,
+	/// Channel for sending commands to the manual seal authorship task.
+	pub command_sink: mpsc::Sender<EngineCommand<Hash>>,
+	/// Queue of the messages to inject into the next block.
+	pub message_injector: MessageInjector,
+}
+
+/// Instantiate all RPC extensions of a node running in development mode.
+///
+/// These are the RPC extensions of [`create_full`] plus the manual seal and message injection
+/// RPCs.
+pub fn create_dev<C, P>(deps: FullDeps<C, P>, dev_deps: DevDeps) -> RpcExtension
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ AuxStore
+		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: cumulus_pallet_xcmp_queue_rpc::XcmpQueueRuntimeApi<Block>,
+	C::Api: cumulus_pallet_dmp_queue_rpc::DmpQueueRuntimeApi<Block>,
+	C::Api: cumulus_pallet_parachain_system_rpc::ParachainUpgradeRuntimeApi<Block>,
+	C::Api: BlockBuilder<Block>,
+	P: TransactionPool + Sync + Send + 'static,
+{
+	use cumulus_client_dev::rpc::{DevApi, DevRpc};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApi};
+
+	let mut io = create_full(deps);
+	let DevDeps { para_id, command_sink, message_injector } = dev_deps;
+
+	io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	io.extend_with(DevApi::to_delegate(DevRpc::new(para_id, message_injector)));
 
 	io
 }
@@ -58,7 +58,8 @@ pub use parachains_common as common;
 use parachains_common::{
 	impls::{AssetsToBlockAuthor, DealWithFees, NonZeroIssuance},
 	opaque, AccountId, AssetId, AuraId, Balance, BlockNumber, Hash, Header, Index, Signature,
-	AVERAGE_ON_INITIALIZE_RATIO, HOURS, MAXIMUM_BLOCK_WEIGHT, NORMAL_DISPATCH_RATIO, SLOT_DURATION,
+	AVERAGE_ON_INITIALIZE_RATIO, DAYS, HOURS, MAXIMUM_BLOCK_WEIGHT, NORMAL_DISPATCH_RATIO,
+	SLOT_DURATION,
 };
 
 #[cfg(any(feature = "std", test))]
@@ -408,6 +409,9 @@ impl pallet_proxy::Config for Runtime {
 parameter_types! {
 	pub const ReservedXcmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
 	pub const ReservedDmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
+	pub const MaxPendingUpwardMessages: u32 = 1_000;
+	pub const UpgradeAuthorizationPeriod: BlockNumber = 7 * DAYS;
+	pub const RelayParentHistoryLength: u32 = 16;
 }
 
 impl cumulus_pallet_parachain_system::Config for Runtime {
@@ -419,6 +423,12 @@ impl cumulus_pallet_parachain_system::Config for Runtime {
 	type OutboundXcmpMessageSource = XcmpQueue;
 	type XcmpMessageHandler = XcmpQueue;
 	type ReservedXcmpWeight = ReservedXcmpWeight;
+	type MaxPendingUpwardMessages = MaxPendingUpwardMessages;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = UpgradeAuthorizationPeriod;
+	type RelayParentHistoryLength = RelayParentHistoryLength;
+	type WeightInfo = cumulus_pallet_parachain_system::weights::SubstrateWeight<Runtime>;
 }
 
 impl parachain_info::Config for Runtime {}
@@ -590,18 +600,42 @@ impl cumulus_pallet_xcm::Config for Runtime {
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 }
 
+parameter_types! {
+	/// Trapping the assets of a discarded overweight message writes to the asset trap and, for
+	/// teleports, to the checking account.
+	pub TrapAssetsWeight: Weight = RocksDbWeight::get().reads_writes(2, 2) + UnitWeightCost::get();
+	pub const MaxDiscard: u32 = 100;
+}
+
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ChannelInfo = ParachainSystem;
 	type VersionWrapper = PolkadotXcm;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type BlobHandler = ();
+	type WeightInfo = cumulus_pallet_xcmp_queue::weights::SubstrateWeight<Runtime>;
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = EnsureRoot<AccountId>;
+	type AssetTrap = PolkadotXcm;
+	type IsReserve = <XcmConfig as Config>::IsReserve;
+	type IsTeleporter = <XcmConfig as Config>::IsTeleporter;
+	type AssetTransactor = <XcmConfig as Config>::AssetTransactor;
+	type TrapAssetsWeight = TrapAssetsWeight;
+	type MaxDiscard = MaxDiscard;
+	type WeightInfo = cumulus_pallet_dmp_queue::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -724,7 +758,7 @@ construct_runtime!(
 		XcmpQueue: cumulus_pallet_xcmp_queue::{Pallet, Call, Storage, Event<T>} = 30,
 		PolkadotXcm: pallet_xcm::{Pallet, Call, Storage, Event<T>, Origin} = 31,
 		CumulusXcm: cumulus_pallet_xcm::{Pallet, Event<T>, Origin} = 32,
-		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Storage, Event<T>} = 33,
+		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Config, Storage, Event<T>} = 33,
 
 		// Handy utilities.
 		Utility: pallet_utility::{Pallet, Call, Event} = 40,
@@ -887,6 +921,42 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_primitives_core::RelayStorageKeysApi<Block> for Runtime {
+		fn additional_relay_storage_keys() -> Vec<Vec<u8>> {
+			ParachainSystem::additional_relay_storage_keys()
+		}
+	}
+
+	impl cumulus_pallet_xcmp_queue_rpc_runtime_api::XcmpQueueApi<Block> for Runtime {
+		fn inbound_channels() -> Vec<cumulus_pallet_xcmp_queue::InboundChannelInfo> {
+			XcmpQueue::inbound_channels()
+		}
+
+		fn outbound_channels() -> Vec<cumulus_pallet_xcmp_queue::OutboundChannelInfo> {
+			XcmpQueue::outbound_channels()
+		}
+
+		fn overweight_messages() -> Vec<cumulus_pallet_xcmp_queue::OverweightMessageInfo> {
+			XcmpQueue::overweight_messages()
+		}
+	}
+
+	impl cumulus_pallet_dmp_queue_rpc_runtime_api::DmpQueueApi<Block> for Runtime {
+		fn queue_info() -> cumulus_pallet_dmp_queue::QueueInfo {
+			DmpQueue::queue_info()
+		}
+
+		fn overweight_indices() -> Vec<cumulus_pallet_dmp_queue::OverweightIndex> {
+			DmpQueue::overweight_indices()
+		}
+	}
+
+	impl cumulus_pallet_parachain_system_rpc_runtime_api::ParachainUpgradeApi<Block> for Runtime {
+		fn upgrade_status() -> cumulus_pallet_parachain_system::UpgradeStatus<<Block as BlockT>::Hash> {
+			ParachainSystem::upgrade_status()
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade() -> (Weight, Weight) {
@@ -923,6 +993,9 @@ impl_runtime_apis! {
 			list_benchmark!(list, extra, pallet_utility, Utility);
 			list_benchmark!(list, extra, pallet_timestamp, Timestamp);
 			list_benchmark!(list, extra, pallet_collator_selection, CollatorSelection);
+			list_benchmark!(list, extra, cumulus_pallet_parachain_system, ParachainSystem);
+			list_benchmark!(list, extra, cumulus_pallet_xcmp_queue, XcmpQueue);
+			list_benchmark!(list, extra, cumulus_pallet_dmp_queue, DmpQueue);
 
 			let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -966,6 +1039,9 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, pallet_utility, Utility);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
 			add_benchmark!(params, batches, pallet_collator_selection, CollatorSelection);
+			add_benchmark!(params, batches, cumulus_pallet_parachain_system, ParachainSystem);
+			add_benchmark!(params, batches, cumulus_pallet_xcmp_queue, XcmpQueue);
+			add_benchmark!(params, batches, cumulus_pallet_dmp_queue, DmpQueue);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)
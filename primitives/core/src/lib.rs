@@ -255,4 +255,11 @@ sp_api::decl_runtime_apis! {
 		/// we are collecting the collation info for.
 		fn collect_collation_info(header: &Block::Header) -> CollationInfo;
 	}
+
+	/// Runtime api to query the relay chain storage the runtime wants to read.
+	pub trait RelayStorageKeysApi {
+		/// The relay chain storage keys that should be put into the relay chain state proof of
+		/// the next block, in addition to the keys that are always proven.
+		fn additional_relay_storage_keys() -> Vec<Vec<u8>>;
+	}
 }
@@ -20,19 +20,23 @@ use crate::ParachainInherentData;
 use codec::Decode;
 use cumulus_primitives_core::{
 	relay_chain::{self, v1::HrmpChannelId, Hash as PHash},
-	ParaId, PersistedValidationData,
+	ParaId, PersistedValidationData, RelayStorageKeysApi,
 };
 use cumulus_relay_chain_interface::RelayChainInterface;
-use sp_runtime::generic::BlockId;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 
 const LOG_TARGET: &str = "parachain-inherent";
 
 /// Collect the relevant relay chain state in form of a proof for putting it into the validation
 /// data inherent.
+///
+/// The `additional_keys` are proven next to the keys that are always required by the runtime.
 fn collect_relay_storage_proof(
 	relay_chain_interface: &impl RelayChainInterface,
 	para_id: ParaId,
 	relay_parent: PHash,
+	additional_keys: &[Vec<u8>],
 ) -> Option<sp_state_machine::StorageProof> {
 	use relay_chain::well_known_keys as relay_well_known_keys;
 
@@ -107,6 +111,11 @@ fn collect_relay_storage_proof(
 	relevant_keys.extend(egress_channels.into_iter().map(|recipient| {
 		relay_well_known_keys::hrmp_channels(HrmpChannelId { sender: para_id, recipient })
 	}));
+	for key in additional_keys {
+		if !relevant_keys.contains(key) {
+			relevant_keys.push(key.clone());
+		}
+	}
 
 	relay_chain_interface.prove_read(&relay_parent_block_id, &relevant_keys).ok()?
 }
@@ -121,8 +130,34 @@ impl ParachainInherentData {
 		validation_data: &PersistedValidationData,
 		para_id: ParaId,
 	) -> Option<ParachainInherentData> {
-		let relay_chain_state =
-			collect_relay_storage_proof(relay_chain_interface, para_id, relay_parent)?;
+		Self::create_at_with_additional_keys(
+			relay_parent,
+			relay_chain_interface,
+			validation_data,
+			para_id,
+			&[],
+		)
+	}
+
+	/// Create the [`ParachainInherentData`] at the given `relay_parent`, additionally proving the
+	/// given relay chain storage keys in the relay chain state proof.
+	///
+	/// The keys are usually obtained through [`additional_relay_storage_keys`].
+	///
+	/// Returns `None` if the creation failed.
+	pub fn create_at_with_additional_keys(
+		relay_parent: PHash,
+		relay_chain_interface: &impl RelayChainInterface,
+		validation_data: &PersistedValidationData,
+		para_id: ParaId,
+		additional_keys: &[Vec<u8>],
+	) -> Option<ParachainInherentData> {
+		let relay_chain_state = collect_relay_storage_proof(
+			relay_chain_interface,
+			para_id,
+			relay_parent,
+			additional_keys,
+		)?;
 
 		let downward_messages =
 			relay_chain_interface.retrieve_dmq_contents(para_id, relay_parent)?;
@@ -138,6 +173,42 @@ impl ParachainInherentData {
 	}
 }
 
+/// Query the relay chain storage keys that the runtime at `parent` wants to have proven in the
+/// relay chain state proof of its child block.
+///
+/// Returns no keys if the runtime doesn't implement [`RelayStorageKeysApi`] or the call fails.
+pub fn additional_relay_storage_keys<B, C>(client: &C, parent: B::Hash) -> Vec<Vec<u8>>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: RelayStorageKeysApi<B>,
+{
+	let at = BlockId::Hash(parent);
+	let runtime_api = client.runtime_api();
+
+	match runtime_api.has_api::<dyn RelayStorageKeysApi<B>>(&at) {
+		Ok(true) => (),
+		Ok(false) => return Vec::new(),
+		Err(e) => {
+			tracing::error!(
+				target: LOG_TARGET,
+				error = ?e,
+				"Failed to check for `RelayStorageKeysApi`.",
+			);
+			return Vec::new()
+		},
+	}
+
+	runtime_api.additional_relay_storage_keys(&at).unwrap_or_else(|e| {
+		tracing::error!(
+			target: LOG_TARGET,
+			error = ?e,
+			"Failed to fetch the additional relay chain storage keys.",
+		);
+		Vec::new()
+	})
+}
+
 #[async_trait::async_trait]
 impl sp_inherents::InherentDataProvider for ParachainInherentData {
 	fn provide_inherent_data(
@@ -20,7 +20,7 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::Encode;
-use cumulus_primitives_core::UpwardMessageSender;
+use cumulus_primitives_core::{MessageSendError, UpwardMessageSender};
 use sp_std::marker::PhantomData;
 use xcm::{latest::prelude::*, WrapVersion};
 
@@ -28,9 +28,8 @@ use xcm::{latest::prelude::*, WrapVersion};
 /// the given UMP `UpwardMessageSender` implementation. Thus this essentially adapts an
 /// `UpwardMessageSender` trait impl into a `SendXcm` trait impl.
 ///
-/// NOTE: This is a pretty dumb "just send it" router; we will probably want to introduce queuing
-/// to UMP eventually and when we do, the pallet which implements the queuing will be responsible
-/// for the `SendXcm` implementation.
+/// Messages which are too big fail with `SendError::ExceedsMaxMessageSize`, and messages which
+/// cannot be queued, e.g. because the queue is full, with `SendError::Transport`.
 pub struct ParentAsUmp<T, W>(PhantomData<(T, W)>);
 impl<T: UpwardMessageSender, W: WrapVersion> SendXcm for ParentAsUmp<T, W> {
 	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> Result<(), SendError> {
@@ -42,7 +41,10 @@ impl<T: UpwardMessageSender, W: WrapVersion> SendXcm for ParentAsUmp<T, W> {
 				W::wrap_version(&dest, msg).map_err(|()| SendError::DestinationUnsupported)?;
 			let data = versioned_xcm.encode();
 
-			T::send_upward_message(data).map_err(|e| SendError::Transport(e.into()))?;
+			T::send_upward_message(data).map_err(|e| match e {
+				MessageSendError::TooBig => SendError::ExceedsMaxMessageSize,
+				e => SendError::Transport(e.into()),
+			})?;
 
 			Ok(())
 		} else {
@@ -21,6 +21,9 @@ pallets=(
 	pallet_timestamp
 	pallet_utility
     pallet_uniques
+	cumulus_pallet_parachain_system
+	cumulus_pallet_xcmp_queue
+	cumulus_pallet_dmp_queue
 )
 
 for p in ${pallets[@]}
@@ -17,7 +17,7 @@
 use cumulus_primitives_core::{
 	relay_chain, AbridgedHostConfiguration, AbridgedHrmpChannel, ParaId,
 };
-use polkadot_primitives::v1::UpgradeGoAhead;
+use polkadot_primitives::v1::{UpgradeGoAhead, UpgradeRestriction};
 use sp_runtime::traits::HashFor;
 use sp_state_machine::MemoryDB;
 use sp_std::collections::btree_map::BTreeMap;
@@ -38,11 +38,14 @@ pub struct RelayStateSproofBuilder {
 	pub host_config: AbridgedHostConfiguration,
 	pub dmq_mqc_head: Option<relay_chain::Hash>,
 	pub upgrade_go_ahead: Option<UpgradeGoAhead>,
+	pub upgrade_restriction: Option<UpgradeRestriction>,
 	pub relay_dispatch_queue_size: Option<(u32, u32)>,
 	pub hrmp_ingress_channel_index: Option<Vec<ParaId>>,
 	pub hrmp_egress_channel_index: Option<Vec<ParaId>>,
 	pub hrmp_channels: BTreeMap<relay_chain::v1::HrmpChannelId, AbridgedHrmpChannel>,
 	pub current_slot: relay_chain::v1::Slot,
+	/// Additional raw key-value pairs that are put into the storage proof.
+	pub additional_key_values: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl Default for RelayStateSproofBuilder {
@@ -62,11 +65,13 @@ impl Default for RelayStateSproofBuilder {
 			},
 			dmq_mqc_head: None,
 			upgrade_go_ahead: None,
+			upgrade_restriction: None,
 			relay_dispatch_queue_size: None,
 			hrmp_ingress_channel_index: None,
 			hrmp_egress_channel_index: None,
 			hrmp_channels: BTreeMap::new(),
 			current_slot: 0.into(),
+			additional_key_values: Vec::new(),
 		}
 	}
 }
@@ -130,6 +135,12 @@ impl RelayStateSproofBuilder {
 					upgrade_go_ahead.encode(),
 				);
 			}
+			if let Some(upgrade_restriction) = self.upgrade_restriction {
+				insert(
+					relay_chain::well_known_keys::upgrade_restriction_signal(self.para_id),
+					upgrade_restriction.encode(),
+				);
+			}
 			if let Some(hrmp_ingress_channel_index) = self.hrmp_ingress_channel_index {
 				let mut sorted = hrmp_ingress_channel_index.clone();
 				sorted.sort();
@@ -155,6 +166,10 @@ impl RelayStateSproofBuilder {
 			}
 
 			insert(relay_chain::well_known_keys::CURRENT_SLOT.to_vec(), self.current_slot.encode());
+
+			for (key, value) in self.additional_key_values {
+				insert(key, value);
+			}
 		}
 
 		let root = backend.root().clone();
@@ -265,6 +265,12 @@ impl cumulus_pallet_parachain_system::Config for Runtime {
 	type ReservedDmpWeight = ();
 	type XcmpMessageHandler = ();
 	type ReservedXcmpWeight = ();
+	type MaxPendingUpwardMessages = frame_support::traits::ConstU32<1_000>;
+	type AdditionalRelayStorageKeys = ();
+	type TrackedSiblingParas = ();
+	type UpgradeAuthorizationPeriod = frame_support::traits::ConstU32<{ 7 * DAYS }>;
+	type RelayParentHistoryLength = frame_support::traits::ConstU32<16>;
+	type WeightInfo = ();
 }
 
 parameter_types! {
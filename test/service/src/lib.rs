@@ -326,6 +326,7 @@ where
 			collator_key,
 			import_queue,
 			slot_duration: Duration::from_secs(6),
+			prometheus_registry: prometheus_registry.as_ref(),
 		};
 
 		start_collator(params).await?;
@@ -336,6 +337,9 @@ where
 			task_manager: &mut task_manager,
 			para_id,
 			relay_chain_interface,
+			import_queue,
+			pov_recovery: None,
+			prometheus_registry: prometheus_registry.as_ref(),
 		};
 
 		start_full_node(params)?;